@@ -1,11 +1,12 @@
-use xcb::ModMask;
 
 use crate::types::{
-    Direction::*, Keybind,
+    Direction::*, Keybind, ActivationPolicy, FocusStealingPrevention, NetWindowState,
     SHIFT, CTRL, ModKey, modkey,
     keysym,
 };
 use crate::utils;
+use crate::x::XConn;
+use crate::x::core::{ModMask, Timestamp};
 
 pub const MODKEY: ModMask = modkey(ModKey::Meta);
 pub const BORDER_WIDTH: u32 = 2;
@@ -14,7 +15,30 @@ pub const BORDER_FOCUSED: u32 = 0xdddddd;
 pub const BORDER_UNFOCUSED: u32 = 0x555555;
 pub const BORDER_URGENT: u32 = 0xff0000;
 
-pub const KEYBINDS: &[Keybind] = &[
+/// How to respond to applications asking for their windows to be activated,
+/// e.g. a browser opening a link from another program.
+/// Also applies to clients that do not indicate where the request came from.
+pub const APPLICATION_ACTIVATION: ActivationPolicy = ActivationPolicy::Focus;
+/// How to respond to pagers and taskbars activating windows.
+pub const PAGER_ACTIVATION: ActivationPolicy = ActivationPolicy::Focus;
+
+/// Whether newly mapped windows may take the focus from the window
+/// the user is working in. Windows that are kept from it are marked urgent.
+pub const FOCUS_STEALING_PREVENTION: FocusStealingPrevention = FocusStealingPrevention::Smart;
+
+/// How far an arrow key moves or resizes a window
+/// during a keyboard move or resize, in pixels.
+pub const KEYBOARD_DRAG_STEP: i32 = 10;
+
+/// How long a client has to answer a _NET_WM_PING, in milliseconds,
+/// before it is marked as not responding.
+pub const PING_TIMEOUT: Timestamp = 5000;
+
+/// The names of the workspaces, which also sets their number.
+pub const WORKSPACE_NAMES: [&str; 10] = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"];
+
+/// Returns the keybinds used by the window manager.
+pub fn keybinds<X: XConn>() -> Vec<Keybind<X>> { vec![
     (MODKEY, keysym::XK_1, |wm| {wm.goto_workspace(0);}),
     (MODKEY, keysym::XK_2, |wm| {wm.goto_workspace(1);}),
     (MODKEY, keysym::XK_3, |wm| {wm.goto_workspace(2);}),
@@ -28,6 +52,9 @@ pub const KEYBINDS: &[Keybind] = &[
     (MODKEY|SHIFT, keysym::XK_5, |wm| {wm.send_window_to(4)}),
 
     (MODKEY, keysym::XK_t, |wm| {wm.toggle_focused_state()}),
+    (MODKEY, keysym::XK_f, |wm| {wm.toggle_focused(NetWindowState::Fullscreen)}),
+    (MODKEY, keysym::XK_a, |wm| {wm.toggle_focused(NetWindowState::Above)}),
+    (MODKEY, keysym::XK_s, |wm| {wm.toggle_focused(NetWindowState::Sticky)}),
 
     (MODKEY, keysym::XK_Return, |_| {utils::run_external(&["alacritty"])}),
     (MODKEY, keysym::XK_r, |_| {utils::run_external(&["dmenu_run", "-b"])}),
@@ -44,4 +71,4 @@ pub const KEYBINDS: &[Keybind] = &[
     (MODKEY, keysym::XK_w, utils::close_window),
 
     (MODKEY|SHIFT, keysym::XK_q, |wm| {wm.quit()}),
-];
\ No newline at end of file
+]}
\ No newline at end of file
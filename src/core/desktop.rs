@@ -5,29 +5,47 @@
 //! windows between workspaces.
 //! 
 //! `Screen` represents a physical monitor that X is connected to.
-//! It encapsulates monitor resolution and is used by the tiling
-//! algorithms to resize windows.
+//! It encapsulates monitor resolution and the space reserved by docks,
+//! and is used by the tiling algorithms to resize windows.
+//! 
+//! `ClientList` keeps track of every managed client across all
+//! workspaces, for the EWMH client list properties.
 
 use crate::workspace::Workspace;
-use crate::x::core::{XWindowID, XCBConnection};
+use crate::window::Client;
+use crate::x::core::{XWindowID, XConn};
 use crate::layout::LayoutType;
-use crate::types::{Ring, Direction};
+use crate::types::{Ring, Direction, Geometry, NetWindowState, StackLayer, Strut, Result, WMError};
+use crate::utils;
+use crate::config::WORKSPACE_NAMES;
 use crate::x::XWindow;
 
-const MAX_WKSPACES: usize = 10;
-
 /// The main `Desktop` used by `WindowManager`.
 #[derive(Clone, Default)]
 pub struct Desktop {
     pub(crate) workspaces: Ring<Workspace>,
+    pub(crate) clients: ClientList,
     current: usize,
 }
 
+/// Every client managed by the window manager, in mapping
+/// and in stacking order.
+/// 
+/// Used to set _NET_CLIENT_LIST and _NET_CLIENT_LIST_STACKING.
+#[derive(Clone, Default, Debug)]
+pub struct ClientList {
+    mapped: Vec<XWindowID>,
+    stacking: Vec<XWindowID>,
+    changed: bool,
+}
+
 /// Represents a physical monitor.
 #[derive(Clone, Copy)]
 pub struct Screen {
     pub xwindow: XWindow,
     pub idx: i32,
+    /// The part of the screen not reserved by docks, if any are.
+    workarea: Option<Geometry>,
 }
 
 impl Screen {
@@ -35,8 +53,102 @@ impl Screen {
         Self {
             xwindow: XWindow::from(root_id),
             idx: screen_idx,
+            workarea: None,
         }
     }
+
+    /// The part of the screen that windows are laid out in.
+    pub fn workarea(&self) -> Geometry {
+        self.workarea.unwrap_or(self.xwindow.geom)
+    }
+
+    /// Reserves the space taken up by docks.
+    /// 
+    /// Returns whether the work area changed.
+    pub fn set_struts<'a, I>(&mut self, struts: I) -> bool
+    where
+        I: IntoIterator<Item = &'a Strut>
+    {
+        let geom = self.xwindow.geom;
+        // the screen covers the whole root window
+        let workarea = struts.into_iter()
+            .fold(geom, |area, strut| strut.apply(area, geom));
+        let workarea = if workarea == geom {None} else {Some(workarea)};
+
+        let changed = workarea != self.workarea;
+        self.workarea = workarea;
+        changed
+    }
+}
+
+impl ClientList {
+    /// Adds a newly mapped client, on top of the stack.
+    pub fn insert(&mut self, window: XWindowID) {
+        if self.contains(window) {
+            return
+        }
+        self.mapped.push(window);
+        self.stacking.push(window);
+        self.changed = true;
+    }
+
+    /// Removes a client that is no longer managed.
+    pub fn remove(&mut self, window: XWindowID) {
+        if !self.contains(window) {
+            return
+        }
+        self.mapped.retain(|id| *id != window);
+        self.stacking.retain(|id| *id != window);
+        self.changed = true;
+    }
+
+    /// Moves a client to the top of the stack.
+    pub fn raise(&mut self, window: XWindowID) {
+        if !self.contains(window) || self.stacking.last() == Some(&window) {
+            return
+        }
+        self.stacking.retain(|id| *id != window);
+        self.stacking.push(window);
+        self.changed = true;
+    }
+
+    /// Moves a client to the bottom of the stack.
+    pub fn lower(&mut self, window: XWindowID) {
+        if !self.contains(window) || self.stacking.first() == Some(&window) {
+            return
+        }
+        self.stacking.retain(|id| *id != window);
+        self.stacking.insert(0, window);
+        self.changed = true;
+    }
+
+    pub fn contains(&self, window: XWindowID) -> bool {
+        self.mapped.contains(&window)
+    }
+
+    /// Clients in the order they were mapped.
+    pub fn mapping_order(&self) -> &[XWindowID] {
+        &self.mapped
+    }
+
+    /// Clients from bottom to top.
+    pub fn stacking_order(&self) -> &[XWindowID] {
+        &self.stacking
+    }
+
+    /// Replaces the stacking order with a reordering of the same clients.
+    pub(crate) fn set_stacking_order(&mut self, stacking: Vec<XWindowID>) {
+        debug_assert_eq!(stacking.len(), self.stacking.len());
+        if stacking != self.stacking {
+            self.stacking = stacking;
+            self.changed = true;
+        }
+    }
+
+    /// Whether the list changed since the last call.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
 }
 
 impl Desktop {
@@ -44,15 +156,18 @@ impl Desktop {
     pub fn new(layout: LayoutType) -> Self {
         Self {
             workspaces: {
-                let mut workspaces = Ring::with_capacity(MAX_WKSPACES);
+                let mut workspaces = Ring::with_capacity(WORKSPACE_NAMES.len());
 
-                for _ in 0..MAX_WKSPACES {
-                    workspaces.push(Workspace::with_layout(layout));
+                for name in WORKSPACE_NAMES.iter() {
+                    let mut ws = Workspace::with_layout(layout);
+                    ws.name = name.to_string();
+                    workspaces.append(ws);
                 }
 
                 workspaces.set_focused(0);
                 workspaces
             },
+            clients: ClientList::default(),
             current: 0,
         }
     }
@@ -67,8 +182,28 @@ impl Desktop {
         &mut self.workspaces[self.current]
     }
 
+    /// The index of the current workspace.
+    pub fn current_idx(&self) -> usize {
+        self.current
+    }
+
+    /// The number of workspaces.
+    pub fn workspace_count(&self) -> usize {
+        self.workspaces.len()
+    }
+
+    /// The names of all workspaces, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.workspaces.iter().map(|ws| ws.name()).collect()
+    }
+
+    /// Get the index of the workspace the window is on.
+    pub fn workspace_of(&self, window: XWindowID) -> Option<usize> {
+        self.workspaces.iter().position(|ws| ws.contains(window).is_some())
+    }
+
     /// Get the workspace the window is on and its index inside it.
-    pub fn retrieve(&mut self, window: XWindowID) -> Option<(&Workspace, usize)> {
+    pub fn retrieve(&self, window: XWindowID) -> Option<(&Workspace, usize)> {
         for ws in self.workspaces.iter() {
             if let Some(idx) = ws.contains(window) {
                 return Some((ws, idx))
@@ -78,6 +213,21 @@ impl Desktop {
         None
     }
 
+    /// Get a managed client on any workspace.
+    pub fn client(&self, window: XWindowID) -> Option<&Client> {
+        self.retrieve(window).map(|(ws, idx)| &ws[idx])
+    }
+
+    /// `Desktop::client`, mutable edition.
+    pub fn client_mut(&mut self, window: XWindowID) -> Option<&mut Client> {
+        self.retrieve_mut(window).map(|(ws, idx)| &mut ws[idx])
+    }
+
+    /// Iterates over the managed clients on every workspace.
+    pub fn clients_mut(&mut self) -> impl Iterator<Item = &mut Client> {
+        self.workspaces.iter_mut().flat_map(|ws| ws.windows.iter_mut())
+    }
+
     /// `Desktop::retrieve`, mutable edition.
     pub fn retrieve_mut(&mut self, window: XWindowID) -> Option<(&mut Workspace, usize)> {
         for ws in self.workspaces.iter_mut() {
@@ -89,17 +239,18 @@ impl Desktop {
         None
     }
 
-    pub fn cycle_workspace(&mut self, 
-        conn: &XCBConnection, 
+    pub fn cycle_workspace<X: XConn>(&mut self, 
+        conn: &X, 
         scr: &Screen, 
         direction: Direction
-    ) {
+    ) -> Result<()> {
         debug!("Cycling workspaces in direction {:?}", direction);
         self.workspaces.cycle_focus(direction);
         if let Some(i) = self.workspaces.focused_idx() {
-            self.goto(conn, scr, i);
+            self.goto(conn, scr, i)
         } else {
             error!("Focused should be Some");
+            Ok(())
         }
     }
 
@@ -122,32 +273,123 @@ impl Desktop {
     }
 
     /// Switch to a given workspace.
-    pub fn goto(&mut self, conn: &XCBConnection, scr: &Screen, idx: usize) {
+    pub fn goto<X: XConn>(&mut self, conn: &X, scr: &Screen, idx: usize) -> Result<()> {
         if self.current == idx {
-            return
+            return Ok(())
+        }
+        if idx >= self.workspaces.len() {
+            return Err(WMError::NoSuchWorkspace(idx))
         }
         debug!("Goto desktop {}", idx);
 
-        self.workspaces.get_mut(self.current).unwrap().deactivate(conn);
+        // sticky windows come along, so take them out before they are unmapped
+        let mut sticky = Vec::new();
+        loop {
+            let win_idx = self.current().windows.iter().position(|win| win.is_sticky());
+            match win_idx {
+                Some(win_idx) => sticky.push(self.current_mut().take_window(win_idx)),
+                None => break,
+            }
+        }
+
+        self.current_mut().deactivate(conn);
         
         self.current = idx;
+        // keep the ring in step, so that cycling starts from here
+        self.workspaces.set_focused(idx);
+        conn.set_current_desktop(scr.idx, idx as u32);
 
-        if let Some(ws) = self.get_mut(self.current) {
-            ws.activate(conn, scr);
-        } else {
-            error!("No workspace found for index {}", idx);
+        for window in sticky {
+            debug!("Bringing sticky window {} to workspace {}", window.id(), idx);
+            self.current_mut().push_window(window);
         }
+
+        self.current_mut().activate(conn, scr)
+    }
+
+    /// Keeps clients in their stacking layers.
+    /// 
+    /// If any client is out of place in the stacking order, every client
+    /// is restacked on the X server, from bottom to top.
+    pub fn restack<X: XConn>(&mut self, conn: &X) {
+        let mut layered: Vec<(StackLayer, XWindowID)> = self.clients.stacking_order().iter()
+            .map(|&id| (self.client(id).map(|win| win.layer()).unwrap_or_default(), id))
+            .collect();
+
+        if layered.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+            return
+        }
+        // stable, so the order within each layer is kept
+        layered.sort_by_key(|(layer, _)| *layer);
+        debug!("Restacking clients by layer: {:?}", layered);
+
+        let stacking: Vec<XWindowID> = layered.into_iter().map(|(_, id)| id).collect();
+        for &id in &stacking {
+            conn.configure_window(id, &utils::stack_above());
+        }
+        self.clients.set_stacking_order(stacking);
     }
 
     /// Send a window to a given workspace.
-    pub fn send_window_to(&mut self, conn: &XCBConnection, scr: &Screen, idx: usize) {
+    pub fn send_window_to<X: XConn>(&mut self, conn: &X, scr: &Screen, idx: usize) -> Result<()> {
         debug!("Attempting to send window to workspace {}", idx);
-        if let Some(window) = self.current_mut().take_focused_window(conn, scr) {
+        if idx >= self.workspaces.len() {
+            return Err(WMError::NoSuchWorkspace(idx))
+        }
+        if let Some(mut window) = self.current_mut().take_focused_window(conn, scr)? {
             debug!("Sending window {} to workspace {}", window.id(), idx);
+            // the window now belongs to a single workspace
+            if window.remove_wm_state(NetWindowState::Sticky) {
+                window.set_wm_states(conn);
+            }
+            conn.set_wm_desktop(window.id(), idx as u32);
             self.workspaces[idx].push_window(window);
         } else {
             debug!("No focused window for workspace {}", idx);
         }
-        self.current_mut().relayout(conn, scr);
+        self.current_mut().relayout(conn, scr)
+    }
+
+    /// Send a specific window to a given workspace.
+    /// 
+    /// The window is shown or hidden as necessary.
+    pub fn move_window<X: XConn>(&mut self, 
+        conn: &X, 
+        scr: &Screen, 
+        window: XWindowID, 
+        idx: usize
+    ) -> Result<()> {
+        if idx >= self.workspaces.len() {
+            return Err(WMError::NoSuchWorkspace(idx))
+        }
+        let from = self.workspace_of(window).ok_or(WMError::NoSuchWindow(window))?;
+
+        // the window now belongs to a single workspace
+        let client = self.client_mut(window).unwrap();
+        if client.remove_wm_state(NetWindowState::Sticky) {
+            client.set_wm_states(conn);
+            conn.set_wm_desktop(window, from as u32);
+        }
+
+        if from == idx {
+            return Ok(())
+        }
+        debug!("Moving window {} from workspace {} to {}", window, from, idx);
+
+        let win_idx = self.workspaces[from].contains(window).unwrap();
+        let client = if from == self.current {
+            self.workspaces[from].del_window(conn, scr, window, win_idx)?
+        } else {
+            self.workspaces[from].take_window(win_idx)
+        };
+
+        conn.set_wm_desktop(window, idx as u32);
+        self.workspaces[idx].push_window(client);
+
+        if idx == self.current {
+            self.current_mut().activate(conn, scr)?;
+        }
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
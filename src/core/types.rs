@@ -1,13 +1,13 @@
 //! Various data types and definitions for use within RaccoonWM.
-use xcb_util::icccm::{self, WmState};
-use xcb::xproto;
-
 use thiserror::Error;
+use serde::{Serialize, Deserialize};
 
 use std::ops::Deref;
 
 use crate::layout::LayoutType;
-use crate::x::{XCBConnection, Atom, XWindowID};
+use crate::x::{XConn, XError, Atom, XWindowID};
+use crate::x::core::{ModMask, KeySym, InternedAtoms};
+use crate::x::consts;
 use crate::WindowManager;
 
 pub use crate::core::{Ring, Selector};
@@ -18,7 +18,7 @@ pub mod keysym {
 }
 
 /// Encapsulates a Keybind: The modifier, the actual key, and the callback to run.
-pub type Keybind = (xcb::ModMask, xcb::Keysym, fn(&mut WindowManager));
+pub type Keybind<X> = (ModMask, KeySym, fn(&mut WindowManager<X>));
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ModKey {
@@ -27,23 +27,40 @@ pub enum ModKey {
 }
 
 /// The Shift keymask.
-pub const SHIFT: xcb::ModMask = xproto::MOD_MASK_SHIFT;
+pub const SHIFT: ModMask = consts::MOD_MASK_SHIFT;
 /// The Ctrl keymask.
-pub const CTRL: xcb::ModMask = xproto::MOD_MASK_CONTROL;
+pub const CTRL: ModMask = consts::MOD_MASK_CONTROL;
 
 /// Sets the Modkey to be used.
-pub const fn modkey(modkey: ModKey) -> xcb::ModMask {
+pub const fn modkey(modkey: ModKey) -> ModMask {
     use ModKey::*;
     match modkey {
-        Meta => xproto::MOD_MASK_4,
-        Alt  => xproto::MOD_MASK_1,
+        Meta => consts::MOD_MASK_4,
+        Alt  => consts::MOD_MASK_1,
     }
 }
-#[derive(Debug, Error, Clone, Copy)]
+/// Errors that can occur while managing windows.
+#[derive(Debug, Error, Clone)]
 pub enum WMError {
-    
+    /// A request to the X server failed.
+    #[error(transparent)]
+    XError(#[from] XError),
+    /// The window is not tracked by the window manager.
+    #[error("Window {0} is not tracked.")]
+    NoSuchWindow(XWindowID),
+    /// The workspace index is out of range.
+    #[error("Workspace {0} does not exist.")]
+    NoSuchWorkspace(usize),
+    /// Another client has already selected substructure redirect on the root.
+    #[error("Another window manager is running.")]
+    OtherWMRunning,
+    /// The configured layout is not supported yet.
+    #[error("Layout {0:?} is not supported.")]
+    UnsupportedLayout(LayoutType),
 }
 
+pub type Result<T> = ::core::result::Result<T, WMError>;
+
 #[derive(Debug, Clone, Copy)]
 pub struct ResizeAction {
     pub window: XWindowID,
@@ -59,15 +76,49 @@ pub enum Direction {
     Backward,
 }
 
+/// The edges of a window that follow the pointer during an interactive resize.
+/// 
+/// Along each axis, -1 moves the left or top edge, 1 moves the right
+/// or bottom edge, and 0 keeps the size of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeEdges {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ResizeEdges {
+    /// The bottom right corner, used by Mod+drag and keyboard resizes.
+    pub const BOTTOM_RIGHT: Self = Self {x: 1, y: 1};
+
+    /// The edges named by a _NET_WM_MOVERESIZE direction.
+    pub fn from_moveresize(direction: u32) -> Option<Self> {
+        use consts::*;
+
+        let (x, y) = match direction {
+            NET_WM_MOVERESIZE_SIZE_TOPLEFT => (-1, -1),
+            NET_WM_MOVERESIZE_SIZE_TOP => (0, -1),
+            NET_WM_MOVERESIZE_SIZE_TOPRIGHT => (1, -1),
+            NET_WM_MOVERESIZE_SIZE_RIGHT => (1, 0),
+            NET_WM_MOVERESIZE_SIZE_BOTTOMRIGHT => (1, 1),
+            NET_WM_MOVERESIZE_SIZE_BOTTOM => (0, 1),
+            NET_WM_MOVERESIZE_SIZE_BOTTOMLEFT => (-1, 1),
+            NET_WM_MOVERESIZE_SIZE_LEFT => (-1, 0),
+            NET_WM_MOVERESIZE_SIZE_KEYBOARD => (1, 1),
+            _ => return None,
+        };
+        Some(Self {x, y})
+    }
+}
+
 /// A specific point on a window.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
 /// The Geometry of a given window.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Geometry {
     pub x: i32,
     pub y: i32,
@@ -98,28 +149,44 @@ impl Default for Geometry {
 }
 
 /// The ICCCM-defined window states.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WindowState {
+    #[default]
     Normal,
     Withdrawn,
     Iconic,
 }
 
-impl From<WmState> for WindowState {
-    fn from(from: WmState) -> Self {
+impl From<u32> for WindowState {
+    fn from(from: u32) -> Self {
+        match from {
+            consts::WM_STATE_NORMAL => Self::Normal,
+            consts::WM_STATE_WITHDRAWN => Self::Withdrawn,
+            consts::WM_STATE_ICONIC => Self::Iconic,
+            unknown => {
+                warn!("Unknown WM_STATE value {}", unknown);
+                Self::default()
+            }
+        }
+    }
+}
+
+impl From<WindowState> for u32 {
+    fn from(from: WindowState) -> Self {
         match from {
-            icccm::WM_STATE_NORMAL => Self::Normal,
-            icccm::WM_STATE_WITHDRAWN => Self::Withdrawn,
-            icccm::WM_STATE_ICONIC => Self::Iconic,
-            _ => unreachable!("Unknown state")
+            WindowState::Normal => consts::WM_STATE_NORMAL,
+            WindowState::Withdrawn => consts::WM_STATE_WITHDRAWN,
+            WindowState::Iconic => consts::WM_STATE_ICONIC,
         }
     }
 }
 
-/// Convenience wrapper around a Vec of NetWindowStates.
-#[derive(Debug, Clone)]
+/// The set of EWMH states a window is in.
+/// 
+/// Stored in the order the states were added.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct NetWindowStates {
-    states: Vec<Atom>,
+    states: Vec<NetWindowState>,
 }
 
 impl NetWindowStates {
@@ -129,44 +196,69 @@ impl NetWindowStates {
         }
     }
 
-    pub fn contains(&self, prop: Atom) -> bool {
-        self.states.contains(&prop)
+    /// Converts the atoms of a _NET_WM_STATE property,
+    /// skipping any states that are not known.
+    pub fn from_atoms(atoms: &[Atom], interned: &InternedAtoms) -> Self {
+        Self {
+            states: atoms.iter()
+                .filter_map(|atom| NetWindowState::from_atom(*atom, interned))
+                .fold(Vec::new(), |mut states, state| {
+                    if !states.contains(&state) {
+                        states.push(state);
+                    }
+                    states
+                })
+        }
     }
 
-    pub fn add(&mut self, prop: Atom) {
-        self.states.push(prop)
+    /// Converts the states to atoms, for setting _NET_WM_STATE.
+    pub fn to_atoms(&self, interned: &InternedAtoms) -> Vec<Atom> {
+        self.states.iter().map(|state| state.to_atom(interned)).collect()
     }
 
-    pub fn remove(&mut self, prop: Atom) -> Atom {
-        for (idx, atom) in self.states.iter().enumerate() {
-            if *atom == prop {
-                return self.states.remove(idx)
-            }
+    pub fn contains(&self, state: NetWindowState) -> bool {
+        self.states.contains(&state)
+    }
+
+    /// Adds a state, returning false if it was already present.
+    pub fn add(&mut self, state: NetWindowState) -> bool {
+        if self.contains(state) {
+            return false
+        }
+        self.states.push(state);
+        true
+    }
+
+    /// Removes a state, returning false if it was not present.
+    pub fn remove(&mut self, state: NetWindowState) -> bool {
+        if !self.contains(state) {
+            return false
         }
-        error!("Tried to remove atom not in states");
-        0
+        self.states.retain(|s| *s != state);
+        true
     }
 }
 
-impl From<Vec<Atom>> for NetWindowStates {
-    fn from(from: Vec<Atom>) -> Self {
-        Self {
-            states: from
+impl From<Vec<NetWindowState>> for NetWindowStates {
+    fn from(from: Vec<NetWindowState>) -> Self {
+        let mut states = Self::new();
+        for state in from {
+            states.add(state);
         }
+        states
     }
 }
 
 impl Deref for NetWindowStates {
-    type Target = [Atom];
+    type Target = [NetWindowState];
 
     fn deref(&self) -> &Self::Target {
         self.states.as_slice()
     }
 }
 
-//? This thing's existence is in question.
-/// EWMH-defined window states.
-#[derive(Clone, Copy, Debug)]
+/// EWMH-defined window states, as set in _NET_WM_STATE.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NetWindowState {
     Modal,
     Sticky,
@@ -183,36 +275,86 @@ pub enum NetWindowState {
 }
 
 impl NetWindowState {
-    pub fn from_atom(atom: Atom, conn: &XCBConnection) -> Option<Self> {
-        let raw = conn.get_raw();
-        if atom == raw.WM_STATE_MODAL() {
-            return Some(Self::Modal)
-        } else if atom == raw.WM_STATE_STICKY() {
-            return Some(Self::Sticky)
-        } else if atom == raw.WM_STATE_MAXIMIZED_VERT() {
-            return Some(Self::MaxVert)
-        } else if atom == raw.WM_STATE_MAXIMIZED_HORZ() {
-            return Some(Self::MaxHorz)
-        } else if atom == raw.WM_STATE_SHADED() {
-            return Some(Self::Shaded)
-        } else if atom == raw.WM_STATE_SKIP_TASKBAR() {
-            return Some(Self::SkipTaskbar)
-        } else if atom == raw.WM_STATE_SKIP_PAGER() {
-            return Some(Self::SkipPager)
-        } else if atom == raw.WM_STATE_HIDDEN() {
-            return Some(Self::Hidden)
+    pub fn from_atom(atom: Atom, atoms: &InternedAtoms) -> Option<Self> {
+        if atom == atoms.WM_STATE_MODAL {
+            Some(Self::Modal)
+        } else if atom == atoms.WM_STATE_STICKY {
+            Some(Self::Sticky)
+        } else if atom == atoms.WM_STATE_MAXIMIZED_VERT {
+            Some(Self::MaxVert)
+        } else if atom == atoms.WM_STATE_MAXIMIZED_HORZ {
+            Some(Self::MaxHorz)
+        } else if atom == atoms.WM_STATE_SHADED {
+            Some(Self::Shaded)
+        } else if atom == atoms.WM_STATE_SKIP_TASKBAR {
+            Some(Self::SkipTaskbar)
+        } else if atom == atoms.WM_STATE_SKIP_PAGER {
+            Some(Self::SkipPager)
+        } else if atom == atoms.WM_STATE_HIDDEN {
+            Some(Self::Hidden)
+        } else if atom == atoms.WM_STATE_FULLSCREEN {
+            Some(Self::Fullscreen)
+        } else if atom == atoms.WM_STATE_ABOVE {
+            Some(Self::Above)
+        } else if atom == atoms.WM_STATE_BELOW {
+            Some(Self::Below)
+        } else if atom == atoms.WM_STATE_DEMANDS_ATTENTION {
+            Some(Self::Urgent)
         } else {
-            return None
+            None
+        }
+    }
+
+    pub fn to_atom(self, atoms: &InternedAtoms) -> Atom {
+        use NetWindowState::*;
+
+        match self {
+            Modal => atoms.WM_STATE_MODAL,
+            Sticky => atoms.WM_STATE_STICKY,
+            MaxVert => atoms.WM_STATE_MAXIMIZED_VERT,
+            MaxHorz => atoms.WM_STATE_MAXIMIZED_HORZ,
+            Shaded => atoms.WM_STATE_SHADED,
+            SkipTaskbar => atoms.WM_STATE_SKIP_TASKBAR,
+            SkipPager => atoms.WM_STATE_SKIP_PAGER,
+            Hidden => atoms.WM_STATE_HIDDEN,
+            Fullscreen => atoms.WM_STATE_FULLSCREEN,
+            Above => atoms.WM_STATE_ABOVE,
+            Below => atoms.WM_STATE_BELOW,
+            Urgent => atoms.WM_STATE_DEMANDS_ATTENTION,
         }
     }
 }
 
-impl Default for WindowState {
-    fn default() -> Self {
-        Self::Normal
+/// How a _NET_WM_STATE request changes a state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateChange {
+    Remove,
+    Add,
+    Toggle,
+}
+
+impl StateChange {
+    /// Gets the change from the action field of a _NET_WM_STATE client message.
+    pub fn from_action(action: u32) -> Option<Self> {
+        match action {
+            consts::NET_WM_STATE_REMOVE => Some(Self::Remove),
+            consts::NET_WM_STATE_ADD => Some(Self::Add),
+            consts::NET_WM_STATE_TOGGLE => Some(Self::Toggle),
+            _ => None,
+        }
     }
 }
 
+/// The stacking layers windows are kept in, from bottom to top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StackLayer {
+    Below,
+    #[default]
+    Normal,
+    Above,
+    Fullscreen,
+}
+
 /// Used internally to track whether a window is floating or tiled.
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum WinLayoutState {
@@ -232,6 +374,219 @@ impl From<LayoutType> for WinLayoutState {
     }
 }
 
+/// Where an EWMH request came from, as indicated by the client sending it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SourceIndication {
+    /// A client that predates source indications.
+    Legacy,
+    /// A normal application.
+    Application,
+    /// A pager, taskbar or other tool acting on behalf of the user.
+    Pager,
+}
+
+impl From<u32> for SourceIndication {
+    fn from(from: u32) -> Self {
+        match from {
+            consts::SOURCE_APPLICATION => Self::Application,
+            consts::SOURCE_PAGER => Self::Pager,
+            consts::SOURCE_LEGACY => Self::Legacy,
+            unknown => {
+                warn!("Unknown source indication {}", unknown);
+                Self::Legacy
+            }
+        }
+    }
+}
+
+/// How to respond to a request to activate a window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActivationPolicy {
+    /// Switch to the window's workspace, then focus and raise it.
+    Focus,
+    /// Set _NET_WM_STATE_DEMANDS_ATTENTION on the window instead.
+    MarkUrgent,
+    /// Ignore the request.
+    Ignore,
+}
+
+/// How strictly newly mapped windows are kept from taking the focus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusStealingPrevention {
+    /// New windows always take the focus.
+    Off,
+    /// New windows take the focus unless their _NET_WM_USER_TIME shows
+    /// that the user has interacted with the focused window since.
+    /// Windows that do not set it take the focus.
+    Smart,
+    /// Like `Smart`, but windows that do not set _NET_WM_USER_TIME
+    /// never take the focus.
+    Strict,
+}
+
+/// An application icon, as set in _NET_WM_ICON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Icon {
+    pub width: u32,
+    pub height: u32,
+    /// The pixels as 32-bit ARGB, row by row from the top left.
+    pub data: Vec<u32>,
+}
+
+impl Icon {
+    /// Decodes every icon in a _NET_WM_ICON, which holds
+    /// each icon as its width and height followed by its pixels.
+    /// 
+    /// Decoding stops at the first malformed icon.
+    pub fn decode_all(mut values: &[u32]) -> Vec<Self> {
+        let mut icons = Vec::new();
+
+        while let [width, height, rest @ ..] = values {
+            let len = match (*width as usize).checked_mul(*height as usize) {
+                Some(len) if len > 0 && len <= rest.len() => len,
+                _ => break,
+            };
+            icons.push(Self {
+                width: *width,
+                height: *height,
+                data: rest[..len].to_vec(),
+            });
+            values = &rest[len..];
+        }
+
+        icons
+    }
+}
+
+/// The ICCCM input models, which decide how a window is given the focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputModel {
+    /// The window never takes the focus.
+    NoInput,
+    /// The window is given the focus with SetInputFocus.
+    Passive,
+    /// The window is given the focus with SetInputFocus,
+    /// and is sent WM_TAKE_FOCUS.
+    LocallyActive,
+    /// The window is sent WM_TAKE_FOCUS, and sets the focus itself.
+    GloballyActive,
+}
+
+impl InputModel {
+    /// Works out the input model from the input field of WM_HINTS,
+    /// and whether WM_PROTOCOLS contains WM_TAKE_FOCUS.
+    pub fn new(input: bool, take_focus: bool) -> Self {
+        match (input, take_focus) {
+            (false, false) => Self::NoInput,
+            (true, false) => Self::Passive,
+            (true, true) => Self::LocallyActive,
+            (false, true) => Self::GloballyActive,
+        }
+    }
+
+    /// Whether the window manager sets the focus to the window.
+    pub fn sets_focus(&self) -> bool {
+        matches!(self, Self::Passive | Self::LocallyActive)
+    }
+
+    /// Whether the window is sent WM_TAKE_FOCUS.
+    pub fn takes_focus(&self) -> bool {
+        matches!(self, Self::LocallyActive | Self::GloballyActive)
+    }
+}
+
+/// Space reserved by a dock along the edges of the root window,
+/// as set in _NET_WM_STRUT_PARTIAL.
+/// 
+/// Each edge reserves a strip of the given width, spanning
+/// between its start and end coordinates along the edge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Strut {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    pub left_start_y: u32,
+    pub left_end_y: u32,
+    pub right_start_y: u32,
+    pub right_end_y: u32,
+    pub top_start_x: u32,
+    pub top_end_x: u32,
+    pub bottom_start_x: u32,
+    pub bottom_end_x: u32,
+}
+
+impl Strut {
+    /// Creates a strut from a _NET_WM_STRUT, which spans each edge entirely.
+    pub fn from_edges(left: u32, right: u32, top: u32, bottom: u32) -> Self {
+        Self {
+            left, right, top, bottom,
+            left_end_y: u32::MAX,
+            right_end_y: u32::MAX,
+            top_end_x: u32::MAX,
+            bottom_end_x: u32::MAX,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a strut from the twelve values of _NET_WM_STRUT_PARTIAL.
+    pub fn from_partial(values: &[u32]) -> Option<Self> {
+        if values.len() < 12 {
+            return None
+        }
+        Some(Self {
+            left: values[0],
+            right: values[1],
+            top: values[2],
+            bottom: values[3],
+            left_start_y: values[4],
+            left_end_y: values[5],
+            right_start_y: values[6],
+            right_end_y: values[7],
+            top_start_x: values[8],
+            top_end_x: values[9],
+            bottom_start_x: values[10],
+            bottom_end_x: values[11],
+        })
+    }
+
+    /// Shrinks `area` to leave out the space reserved by the strut.
+    /// 
+    /// Struts are relative to the root window, with geometry `root`,
+    /// and only reserve space on `area` where they overlap with it.
+    pub fn apply(&self, area: Geometry, root: Geometry) -> Geometry {
+        // whether the span of an edge overlaps with [from, to)
+        fn overlaps(start: u32, end: u32, from: i32, to: i32) -> bool {
+            (start as i64) < to as i64 && (end as i64) >= from as i64
+        }
+
+        let mut left = area.x;
+        let mut right = area.x + area.width;
+        let mut top = area.y;
+        let mut bottom = area.y + area.height;
+
+        if self.left > 0 && overlaps(self.left_start_y, self.left_end_y, area.y, area.y + area.height) {
+            left = left.max(root.x + self.left as i32);
+        }
+        if self.right > 0 && overlaps(self.right_start_y, self.right_end_y, area.y, area.y + area.height) {
+            right = right.min(root.x + root.width - self.right as i32);
+        }
+        if self.top > 0 && overlaps(self.top_start_x, self.top_end_x, area.x, area.x + area.width) {
+            top = top.max(root.y + self.top as i32);
+        }
+        if self.bottom > 0 && overlaps(self.bottom_start_x, self.bottom_end_x, area.x, area.x + area.width) {
+            bottom = bottom.min(root.y + root.height - self.bottom as i32);
+        }
+
+        Geometry {
+            x: left,
+            y: top,
+            width: (right - left).max(0),
+            height: (bottom - top).max(0),
+        }
+    }
+}
+
 /// The style for the window border.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BorderStyle {
@@ -256,39 +611,40 @@ pub enum WindowType {
 }
 
 impl WindowType {
-    /// Gets the atom type from an atom and an XCBConnection.
-    pub fn from_atom(atom: Atom, conn: &XCBConnection) -> Option<Self> {
+    /// Gets the atom type from an atom and a connection.
+    pub fn from_atom<X: XConn>(atom: Atom, conn: &X) -> Option<Self> {
         use WindowType::*;
 
-        if atom == conn.atoms.WM_WINDOW_TYPE_DESKTOP {
-            return Some(Desktop)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_DOCK {
-            return Some(Dock)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_TOOLBAR {
-            return Some(Toolbar)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_MENU {
-            return Some(Menu)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_UTILITY {
-            return Some(Utility)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_SPLASH {
-            return Some(Splash)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_DIALOG {
-            return Some(Dialog)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_DROPDOWN_MENU {
-            return Some(Dropdown)
-        } else if atom == conn.atoms.WM_WINDOW_TYPE_NOTIFICATION {
-            return Some(Notification)
-        }else if atom == conn.atoms.WM_WINDOW_TYPE_NORMAL {
-            return Some(Normal)
+        let atoms = conn.get_atoms();
+
+        if atom == atoms.WM_WINDOW_TYPE_DESKTOP {
+            Some(Desktop)
+        } else if atom == atoms.WM_WINDOW_TYPE_DOCK {
+            Some(Dock)
+        } else if atom == atoms.WM_WINDOW_TYPE_TOOLBAR {
+            Some(Toolbar)
+        } else if atom == atoms.WM_WINDOW_TYPE_MENU {
+            Some(Menu)
+        } else if atom == atoms.WM_WINDOW_TYPE_UTILITY {
+            Some(Utility)
+        } else if atom == atoms.WM_WINDOW_TYPE_SPLASH {
+            Some(Splash)
+        } else if atom == atoms.WM_WINDOW_TYPE_DIALOG {
+            Some(Dialog)
+        } else if atom == atoms.WM_WINDOW_TYPE_DROPDOWN_MENU {
+            Some(Dropdown)
+        } else if atom == atoms.WM_WINDOW_TYPE_NOTIFICATION {
+            Some(Notification)
+        }else if atom == atoms.WM_WINDOW_TYPE_NORMAL {
+            Some(Normal)
         } else {
             None
         }
     }
 }
 
-//todo
 /// ICCCM-defined window size hints.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SizeHints {
     pub position: Option<(i32, i32)>,
     pub size: Option<(i32, i32)>,
@@ -301,21 +657,116 @@ pub struct SizeHints {
     pub gravity: Option<u32>
 }
 
+impl SizeHints {
+    /// Whether the hints fix the size of the window,
+    /// with a minimum size that is the same as the maximum.
+    pub fn is_fixed(&self) -> bool {
+        self.min_size.is_some() && self.min_size == self.max_size
+    }
+
+    /// Adjusts a size to follow the hints, as laid out in ICCCM section 4.1.2.3.
+    /// 
+    /// The size is kept within the minimum and maximum sizes and
+    /// aspect ratios, and rounded down to a whole number of resize
+    /// increments above the base size.
+    pub fn constrain(&self, width: i32, height: i32) -> (i32, i32) {
+        let (base_w, base_h) = self.base.or(self.min_size).unwrap_or((0, 0));
+        let (min_w, min_h) = self.min_size.or(self.base).unwrap_or((1, 1));
+        let (mut w, mut h) = (width, height);
+
+        // a base size is left out of the aspect ratio,
+        // but the minimum size standing in for it is not
+        let has_base = self.base.is_some();
+        if has_base {
+            w -= base_w;
+            h -= base_h;
+        }
+        if let (Some((min_x, min_y)), Some((max_x, max_y))) = (self.min_aspect, self.max_aspect) {
+            if min_x > 0 && min_y > 0 && max_x > 0 && max_y > 0 && w > 0 && h > 0 {
+                let min_aspect = min_x as f64 / min_y as f64;
+                let max_aspect = max_x as f64 / max_y as f64;
+                let aspect = w as f64 / h as f64;
+
+                if aspect > max_aspect {
+                    w = (h as f64 * max_aspect).round() as i32;
+                } else if aspect < min_aspect {
+                    h = (w as f64 / min_aspect).round() as i32;
+                }
+            }
+        }
+        if !has_base {
+            w -= base_w;
+            h -= base_h;
+        }
+
+        if let Some((inc_w, inc_h)) = self.resize {
+            if inc_w > 0 {
+                w -= w.rem_euclid(inc_w);
+            }
+            if inc_h > 0 {
+                h -= h.rem_euclid(inc_h);
+            }
+        }
+
+        w = (w + base_w).max(min_w);
+        h = (h + base_h).max(min_h);
+
+        if let Some((max_w, max_h)) = self.max_size {
+            if max_w > 0 {
+                w = w.min(max_w);
+            }
+            if max_h > 0 {
+                h = h.min(max_h);
+            }
+        }
+
+        (w.max(1), h.max(1))
+    }
+
+    /// Constrains the size of `area` with the hints, and places
+    /// the result inside it according to `gravity`.
+    /// 
+    /// Used for the window gravity as well as to center windows.
+    pub fn fit(&self, area: Geometry, gravity: u32) -> Geometry {
+        use consts::*;
+
+        let (width, height) = self.constrain(area.width, area.height);
+        let dx = area.width - width;
+        let dy = area.height - height;
+
+        let x = match gravity {
+            GRAVITY_NORTH | GRAVITY_CENTER | GRAVITY_SOUTH => area.x + dx / 2,
+            GRAVITY_NORTH_EAST | GRAVITY_EAST | GRAVITY_SOUTH_EAST => area.x + dx,
+            _ => area.x,
+        };
+        let y = match gravity {
+            GRAVITY_WEST | GRAVITY_CENTER | GRAVITY_EAST => area.y + dy / 2,
+            GRAVITY_SOUTH_WEST | GRAVITY_SOUTH | GRAVITY_SOUTH_EAST => area.y + dy,
+            _ => area.y,
+        };
+
+        Geometry {x, y, width, height}
+    }
+}
+
 /// ICCCM-defined window hints.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct WmHints {
     pub state: WindowState,
     pub urgent: bool,
+    /// Whether the client relies on the window manager to set the input focus.
+    pub input: bool,
     //todo: add pixmaps
 }
 
 /// ICCCM-defined window properties.
 //todo: make all fields private, accessible with methods.
+#[derive(Serialize, Deserialize)]
 pub struct XWinProperties {
     pub(crate) wm_name: String,
     pub(crate) wm_icon_name: String,
-    pub(crate) wm_size_hints: Option<icccm::SizeHints>,
-    pub(crate) wm_hints: Option<icccm::WmHints>,
+    pub(crate) wm_size_hints: Option<SizeHints>,
+    pub(crate) wm_hints: Option<WmHints>,
     pub(crate) wm_class: (String, String), //Instance, Class
     pub(crate) wm_protocols: Option<Vec<Atom>>,
     pub(crate) wm_state: WindowState,
@@ -332,41 +783,11 @@ impl XWinProperties {
 
     #[inline]
     pub fn wm_size_hints(&self) -> Option<SizeHints> {
-        if let Some(hints) = &self.wm_size_hints {
-            Some(SizeHints {
-                position: hints.position(),
-                size: hints.size(),
-                min_size: hints.min_size(),
-                max_size: hints.max_size(),
-                resize: hints.resize(),
-                min_aspect: hints.aspect().map(|(i,_)| i),
-                max_aspect: hints.aspect().map(|(_,i)| i),
-                base: hints.base(),
-                gravity: hints.gravity(),
-            })
-        } else {
-            None
-        }
+        self.wm_size_hints
     }
 
     pub fn wm_hints(&self) -> Option<WmHints> {
-        use WindowState::*;
-        if let Some(hints) = &self.wm_hints {
-            Some(WmHints {
-                state: if hints.is_normal() {
-                    Normal
-                } else if hints.is_iconic() {
-                    Iconic
-                } else if hints.is_withdrawn() {
-                    Withdrawn
-                } else {
-                    Normal
-                },
-                urgent: if let Some(u) = hints.is_urgent() {u} else {false}
-            })
-        } else {
-            None
-        }
+        self.wm_hints
     }
 
     pub fn wm_class(&self) -> (&str, &str) {
@@ -375,7 +796,7 @@ impl XWinProperties {
 
     pub fn window_type(&self) -> Option<&[Atom]> {
         if let Some(prtcls) = &self.wm_protocols {
-            return Some(&prtcls)
+            Some(prtcls)
         } else {
             None
         }
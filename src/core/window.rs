@@ -5,26 +5,36 @@ use std::collections::HashSet;
 
 use crate::x::core::{
     Atom,
-    XCBConnection,
+    XConn,
     XWindow, 
-    XWindowID
+    XWindowID,
+    Timestamp,
 };
-use crate::x::{Icccm, Ewmh};
+use crate::x::consts;
+use crate::x::ConfigureBatch;
 use crate::utils;
 use crate::types::{
     WinLayoutState, 
+    NetWindowState,
     NetWindowStates,
+    StackLayer,
     WindowState,
+    InputModel,
     Geometry, 
     Ring, 
     Selector, 
     BorderStyle,
+    Icon,
+    SizeHints,
+    ResizeEdges,
 };
 use crate::desktop::Screen;
 use crate::config::{
+    BORDER_WIDTH,
     BORDER_FOCUSED,
     BORDER_UNFOCUSED,
     BORDER_URGENT,
+    PING_TIMEOUT,
 };
 
 pub const WIN_HEIGHT_MIN: i32 = 100;
@@ -98,7 +108,7 @@ impl ClientRing {
 
     pub fn is_focused(&self, id: XWindowID) -> bool {
         if let Some(window) = self.focused() {
-            return window.id() == id
+            window.id() == id
         } else {
             false
         }
@@ -114,14 +124,39 @@ pub struct Client {
     pub name: String,
     pub icon_name: String,
     pub class: (String, String),
+    /// The icons set in _NET_WM_ICON, at each size provided.
+    pub icons: Vec<Icon>,
 
     initial_geom: Geometry,
     urgent: bool,
+    /// The input field of WM_HINTS.
+    accepts_input: bool,
+    /// The size hints set in WM_NORMAL_HINTS.
+    size_hints: Option<SizeHints>,
+    /// The size the pointer has resized the window to, before
+    /// the size hints are applied.
+    resize_size: Option<(i32, i32)>,
     transient_for: Option<XWindowID>,
+    /// The ICCCM state last set in WM_STATE.
     mapped_state: WindowState,
+    /// The number of UnmapNotify events caused by the window manager
+    /// unmapping the window, that have not been received yet.
+    expected_unmaps: u32,
     net_states: NetWindowStates,
     layout_state: WinLayoutState,
     protocols: HashSet<Atom>,
+
+    /// The geometry to restore when leaving fullscreen.
+    fullscreen_geom: Option<Geometry>,
+    /// The geometry to restore when no longer maximized.
+    maximize_geom: Option<Geometry>,
+    /// Whether the window was tiled before being made fullscreen or hidden,
+    /// and should be tiled again afterwards.
+    pub(crate) retile: bool,
+    /// The time of the last _NET_WM_PING the client has not answered yet.
+    ping: Option<Timestamp>,
+    /// Whether the client answered its last _NET_WM_PING in time.
+    responding: bool,
 }
 
 impl PartialEq for Client {
@@ -132,15 +167,15 @@ impl PartialEq for Client {
 
 //todo: fix your calculations, they are deeply broken.
 impl Client {
-    pub fn tiled(from: XWindowID, conn: &XCBConnection) -> Self {
+    pub fn tiled<X: XConn>(from: XWindowID, conn: &X) -> Self {
         Self::new(from, conn, WinLayoutState::Tiled)
     }
 
-    pub fn floating(from: XWindowID, conn: &XCBConnection) -> Self {
+    pub fn floating<X: XConn>(from: XWindowID, conn: &X) -> Self {
         Self::new(from, conn, WinLayoutState::Floating)
     }
 
-    fn new(from: XWindowID, conn: &XCBConnection, layout: WinLayoutState) -> Self {
+    fn new<X: XConn>(from: XWindowID, conn: &X, layout: WinLayoutState) -> Self {
         let properties = conn.get_client_properties(from);
         Self {
             xwindow: XWindow::from(from),
@@ -150,14 +185,25 @@ impl Client {
                 let (class1, class2) = properties.wm_class();
                 (class1.into(), class2.into())
             },
+            icons: conn.get_wm_icon(from),
             
             initial_geom: Geometry::default(),
             transient_for: None,
             urgent: false,
-            mapped_state: WindowState::Normal,
+            accepts_input: properties.wm_hints().is_none_or(|hints| hints.input),
+            size_hints: properties.wm_size_hints(),
+            resize_size: None,
+            mapped_state: WindowState::Withdrawn,
+            expected_unmaps: 0,
             net_states: NetWindowStates::new(),
             layout_state: layout,
             protocols: HashSet::new(),
+
+            fullscreen_geom: None,
+            maximize_geom: None,
+            retile: false,
+            ping: None,
+            responding: true,
         }
     }
 
@@ -177,9 +223,39 @@ impl Client {
         false
     }
 
+    /// Whether the window is urgent, either through WM_HINTS
+    /// or _NET_WM_STATE_DEMANDS_ATTENTION.
     #[inline(always)]
     pub fn is_urgent(&self) -> bool {
-        self.urgent
+        self.urgent || self.has_state(NetWindowState::Urgent)
+    }
+
+    #[inline]
+    pub fn is_fullscreen(&self) -> bool {
+        self.has_state(NetWindowState::Fullscreen)
+    }
+
+    #[inline]
+    pub fn is_hidden(&self) -> bool {
+        self.has_state(NetWindowState::Hidden)
+    }
+
+    #[inline]
+    pub fn is_sticky(&self) -> bool {
+        self.has_state(NetWindowState::Sticky)
+    }
+
+    /// The stacking layer the window is kept in.
+    pub fn layer(&self) -> StackLayer {
+        if self.is_fullscreen() {
+            StackLayer::Fullscreen
+        } else if self.has_state(NetWindowState::Above) {
+            StackLayer::Above
+        } else if self.has_state(NetWindowState::Below) {
+            StackLayer::Below
+        } else {
+            StackLayer::Normal
+        }
     }
 
     #[inline]
@@ -228,20 +304,23 @@ impl Client {
         self.xwindow.geom.width
     }
 
-    pub fn update_all_properties(&mut self, conn: &XCBConnection) {
+    pub fn update_all_properties<X: XConn>(&mut self, conn: &X) {
         let properties = conn.get_client_properties(self.id());
         let initial_geom = if let Some(sizes) = properties.wm_size_hints() {
             debug!("Got size hints: {:#?}", sizes);
+            let (width, height) = sizes.size.unwrap_or((160, 100));
+            let (width, height) = sizes.constrain(width, height);
             Geometry {
                 x: if let Some(pos) = sizes.position {pos.0} else {0},
                 y: if let Some(pos) = sizes.position {pos.1} else {0},
-                height: if let Some(dim) = sizes.size {dim.0} else {100},
-                width: if let Some(dim) = sizes.size {dim.1} else {160},
+                width,
+                height,
             }
         } else {
             debug!("initial size is None");
-            Geometry::from((0, 0, 100, 160))
+            Geometry::default()
         };
+        self.size_hints = properties.wm_size_hints();
         self.name = properties.wm_name().into();
         self.icon_name = properties.wm_icon_name().into();
 
@@ -252,16 +331,12 @@ impl Client {
         self.urgent = if let Some(hints) = properties.wm_hints() {
             hints.urgent
         } else {false};
-        self.mapped_state = if let Some(hints) = properties.wm_hints() {
-            hints.state
-        } else {
-            WindowState::Normal
-        };
+        self.accepts_input = properties.wm_hints().is_none_or(|hints| hints.input);
         self.net_states = conn.get_window_states(self.id());
         if self.protocols.is_empty() {
             self.set_supported(conn);
         }
-        if self.urgent {
+        if self.is_urgent() {
             self.set_border(conn, BorderStyle::Urgent);
         }
         debug!("Updated properties: {:#?}", self);
@@ -275,7 +350,7 @@ impl Client {
     /// - WM_ICON_NAME
     /// - WM_CLASS
     /// - WM_HINTS.Urgency
-    pub fn update_dynamic(&mut self, conn: &XCBConnection) {
+    pub fn update_dynamic<X: XConn>(&mut self, conn: &X) {
         self.name = conn.get_wm_name(self.id());
         self.icon_name = conn.get_wm_icon_name(self.id());
         self.class = if let Some(class) = conn.get_wm_class(self.id()) {
@@ -285,7 +360,7 @@ impl Client {
         };
         self.urgent = conn.get_urgency(self.id());
 
-        if self.urgent {
+        if self.is_urgent() {
             self.set_border(conn, BorderStyle::Urgent);
         }
     }
@@ -295,76 +370,175 @@ impl Client {
         self.initial_geom = geom;
     }
 
-    pub fn set_border(&mut self, conn: &XCBConnection, border: BorderStyle) {
+    pub fn set_border<X: XConn>(&mut self, conn: &X, border: BorderStyle) {
         use BorderStyle::*;
 
         match border {
             Focused => {
                 conn.change_window_attributes(
-                    self.id(), &[(xcb::CW_BORDER_PIXEL, BORDER_FOCUSED)]
+                    self.id(), &[(consts::CW_BORDER_PIXEL, BORDER_FOCUSED)]
                 );
             }
             Unfocused => {
                 conn.change_window_attributes(
-                    self.id(), &[(xcb::CW_BORDER_PIXEL, BORDER_UNFOCUSED)]
+                    self.id(), &[(consts::CW_BORDER_PIXEL, BORDER_UNFOCUSED)]
                 );
             }
             Urgent => {
                 conn.change_window_attributes(
-                    self.id(), &[(xcb::CW_BORDER_PIXEL, BORDER_URGENT)]
+                    self.id(), &[(consts::CW_BORDER_PIXEL, BORDER_URGENT)]
                 );
             }
         }
     }
 
-    pub fn map(&mut self, conn: &XCBConnection) {
+    pub fn map<X: XConn>(&mut self, conn: &X) {
         self.update_all_properties(conn);
-        self.update_geometry(conn);
         conn.change_window_attributes(
             self.id(), 
-            &[(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)]
+            &[(consts::CW_EVENT_MASK, consts::EVENT_MASK_PROPERTY_CHANGE)]
         );
+        self.show(conn);
+    }
+
+    /// Maps the window, setting its WM_STATE to Normal.
+    pub fn show<X: XConn>(&mut self, conn: &X) {
         conn.map_window(self.id());
+        self.set_mapped_state(conn, WindowState::Normal);
     }
 
-    pub fn unmap(&mut self, conn: &XCBConnection) {
-        self.mapped_state = WindowState::Withdrawn;
+    /// Unmaps the window, setting its WM_STATE to Iconic.
+    /// 
+    /// The resulting UnmapNotify is expected, so that it is not
+    /// mistaken for the client withdrawing the window.
+    pub fn unmap<X: XConn>(&mut self, conn: &X) {
+        if self.mapped_state == WindowState::Normal {
+            self.expected_unmaps += 1;
+        }
         conn.unmap_window(self.id());
+        self.set_mapped_state(conn, WindowState::Iconic);
+    }
+
+    /// Sets the ICCCM state of the window in WM_STATE.
+    pub fn set_mapped_state<X: XConn>(&mut self, conn: &X, state: WindowState) {
+        self.mapped_state = state;
+        conn.set_icccm_wm_state(self.id(), state);
     }
 
-    pub fn set_wm_states(&self, conn: &XCBConnection) {
+    /// The ICCCM state of the window.
+    pub fn mapped_state(&self) -> WindowState {
+        self.mapped_state
+    }
+
+    /// Whether an UnmapNotify caused by the window manager is yet to be received.
+    pub fn expects_unmap(&self) -> bool {
+        self.expected_unmaps > 0
+    }
+
+    /// Marks an UnmapNotify caused by the window manager as received.
+    pub fn unmap_received(&mut self) {
+        self.expected_unmaps = self.expected_unmaps.saturating_sub(1);
+    }
+
+    pub fn set_wm_states<X: XConn>(&self, conn: &X) {
         conn.set_wm_state(self.id(), &self.net_states);
     }
 
-    pub(crate) fn add_wm_state(&mut self, state: Atom) {
+    pub fn net_states(&self) -> &NetWindowStates {
+        &self.net_states
+    }
+
+    pub fn has_state(&self, state: NetWindowState) -> bool {
+        self.net_states.contains(state)
+    }
+
+    /// Returns false if the window already has the state.
+    pub(crate) fn add_wm_state(&mut self, state: NetWindowState) -> bool {
         self.net_states.add(state)
     }
 
-    pub(crate) fn remove_wm_state(&mut self, state: Atom) {
-        if self.net_states.contains(state) {
-            self.net_states.remove(state);
+    /// Returns false if the window does not have the state.
+    pub(crate) fn remove_wm_state(&mut self, state: NetWindowState) -> bool {
+        self.net_states.remove(state)
+    }
+
+    /// Clears and returns the states of the window.
+    pub(crate) fn take_wm_states(&mut self) -> NetWindowStates {
+        std::mem::take(&mut self.net_states)
+    }
+
+    /// Makes the window cover the screen, without a border.
+    /// 
+    /// The current geometry is saved, to be restored by `Client::unset_fullscreen`.
+    pub(crate) fn set_fullscreen<X: XConn>(&mut self, conn: &X, screen: &Screen) {
+        if self.fullscreen_geom.is_none() {
+            self.fullscreen_geom = Some(self.xwindow.geom);
+        }
+        self.configure(conn, &[(consts::CONFIG_WINDOW_BORDER_WIDTH as u16, 0)]);
+        self.set_and_update_geometry(conn, screen.xwindow.geom);
+    }
+
+    /// Restores the border and geometry the window had before it was made fullscreen.
+    pub(crate) fn unset_fullscreen<X: XConn>(&mut self, conn: &X) {
+        self.configure(conn, &[(consts::CONFIG_WINDOW_BORDER_WIDTH as u16, BORDER_WIDTH)]);
+        if let Some(geom) = self.fullscreen_geom.take() {
+            self.set_and_update_geometry(conn, geom);
+        }
+    }
+
+    /// Stretches the window across the given area, according to its
+    /// _NET_WM_STATE_MAXIMIZED_VERT and _NET_WM_STATE_MAXIMIZED_HORZ states.
+    /// 
+    /// Dimensions that are no longer maximized are restored to what they
+    /// were before the window was first maximized.
+    pub(crate) fn update_maximized<X: XConn>(&mut self, conn: &X, area: Geometry) {
+        let vert = self.has_state(NetWindowState::MaxVert);
+        let horz = self.has_state(NetWindowState::MaxHorz);
+
+        let saved = match self.maximize_geom {
+            Some(geom) => geom,
+            None if vert || horz => self.xwindow.geom,
+            None => return,
+        };
+        let mut geom = saved;
+
+        if vert {
+            geom.y = area.y;
+            geom.height = area.height - (BORDER_WIDTH as i32 * 2);
         }
+        if horz {
+            geom.x = area.x;
+            geom.width = area.width - (BORDER_WIDTH as i32 * 2);
+        }
+
+        self.maximize_geom = if vert || horz {Some(saved)} else {None};
+        self.set_and_update_geometry(conn, geom);
     }
     
     /// Configure the `Client` using a provided connection
     /// 
     /// Use `Client::set_geometry` and `Client::update_geometry`
     /// to change client geometry instead of this method.
-    pub fn configure(&self, conn: &XCBConnection, attrs: &[(u16, u32)]) {
+    pub fn configure<X: XConn>(&self, conn: &X, attrs: &[(u16, u32)]) {
         conn.configure_window(self.id(), attrs);
     }
 
     /// Change client attributes.
-    pub fn change_attributes(&self, conn: &XCBConnection, attrs: &[(u32, u32)]) {
+    pub fn change_attributes<X: XConn>(&self, conn: &X, attrs: &[(u32, u32)]) {
         conn.change_window_attributes(self.id(), attrs)
     }
 
-    /// Resize the window using _changes_ in height and width.
+    /// Resize the window by moving `edges` by `dx` and `dy`.
     /// 
-    /// Does not do bounds checking.
-    pub fn do_resize(&mut self, conn: &XCBConnection, _scr: &Screen, dx: i32, dy: i32) {
-        self.xwindow.update_height(dy);
-        self.xwindow.update_width(dx);
+    /// Follows the size hints of the window, but does not do bounds checking.
+    pub fn do_resize<X: XConn>(&mut self, 
+        conn: &X, 
+        _scr: &Screen, 
+        edges: ResizeEdges, 
+        dx: i32, 
+        dy: i32
+    ) {
+        self.resize_by(edges, dx, dy);
 
         // let scrx = scr.xwindow.geom.x;
         // let scry = scr.xwindow.geom.y;
@@ -377,10 +551,8 @@ impl Client {
         // ensure_in_bounds(&mut self.xwindow.geom.width, 
         //     WIN_WIDTH_MIN, scrx + scrw - self.xwindow.geom.x);
 
-        conn.configure_window(self.xwindow.id, &utils::configure_resize(
-            self.width() as u32, 
-            self.height() as u32
-        ));
+        // moving the left or top edge moves the window as well
+        self.update_geometry(conn);
 
         // debug!(
         //     "Updated geometry:\nx: {}, y: {}, h: {}, w: {}", 
@@ -388,10 +560,39 @@ impl Client {
         // );
     }
 
+    /// Changes the size of the window by moving `edges` by `dx` and `dy`,
+    /// following its size hints, but does not update it to the X server.
+    /// 
+    /// The edges opposite the ones moved stay in place. The changes
+    /// are added up until `Client::end_resize`, so that small changes
+    /// are not lost to resize increments.
+    pub fn resize_by(&mut self, edges: ResizeEdges, dx: i32, dy: i32) {
+        let (width, height) = self.resize_size
+            .unwrap_or((self.width(), self.height()));
+        let (width, height) = (width + dx * edges.x, height + dy * edges.y);
+        self.resize_size = Some((width, height));
+
+        let (width, height) = self.constrain_size(width, height);
+        let geom = &mut self.xwindow.geom;
+        if edges.x < 0 {
+            geom.x += geom.width - width;
+        }
+        if edges.y < 0 {
+            geom.y += geom.height - height;
+        }
+        geom.width = width;
+        geom.height = height;
+    }
+
+    /// Ends a resize started with `Client::resize_by`.
+    pub fn end_resize(&mut self) {
+        self.resize_size = None;
+    }
+
     /// Move the window using _changes_ in window coordinates.
     /// 
     /// Does not do bounds checking.
-    pub fn do_move(&mut self, conn: &XCBConnection, _scr: &Screen, dx: i32, dy: i32) {
+    pub fn do_move<X: XConn>(&mut self, conn: &X, _scr: &Screen, dx: i32, dy: i32) {
         self.xwindow.update_pos_y(dy);
         self.xwindow.update_pos_x(dx);
 
@@ -426,26 +627,25 @@ impl Client {
     /// Updates its geometry on the X server.
     /// 
     /// Normally called after `Client::set_geometry`.
-    pub fn update_geometry(&self, conn: &XCBConnection) {
-        conn.configure_window(self.xwindow.id, &utils::configure_resize(
-            self.width() as u32,
-            self.height() as u32,
-        ));
+    pub fn update_geometry<X: XConn>(&self, conn: &X) {
+        conn.configure_window(self.xwindow.id, &utils::configure_geometry(self.xwindow.geom))
+    }
 
-        conn.configure_window(self.xwindow.id, &utils::configure_move(
-            self.x() as u32,
-            self.y() as u32,
-        ))
+    /// Queues the Client geometry to be sent to the X server with a batch.
+    /// 
+    /// Use this instead of `Client::update_geometry` when updating many windows at once.
+    pub fn queue_geometry(&self, batch: &mut ConfigureBatch) {
+        batch.set_geometry(self.xwindow.id, self.xwindow.geom)
     }
 
     /// Updates and sets the Client geometry with a given Geometry.
-    pub fn set_and_update_geometry(&mut self, conn: &XCBConnection, geom: Geometry) {
+    pub fn set_and_update_geometry<X: XConn>(&mut self, conn: &X, geom: Geometry) {
         self.set_geometry(geom);
         self.update_geometry(conn);
     }
 
     /// Sets the supported protocols for the client.
-    pub fn set_supported(&mut self, conn: &XCBConnection) {
+    pub fn set_supported<X: XConn>(&mut self, conn: &X) {
         if let Some(protocols) = conn.get_wm_protocols(self.id()) {
             for protocol in protocols {
                 self.protocols.insert(protocol);
@@ -457,4 +657,126 @@ impl Client {
     pub fn supports(&self, prtcl: Atom) -> bool {
         self.protocols.contains(&prtcl)
     }
+
+    /// The ICCCM input model of the client.
+    pub fn input_model<X: XConn>(&self, conn: &X) -> InputModel {
+        InputModel::new(
+            self.accepts_input,
+            self.supports(conn.get_atoms().WM_TAKE_FOCUS),
+        )
+    }
+
+    /// Gives the input focus to the window, following its input model.
+    /// 
+    /// No Input windows are never given the focus, so it is
+    /// set to the root window instead.
+    pub fn focus<X: XConn>(&self, conn: &X) {
+        let model = self.input_model(conn);
+        debug!("Focusing window {} with input model {:?}", self.id(), model);
+
+        if model.sets_focus() {
+            conn.set_input_focus(self.id());
+        } else if !model.takes_focus() {
+            conn.set_input_focus(conn.get_root_id());
+        }
+        if model.takes_focus() {
+            conn.send_take_focus(self.id());
+        }
+    }
+
+    /// The size hints of the window, from WM_NORMAL_HINTS.
+    pub fn size_hints(&self) -> Option<&SizeHints> {
+        self.size_hints.as_ref()
+    }
+
+    /// Reads the size hints of the window again.
+    pub fn update_size_hints<X: XConn>(&mut self, conn: &X) {
+        self.size_hints = conn.get_wm_size_hints(self.id());
+    }
+
+    /// Whether the size hints fix the size of the window.
+    pub fn is_fixed_size(&self) -> bool {
+        self.size_hints.is_some_and(|hints| hints.is_fixed())
+    }
+
+    /// Adjusts a size to follow the size hints of the window.
+    pub fn constrain_size(&self, width: i32, height: i32) -> (i32, i32) {
+        match self.size_hints {
+            Some(hints) => hints.constrain(width, height),
+            None => (width, height),
+        }
+    }
+
+    /// Fits the window inside `area` following its size hints,
+    /// placing it according to `gravity`.
+    pub fn fit_geometry(&self, area: Geometry, gravity: u32) -> Geometry {
+        match self.size_hints {
+            Some(hints) => hints.fit(area, gravity),
+            None => area,
+        }
+    }
+
+    /// Adjusts a geometry asked for by the client to follow its
+    /// size hints, keeping the point named by its window gravity in place.
+    pub fn requested_geometry(&self, geom: Geometry) -> Geometry {
+        let gravity = self.size_hints
+            .and_then(|hints| hints.gravity)
+            .unwrap_or(consts::GRAVITY_NORTH_WEST);
+        self.fit_geometry(geom, gravity)
+    }
+
+    /// Reads the icons of the window again.
+    pub fn update_icons<X: XConn>(&mut self, conn: &X) {
+        self.icons = conn.get_wm_icon(self.id());
+    }
+
+    /// Returns the smallest icon at least `size` pixels wide and high,
+    /// or the largest icon if none is big enough.
+    pub fn icon(&self, size: u32) -> Option<&Icon> {
+        self.icons.iter()
+            .filter(|icon| icon.width >= size && icon.height >= size)
+            .min_by_key(|icon| icon.width * icon.height)
+            .or_else(|| self.icons.iter().max_by_key(|icon| icon.width * icon.height))
+    }
+
+    /// Whether the client answered its last _NET_WM_PING in time.
+    pub fn is_responding(&self) -> bool {
+        self.responding
+    }
+
+    /// Sends the client a _NET_WM_PING, if it supports it
+    /// and is not already waiting to answer one.
+    pub(crate) fn ping<X: XConn>(&mut self, conn: &X, time: Timestamp) {
+        if !self.supports(conn.get_atoms().WM_PING) || self.ping.is_some() {
+            return
+        }
+        self.ping = Some(time);
+        conn.send_ping(self.id(), time);
+    }
+
+    /// Records the client's answer to the _NET_WM_PING sent at `time`.
+    ///
+    /// Answers to earlier pings are ignored.
+    pub(crate) fn pong(&mut self, time: Timestamp) {
+        if self.ping != Some(time) {
+            debug!("Window {} answered a ping other than the one sent at {:?}", self.id(), self.ping);
+            return
+        }
+        self.ping = None;
+        self.responding = true;
+    }
+
+    /// Marks the client as not responding if it has not answered
+    /// a _NET_WM_PING within `PING_TIMEOUT` of `now`.
+    ///
+    /// Returns true if the client just stopped responding.
+    pub(crate) fn check_ping(&mut self, now: Timestamp) -> bool {
+        match self.ping {
+            Some(sent) if self.responding && now.wrapping_sub(sent) > PING_TIMEOUT => {
+                self.responding = false;
+                true
+            }
+            _ => false,
+        }
+    }
 }
@@ -8,27 +8,22 @@
 use std::ops::{Index, IndexMut};
 
 use crate::window::{Client, ClientRing};
-use crate::types::Direction;
+use crate::types::{Direction, Result, WMError};
 use crate::desktop::Screen;
-use crate::x::core::{XCBConnection, XWindowID};
+use crate::x::core::{XConn, XWindowID};
 use crate::utils;
 
-use crate::layout::{self, *};
+use crate::layout::*;
 
 /// The workspace struct.
+/// 
+/// Layout-specific behaviour is dispatched on the workspace's `LayoutType`.
 #[derive(Clone)]
 pub struct Workspace {
     pub(crate) windows: ClientRing,
     pub(crate) master: Option<XWindowID>,
     pub(crate) layout: LayoutType,
-
-    _activate: fn(&XCBConnection, &mut Workspace, &Screen),
-    _deactivate: fn(&XCBConnection, &mut Workspace),
-    _add_window: fn(&XCBConnection, &mut Workspace, &Screen, XWindowID),
-    _del_window: fn(&XCBConnection, &mut Workspace, &Screen, XWindowID, usize) -> Client,
-    _focus_window: fn(&XCBConnection, &mut Workspace, XWindowID),
-    _relayout: fn(&XCBConnection, &mut Workspace, &Screen),
-    _cycle_focus: fn(&XCBConnection, &mut Workspace, Direction),
+    pub(crate) name: String,
 }
 
 impl Default for Workspace {
@@ -37,14 +32,7 @@ impl Default for Workspace {
             windows: ClientRing::new(),
             master: None,
             layout: LayoutType::DTiled,
-
-            _activate: layout::activate,
-            _deactivate: layout::deactivate,
-            _add_window: dtiled::add_window,
-            _del_window: dtiled::del_window,
-            _focus_window: dtiled::window_focus,
-            _relayout: dtiled::relayout,
-            _cycle_focus: dtiled::cycle_focus,
+            name: String::new(),
         }
     }
 }
@@ -53,31 +41,11 @@ impl Workspace {
     /// Creates a workspace with a given layout.
     pub fn with_layout(layout: LayoutType) -> Self {
         match layout {
-            LayoutType::Floating => Self {
-                windows: ClientRing::new(),
-                master: None,
-                layout: layout,
-    
-                _activate: floating::activate,
-                _deactivate: floating::deactivate,
-                _add_window: floating::add_window,
-                _del_window: floating::del_window,
-                _focus_window: floating::window_focus,
-                _relayout: floating::relayout,
-                _cycle_focus: floating::cycle_focus,
-            },
-            LayoutType::DTiled => Self {
+            LayoutType::Floating | LayoutType::DTiled => Self {
                 windows: ClientRing::new(),
                 master: None,
-                layout: layout,
-
-                _activate: dtiled::activate,
-                _deactivate: dtiled::deactivate,
-                _add_window: dtiled::add_window,
-                _del_window: dtiled::del_window,
-                _focus_window: dtiled::window_focus,
-                _relayout: dtiled::relayout,
-                _cycle_focus: dtiled::cycle_focus,
+                layout,
+                name: String::new(),
             },
             unhandled => {
                 error!("Layout type {:?} not supported", unhandled);
@@ -86,59 +54,91 @@ impl Workspace {
         }
     }
 
+    /// The name of the workspace, as shown by pagers.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Changes a workspace's layout.
-    pub fn set_layout(&mut self, layout: LayoutType, conn: &XCBConnection, scr: &Screen) {
+    pub fn set_layout<X: XConn>(&mut self, 
+        layout: LayoutType, 
+        conn: &X, 
+        scr: &Screen
+    ) -> Result<()> {
         match layout {
             LayoutType::Floating => {
                 self.layout = layout;
                 self.master = None;
-                self._activate = floating::activate;
-                self._deactivate = floating::deactivate;
-                self._add_window = floating::add_window;
-                self._del_window = floating::del_window;
-                self._focus_window = floating::window_focus;
-                self._relayout = floating::relayout;
             }
             LayoutType::DTiled => {
                 self.layout = layout;
-                self._activate = dtiled::activate;
-                self._deactivate = dtiled::deactivate;
-                self._add_window = dtiled::add_window;
-                self._del_window = dtiled::del_window;
-                self._focus_window = dtiled::window_focus;
-                self._relayout = dtiled::relayout;
             }
             unhandled => {
-                error!("Layout type {:?} not supported", unhandled)
+                return Err(WMError::UnsupportedLayout(unhandled))
             }
         }
-        self.relayout(conn, scr);
+        self.relayout(conn, scr)
     }
 
-    pub fn activate(&mut self, conn: &XCBConnection, screen: &Screen) {
-        (self._activate)(conn, self, screen);
+    pub fn activate<X: XConn>(&mut self, conn: &X, screen: &Screen) -> Result<()> {
+        match self.layout {
+            LayoutType::Floating => floating::activate(conn, self, screen),
+            _ => dtiled::activate(conn, self, screen),
+        }
     }
 
-    pub fn deactivate(&mut self, conn: &XCBConnection) {
-        (self._deactivate)(conn, self);
+    pub fn deactivate<X: XConn>(&mut self, conn: &X) {
+        match self.layout {
+            LayoutType::Floating => floating::deactivate(conn, self),
+            _ => dtiled::deactivate(conn, self),
+        }
     }
 
-    pub fn add_window(&mut self, conn: &XCBConnection, screen: &Screen, id: XWindowID) {
-        (self._add_window)(conn, self, screen, id);
+    pub fn add_window<X: XConn>(&mut self, 
+        conn: &X, 
+        screen: &Screen, 
+        id: XWindowID
+    ) -> Result<()> {
+        self.insert_window(conn, screen, id, true)
+    }
+
+    /// Adds a window without giving it the focus.
+    pub fn add_window_unfocused<X: XConn>(&mut self, 
+        conn: &X, 
+        screen: &Screen, 
+        id: XWindowID
+    ) -> Result<()> {
+        self.insert_window(conn, screen, id, false)
+    }
+
+    fn insert_window<X: XConn>(&mut self, 
+        conn: &X, 
+        screen: &Screen, 
+        id: XWindowID,
+        focus: bool,
+    ) -> Result<()> {
+        match self.layout {
+            LayoutType::Floating => floating::add_window(conn, self, screen, id, focus)?,
+            _ => dtiled::add_window(conn, self, screen, id, focus)?,
+        }
         debug!("Current master is {:?}", self.master);
         debug!("{:#?}", &self.windows);
+        Ok(())
     }
 
-    pub fn del_window(&mut self, 
-        conn: &XCBConnection, 
+    pub fn del_window<X: XConn>(&mut self, 
+        conn: &X, 
         screen: &Screen, 
         id: XWindowID, 
         idx: usize
-    ) -> Client {
-        let window = (self._del_window)(conn, self, screen, id, idx);
+    ) -> Result<Client> {
+        let window = match self.layout {
+            LayoutType::Floating => floating::del_window(conn, self, screen, id, idx)?,
+            _ => dtiled::del_window(conn, self, screen, id, idx)?,
+        };
         debug!("Current master is {:?}", self.master);
         debug!("{:#?}", self.windows);
-        window
+        Ok(window)
     }
 
     /// Pushes a window directly to the workspace.
@@ -147,7 +147,7 @@ impl Workspace {
         function_ends!("[start] workspace::push_window");
         if let LayoutType::Floating = self.layout {
             self.windows.push(window);
-        } else if let None = self.master {
+        } else if self.master.is_none() {
             if self.tiled_count() > 0 {
                 warn!("Windows not empty but workspace has no master")
             }
@@ -221,88 +221,144 @@ impl Workspace {
         false
     }
 
-    pub fn toggle_focused_state(&mut self, conn: &XCBConnection, screen: &Screen) {
+    pub fn toggle_focused_state<X: XConn>(&mut self, conn: &X, screen: &Screen) -> Result<()> {
         debug!("Toggling state of focused window {:#?}", self.windows.focused());
-        let master = self.master;
-        // If we have a focused window
-        if let Some(win) = self.windows.focused_mut() {
-            // set a stack variable to avoid overlapping lifetimes
+        if let Some(win) = self.windows.focused() {
             let win_id = win.id();
-            if win.is_floating() { //toggling to tiled
-                debug!("Toggling window state");
-                win.toggle_state();
-                // if we have no master
-                if master.is_none() {
-                    debug!("No master, setting master");
-                    self.set_master(win_id);
-                }
-                // keep floating windows on top
-            } else { //toggling to floating
-                debug!("Toggling window state");
-
-                // toggle state and stack above
-                win.toggle_state();
-                win.configure(conn, &utils::stack_above());
-
-                if self.tiled_count() == 0 && self.master.is_some() {
-                    // if master is the only window
-                    debug!("All windows are floating, unsetting master");
-                    self.unset_master();
-                } else if self.tiled_count() > 0 && master.is_some() {
-                    // if window to toggle is master
-                    if master.unwrap() == win_id {
-                        debug!("Window to toggle is master, setting new master");
-                        // we can get idx 1 and safely unwrap because windows.len() >= 2
-                        let new_master = self.windows.get(1).expect("No window of idx 1").id();
-                        self.set_master(new_master);
-                    }
-                } else {
-                    assert!(master.is_none());
+            self.toggle_window_state(conn, screen, win_id)?;
+        }
+        Ok(())
+    }
+
+    /// Toggles a window between tiled and floating, then relayouts the workspace.
+    pub fn toggle_window_state<X: XConn>(&mut self, 
+        conn: &X, 
+        screen: &Screen, 
+        id: XWindowID
+    ) -> Result<()> {
+        let win = self.windows.lookup(id).ok_or(WMError::NoSuchWindow(id))?;
+        let to_tiled = win.is_floating();
+
+        debug!("Toggling window state");
+        self.set_window_tiled(id, to_tiled)?;
+
+        // keep floating windows on top
+        if !to_tiled {
+            conn.configure_window(id, &utils::stack_above());
+        }
+        self.relayout(conn, screen)
+    }
+
+    /// Makes a window tiled or floating, setting a new master as necessary.
+    /// 
+    /// Makes no requests to the X server, so the workspace
+    /// should be relayouted afterwards if it is onscreen.
+    pub(crate) fn set_window_tiled(&mut self, id: XWindowID, tiled: bool) -> Result<()> {
+        let win = self.windows.lookup_mut(id).ok_or(WMError::NoSuchWindow(id))?;
+        if win.is_tiled() == tiled {
+            return Ok(())
+        }
+
+        if tiled { //toggling to tiled
+            win.set_tiled();
+            // if we have no master
+            if self.master.is_none() {
+                debug!("No master, setting master");
+                self.set_master(id);
+            }
+        } else { //toggling to floating
+            win.set_floating();
+
+            if self.tiled_count() == 0 && self.master.is_some() {
+                // if master is the only window
+                debug!("All windows are floating, unsetting master");
+                self.unset_master();
+            } else if self.is_master(id) {
+                debug!("Window to toggle is master, setting new master");
+                let new_master = self.windows.iter()
+                    .find(|win| win.is_tiled())
+                    .map(|win| win.id());
+                if let Some(new_master) = new_master {
+                    self.set_master(new_master);
                 }
             }
-            self.relayout(conn, screen);
         }
+        Ok(())
     }
 
-    pub fn take_focused_window(&mut self,
-        conn: &XCBConnection,
+    /// Removes a window from the workspace without unmapping it.
+    /// 
+    /// Unlike `Workspace::del_window`, this makes no requests to the X server.
+    /// Used for workspaces that are not onscreen, and for windows that
+    /// stay mapped while moving to another workspace.
+    pub(crate) fn take_window(&mut self, idx: usize) -> Client {
+        let window = self.windows.pop(idx);
+        self.windows.unset_focused();
+
+        if self.is_master(window.id()) {
+            if self.tiled_count() == 0 {
+                self.unset_master();
+            } else {
+                let new_master = self.windows.iter()
+                    .find(|win| win.is_tiled())
+                    .unwrap()
+                    .id();
+                self.set_master(new_master);
+            }
+        }
+
+        window
+    }
+
+    pub fn take_focused_window<X: XConn>(&mut self,
+        conn: &X,
         screen: &Screen,
-    ) -> Option<Client> {
+    ) -> Result<Option<Client>> {
         if let Some(window) = self.windows.focused() {
-            let idx = self.windows.get_idx(window.id()).unwrap();
-            let window = window.to_owned();
-            self.del_window(conn, screen, window.id(), idx);
+            let id = window.id();
+            let idx = self.windows.get_idx(id).ok_or(WMError::NoSuchWindow(id))?;
 
-            Some(window)
+            // keep the client del_window unmapped, so its UnmapNotify is expected
+            Ok(Some(self.del_window(conn, screen, id, idx)?))
         } else {
-            None
+            Ok(None)
         }
     }
 
-    pub fn focus_window(&mut self, conn: &XCBConnection, _screen: &Screen, id: XWindowID) {
+    pub fn focus_window<X: XConn>(&mut self, conn: &X, _screen: &Screen, id: XWindowID) {
         debug!("Focusing window in workspace {}", id);
 
-        (self._focus_window)(conn, self, id);
+        match self.layout {
+            LayoutType::Floating => floating::window_focus(conn, self, id),
+            _ => dtiled::window_focus(conn, self, id),
+        }
     }
 
-    pub fn relayout(&mut self, conn: &XCBConnection, scr: &Screen) {
-        (self._relayout)(conn, self, scr);
+    pub fn relayout<X: XConn>(&mut self, conn: &X, scr: &Screen) -> Result<()> {
+        match self.layout {
+            LayoutType::Floating => floating::relayout(conn, self, scr),
+            _ => dtiled::relayout(conn, self, scr),
+        }
     }
 
-    pub fn cycle_focus(&mut self, conn: &XCBConnection, direction: Direction) {
-        (self._cycle_focus)(conn, self, direction);
+    pub fn cycle_focus<X: XConn>(&mut self, conn: &X, direction: Direction) {
+        match self.layout {
+            LayoutType::Floating => floating::cycle_focus(conn, self, direction),
+            _ => dtiled::cycle_focus(conn, self, direction),
+        }
     }
 
-    pub fn cycle_master(&mut self, 
-        conn: &XCBConnection, 
+    pub fn cycle_master<X: XConn>(&mut self, 
+        conn: &X, 
         screen: &Screen, 
         direction: Direction
-    ) {
+    ) -> Result<()> {
         if !self.windows.is_empty() {
             self.windows.rotate(direction);
-            self.master = Some(self.windows.get(0).unwrap().id());
-            self.relayout(conn, screen);
+            self.master = self.windows.get(0).map(|win| win.id());
+            self.relayout(conn, screen)?;
         }
+        Ok(())
     }
 
     pub fn contains(&self, window: XWindowID) -> Option<usize> {
@@ -1,13 +1,15 @@
-use crate::x::core::{XCBConnection, XWindowID};
+use crate::x::core::{XConn, XWindowID};
+use crate::x::ConfigureBatch;
+use crate::x::consts;
 use crate::workspace::Workspace;
 use crate::window::Client;
-use crate::types::{Geometry, Direction};
+use crate::types::{Geometry, Direction, BorderStyle, Result};
 use crate::desktop::Screen;
 use crate::utils;
 
 use super::BORDER_WIDTH;
 
-pub fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen) {
+pub fn activate<X: XConn>(conn: &X, ws: &mut Workspace, screen: &Screen) -> Result<()> {
     function_ends!("[start] dtiled::activate");
     // we cannot use the base activate function here as it cannot
     // account for when a new window is sent to the workspace from another
@@ -21,33 +23,31 @@ pub fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen) {
     // }
     
     // reload the window layout
-    let root_geom = conn.get_root_geom().expect("Could not get root geom");
-    calculate_geoms(ws, screen, root_geom);
+    calculate_geoms(ws, screen, screen.workarea());
     // now that all the geometries are calculated, we can map the normal way
     super::activate(conn, ws, screen);
     function_ends!("[end] dtiled::activate");
+    Ok(())
 }
 
-pub fn deactivate(conn: &XCBConnection, ws: &mut Workspace) {
+pub fn deactivate<X: XConn>(conn: &X, ws: &mut Workspace) {
     function_ends!("[start] dtiled::deactivate");
     // no special treatment needed for this one.
     super::deactivate(conn, ws);
     function_ends!("[end] dtiled::deactivate");
 }
 
-pub fn add_window(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen, window_id: XWindowID) {
+pub fn add_window<X: XConn>(
+    conn: &X, 
+    ws: &mut Workspace, 
+    screen: &Screen, 
+    window_id: XWindowID,
+    focus: bool,
+) -> Result<()> {
     function_ends!("[start] dtiled::add_window");
     // Internally create a new window and set its supported protocols
     let window = Client::tiled(window_id, conn);
-    // Get root geometries
-    let root_geom = conn.get_root_geom().expect("Could not get root geometry");
-    let root_geom2 = screen.xwindow.geom;
-
-    if root_geom != root_geom2 {
-        warn!("Stored geom and retrieved geom mismatch");
-        debug!("gotten: {:?}", root_geom);
-        debug!("stored: {:?}", root_geom2);
-    }
+    let focused = ws.windows.focused().map(|win| win.id());
 
     // Add new windows
     // If there is already a master, insert after it
@@ -62,38 +62,52 @@ pub fn add_window(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen, win
     }
 
     // Calculate the tile sizes
-    calculate_geoms(ws, screen, root_geom);
+    calculate_geoms(ws, screen, screen.workarea());
 
-    for win in ws.windows.iter_mut() {
-        win.update_geometry(conn);
-        if win.id() == window_id {
-            win.configure(conn, &[(xcb::CONFIG_WINDOW_BORDER_WIDTH as u16, BORDER_WIDTH)]);
+    // configure every window once, including the border and stacking of the new one
+    let mut batch = ConfigureBatch::new();
+    for win in ws.windows.iter() {
+        win.queue_geometry(&mut batch);
+    }
+    batch.configure(window_id, &[(consts::CONFIG_WINDOW_BORDER_WIDTH as u16, BORDER_WIDTH)]);
+    batch.configure(window_id, &utils::stack_above());
+    batch.submit(conn);
 
-            win.map(conn);
-            win.configure(conn, &utils::stack_above());
-            win.change_attributes(conn, &utils::child_events());
+    if let Some(win) = ws.windows.lookup_mut(window_id) {
+        win.map(conn);
+        win.change_attributes(conn, &utils::child_events());
+    }
+    if focus {
+        window_focus(conn, ws, window_id);
+    } else {
+        // inserting may have moved the focused window
+        if let Some(focused) = focused {
+            ws.windows.set_focused_by_winid(focused);
+        }
+        if let Some(win) = ws.windows.lookup_mut(window_id) {
+            win.set_border(conn, BorderStyle::Unfocused);
         }
     }
-    window_focus(conn, ws, window_id);
+    conn.flush();
 
     function_ends!("[end] dtiled::add_window");
+    Ok(())
 }
 
-pub fn del_window(
-    conn: &XCBConnection,
+pub fn del_window<X: XConn>(
+    conn: &X,
     ws: &mut Workspace,
     screen: &Screen,
     window_id: XWindowID,
     idx: usize
-) -> Client {
+) -> Result<Client> {
     function_ends!("[start] dtiled::del_window");
     debug!("Got window with idx {}", idx);
 
     // internally remove window from tracking
     let mut window = ws.windows.pop(idx);
 
-    // disable events and unmap the window
-    window.change_attributes(conn, &utils::disable_events());
+    // unmap the window
     window.unmap(conn);
     ws.windows.unset_focused();
 
@@ -131,27 +145,30 @@ pub fn del_window(
     }
 
     // recalculate layouts
-    relayout(conn, ws, screen);
+    relayout(conn, ws, screen)?;
 
     function_ends!("[end] dtiled::del_window");
-    window
+    Ok(window)
 
     //super::floating::del_window(conn, ws, screen, window_id, idx)
 }
 
-pub fn window_focus(conn: &XCBConnection, ws: &mut Workspace, window: XWindowID) {
+pub fn window_focus<X: XConn>(conn: &X, ws: &mut Workspace, window: XWindowID) {
     // todo: placeholder
     super::floating::window_focus(conn, ws, window)
 }
 
-pub fn cycle_focus(conn: &XCBConnection, ws: &mut Workspace, direction: Direction) {
+pub fn cycle_focus<X: XConn>(conn: &X, ws: &mut Workspace, direction: Direction) {
     super::cycle_focus(conn, ws, direction);
 }
 
-pub fn relayout(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen) {
-    let root_geom = conn.get_root_geom().expect("Could not get root geom");
-    calculate_geoms(ws, screen, root_geom);
-    ws.windows.iter_mut().for_each(|win| win.update_geometry(conn));
+pub fn relayout<X: XConn>(conn: &X, ws: &mut Workspace, screen: &Screen) -> Result<()> {
+    calculate_geoms(ws, screen, screen.workarea());
+
+    let mut batch = ConfigureBatch::new();
+    ws.windows.iter().for_each(|win| win.queue_geometry(&mut batch));
+    batch.submit(conn);
+    Ok(())
 }
 
 fn calculate_geoms(ws: &mut Workspace, _screen: &Screen, root_geom: Geometry) {
@@ -184,7 +201,8 @@ fn calculate_geoms(ws: &mut Workspace, _screen: &Screen, root_geom: Geometry) {
                 width: root_geom.width - (BORDER_WIDTH as i32 * 2),
             };
 
-            master.set_geometry(master_geom);
+            // windows whose size hints stop them filling their tile are centered in it
+            master.set_geometry(master.fit_geometry(master_geom, consts::GRAVITY_CENTER));
 
         } else { 
             debug!("dtiled::calculate_geoms: Multiple windows mapped, calculating");
@@ -204,13 +222,13 @@ fn calculate_geoms(ws: &mut Workspace, _screen: &Screen, root_geom: Geometry) {
                 ) - BORDER_WIDTH as i32;
 
                 
-            if master_geom.x != 0 || master_geom.y != 0 {
+            if master_geom.x != root_geom.x || master_geom.y != root_geom.y {
                 debug!("Master is not in position");
                 
                 master_geom.x = root_geom.x;
                 master_geom.y = root_geom.y;
             }
-            master.set_geometry(master_geom);
+            master.set_geometry(master.fit_geometry(master_geom, consts::GRAVITY_CENTER));
                 
             // get no of slave windows
             let slave_count = if ws.tiled_count() == 0 { 0 } else { ws.tiled_count() - 1 };
@@ -224,21 +242,21 @@ fn calculate_geoms(ws: &mut Workspace, _screen: &Screen, root_geom: Geometry) {
                 .filter(|win| win.id() != mstr && win.is_tiled())
                 .enumerate() {
                 // calculate coords
-                let y = i as i32 * slave_height + if i == 0 { 0 } else {
+                let y = root_geom.y + i as i32 * slave_height + if i == 0 { 0 } else {
                     (BORDER_WIDTH as i32 * 2) * i as i32
                 };
-                let x = master_geom.width + (BORDER_WIDTH as i32 * 2);
+                let x = root_geom.x + master_geom.width + (BORDER_WIDTH as i32 * 2);
 
                 let slave_geom = Geometry {
-                    x: x,
-                    y: y,
+                    x,
+                    y,
                     height: slave_height,
                     width: slave_width,
                 };
 
                 debug!("{:#?}", slave_geom);
 
-                win.set_geometry(slave_geom);
+                win.set_geometry(win.fit_geometry(slave_geom, consts::GRAVITY_CENTER));
             }
 
             
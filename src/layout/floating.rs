@@ -1,72 +1,82 @@
-use crate::x::core::{XCBConnection, XWindowID};
+use crate::x::core::{XConn, XWindowID};
+use crate::x::consts;
 use crate::workspace::Workspace;
 use crate::window::Client;
-use crate::types::{Direction, BorderStyle};
+use crate::types::{Direction, BorderStyle, Result};
 use crate::desktop::Screen;
 use crate::utils;
 
 use super::BORDER_WIDTH;
 
-pub fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen) {
-    super::activate(conn, ws, screen)
+pub fn activate<X: XConn>(conn: &X, ws: &mut Workspace, screen: &Screen) -> Result<()> {
+    super::activate(conn, ws, screen);
+    Ok(())
 }
 
-pub fn deactivate(conn: &XCBConnection, ws: &mut Workspace) {
+pub fn deactivate<X: XConn>(conn: &X, ws: &mut Workspace) {
     super::deactivate(conn, ws)
 }
 
-pub fn add_window(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen, window_id: XWindowID) {
+pub fn add_window<X: XConn>(
+    conn: &X, 
+    ws: &mut Workspace, 
+    screen: &Screen, 
+    window_id: XWindowID,
+    focus: bool,
+) -> Result<()> {
     let mut window = Client::floating(window_id, conn);
+    let focused = ws.windows.focused().map(|win| win.id());
 
     window.set_supported(conn);
 
-    conn.map_window(window.id());
+    window.map(conn);
 
     if let Some(_focused) = ws.windows.focused() {
         conn.configure_window(window_id, &utils::stack_above());
     }
-    conn.configure_window(window.id(), &[(xcb::CONFIG_WINDOW_BORDER_WIDTH as u16, BORDER_WIDTH)]);
+    conn.configure_window(window.id(), &[(consts::CONFIG_WINDOW_BORDER_WIDTH as u16, BORDER_WIDTH)]);
 
     window.xwindow.set_geometry_conn(conn);
+    
+    conn.change_window_attributes(window.id(), &utils::child_events());
 
-    match conn.query_pointer(screen.xwindow.id) {
-        Ok(pointer) => {
-            if pointer.child() == screen.xwindow.id || pointer.child() == window_id {
-                window_focus(conn, ws, window_id);
-            } else {
-                if let Some(focused) = ws.windows.focused_mut() {
-                    focused.set_border(conn, BorderStyle::Unfocused);
-                    window_focus(conn, ws, window_id);
-                } else {
-                    let win = ws.windows.lookup_mut(window_id).unwrap();
-                    win.set_border(conn, BorderStyle::Unfocused);
-                }
-            }
+    ws.windows.push(window);
+
+    if !focus {
+        // pushing moved the focused window
+        if let Some(focused) = focused {
+            ws.windows.set_focused_by_winid(focused);
         }
-        Err(e) => {
-            error!("{}", e)
+        if let Some(win) = ws.windows.lookup_mut(window_id) {
+            win.set_border(conn, BorderStyle::Unfocused);
         }
+        return Ok(())
     }
-    
-    conn.change_window_attributes(window.id(), &utils::child_events());
 
-    ws.windows.push(window);
+    let pointer = conn.query_pointer(screen.xwindow.id)?;
+
+    if pointer.child == screen.xwindow.id || pointer.child == window_id {
+        window_focus(conn, ws, window_id);
+    } else if let Some(focused) = ws.windows.focused_mut() {
+        focused.set_border(conn, BorderStyle::Unfocused);
+        window_focus(conn, ws, window_id);
+    } else if let Some(win) = ws.windows.lookup_mut(window_id) {
+        win.set_border(conn, BorderStyle::Unfocused);
+    }
+
+    Ok(())
 }
 
-#[allow(mutable_borrow_reservation_conflict)]
-//* this ^^^ should be fixed
-pub fn del_window(
-    conn: &XCBConnection, 
+pub fn del_window<X: XConn>(
+    conn: &X, 
     ws: &mut Workspace, 
     _screen: &Screen, 
-    window_id: XWindowID,
+    _window_id: XWindowID,
     idx: usize
-) ->  Client {
-    let window = ws.windows.pop(idx);
-
-    conn.change_window_attributes(window_id, &utils::disable_events());
+) -> Result<Client> {
+    let mut window = ws.windows.pop(idx);
 
-    conn.unmap_window(window_id);
+    window.unmap(conn);
 
     if idx == 0 {
         if let Some(next) = ws.windows.get(0) {
@@ -79,10 +89,10 @@ pub fn del_window(
         ws.windows.unset_focused();
     }
 
-    window
+    Ok(window)
 }
 
-pub fn window_focus(conn: &XCBConnection, ws: &mut Workspace, window: XWindowID) {
+pub fn window_focus<X: XConn>(conn: &X, ws: &mut Workspace, window: XWindowID) {
 
     if let Some(idx) = ws.windows.get_idx(window) {
         debug!("Found window {}", window);
@@ -97,11 +107,12 @@ pub fn window_focus(conn: &XCBConnection, ws: &mut Workspace, window: XWindowID)
     }
 }
 
-pub fn cycle_focus(conn: &XCBConnection, ws: &mut Workspace, direction: Direction) {
+pub fn cycle_focus<X: XConn>(conn: &X, ws: &mut Workspace, direction: Direction) {
     super::cycle_focus(conn, ws, direction)
 }
 
-pub fn relayout(_conn: &XCBConnection, _ws: &mut Workspace, _screen: &Screen) {
+pub fn relayout<X: XConn>(_conn: &X, _ws: &mut Workspace, _screen: &Screen) -> Result<()> {
     //todo: if coming from tiling, 
     //todo: we restore windows to their ICCCM-defined geometries
+    Ok(())
 }
\ No newline at end of file
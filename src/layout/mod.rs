@@ -3,7 +3,8 @@
 pub(crate) mod floating;
 pub(crate) mod dtiled;
 
-use crate::x::core::{XCBConnection, XWindowID};
+use crate::x::core::{XConn, XWindowID};
+use crate::x::ConfigureBatch;
 use crate::types::{Direction, BorderStyle};
 use crate::workspace::Workspace;
 use crate::desktop::Screen;
@@ -23,12 +24,17 @@ pub enum LayoutType {
     MTiled,
 }
 
-fn window_stack_and_focus(ws: &mut Workspace, conn: &XCBConnection, window: XWindowID) {
+fn window_stack_and_focus<X: XConn>(ws: &mut Workspace, conn: &X, window: XWindowID) {
     use BorderStyle::*;
     // disable events
     conn.change_window_attributes(window, &utils::disable_events());
 
-    let win = ws.windows.lookup_mut(window).unwrap();
+    let win = if let Some(win) = ws.windows.lookup_mut(window) {
+        win
+    } else {
+        error!("window_stack_and_focus: No such window {}", window);
+        return
+    };
 
     // if there is a focused window, stack it above
     // if let Some(win) = ws.windows.focused() {
@@ -39,7 +45,7 @@ fn window_stack_and_focus(ws: &mut Workspace, conn: &XCBConnection, window: XWin
     
     // focus to current window
     win.set_border(conn, Focused);
-    conn.set_input_focus(window);
+    win.focus(conn);
 
     // re-enable events
     conn.change_window_attributes(window, &utils::child_events());
@@ -47,8 +53,8 @@ fn window_stack_and_focus(ws: &mut Workspace, conn: &XCBConnection, window: XWin
 
 /// The base activate function.
 /// 
-/// Sequentially maps every window to the screen.
-pub(crate) fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen) {
+/// Configures every window in a single batch, then maps them to the screen.
+pub(crate) fn activate<X: XConn>(conn: &X, ws: &mut Workspace, screen: &Screen) {
     if ws.windows.is_empty() {
         return
     }
@@ -58,8 +64,11 @@ pub(crate) fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen
     // else (should be tiled), focus the master window
     if let LayoutType::Floating = ws.layout {
         assert!(ws.master.is_none());
-        if !ws.is_empty() {
-            ws.focus_window(conn, screen, ws.windows.get(0).unwrap().id());
+        let first = ws.windows.iter()
+            .find(|win| !win.is_hidden())
+            .map(|win| win.id());
+        if let Some(id) = first {
+            ws.focus_window(conn, screen, id);
         }
     } else {
         debug!("Master is {:?}", ws.master);
@@ -68,32 +77,43 @@ pub(crate) fn activate(conn: &XCBConnection, ws: &mut Workspace, screen: &Screen
         }
     }
 
+    // disable events
     for window in ws.windows.iter_rev() {
-        // disable events
         window.change_attributes(conn, &utils::disable_events());
-        // update window geometry in the x server
-        window.update_geometry(conn);
-        // map window
-        conn.map_window(window.id());
-        // re-enable events
+    }
+
+    // update window geometries in the x server
+    let mut batch = ConfigureBatch::new();
+    for window in ws.windows.iter_rev() {
+        window.queue_geometry(&mut batch);
+    }
+    batch.submit(conn);
+
+    for window in ws.windows.iter_rev_mut() {
+        // hidden windows stay unmapped
+        if !window.is_hidden() {
+            window.show(conn);
+        }
+        // re-enable events, so hidden windows still report property changes
         window.change_attributes(conn, &utils::child_events());
     }
+    conn.flush();
 }
 
 /// The base deactivate function.
 /// 
 /// Sequentially unmaps every window in reverse.
-pub(crate) fn deactivate(conn: &XCBConnection, ws: &mut Workspace) {
-    for window in ws.windows.iter() {
-        conn.change_window_attributes(window.id(), &utils::disable_events());
-
-        conn.unmap_window(window.id());
-
-        conn.change_window_attributes(window.id(), &utils::child_events());
+/// 
+/// Windows are left selecting events, so that their UnmapNotify
+/// is received and matched with the unmap.
+pub(crate) fn deactivate<X: XConn>(conn: &X, ws: &mut Workspace) {
+    for window in ws.windows.iter_mut() {
+        window.unmap(conn);
     }
+    conn.flush();
 }
 
-pub(crate) fn cycle_focus(conn: &XCBConnection, ws: &mut Workspace, direction: Direction) {
+pub(crate) fn cycle_focus<X: XConn>(conn: &X, ws: &mut Workspace, direction: Direction) {
     use BorderStyle::*;
 
     //change currently focused border colour to unfocused
@@ -101,12 +121,17 @@ pub(crate) fn cycle_focus(conn: &XCBConnection, ws: &mut Workspace, direction: D
         win.set_border(conn, Unfocused);
     }
     
-    //internally, cycle focus
-    ws.windows.cycle_focus(direction);
+    //internally, cycle focus, passing over hidden windows as they are unmapped
+    for _ in 0..ws.windows.len() {
+        ws.windows.cycle_focus(direction);
+        if ws.windows.focused().is_some_and(|win| !win.is_hidden()) {
+            break
+        }
+    }
 
     // change focus colours
-    if ws.windows.focused().is_some() {
-        let focused = ws.windows.focused().unwrap().id();
+    if let Some(focused) = ws.windows.focused().filter(|win| !win.is_hidden()) {
+        let focused = focused.id();
 
         window_stack_and_focus(ws, conn, focused);
     }
@@ -1,10 +1,10 @@
 #[cfg(debug_assertions)]
 macro_rules! debug {
     ($fmt:expr) => {
-        (println!(concat!("[debug] ", $fmt)));
+        (println!(concat!("[debug] ", $fmt)))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (println!(concat!("[debug] ", $fmt), $($arg)*));
+        (println!(concat!("[debug] ", $fmt), $($arg)*))
     };
 }
 
@@ -17,10 +17,10 @@ macro_rules! debug {
 #[cfg(debug_assertions)]
 macro_rules! function_ends {
     ($fmt:expr) => {
-        (println!(concat!("================ ", $fmt, " ================")));
+        (println!(concat!("================ ", $fmt, " ================")))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (println!(concat!("================ ", $fmt " ================"), $($arg)*));
+        (println!(concat!("================ ", $fmt " ================"), $($arg)*))
     };
 }
 
@@ -32,37 +32,38 @@ macro_rules! function_ends {
 
 macro_rules! info {
     ($fmt:expr) => {
-        (println!(concat!("[*] ", $fmt)));
+        (println!(concat!("[*] ", $fmt)))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (println!(concat!("[*] ", $fmt), $($arg)*));
+        (println!(concat!("[*] ", $fmt), $($arg)*))
     };
 }
 
 macro_rules! warn {
     ($fmt:expr) => {
-        (println!(concat!("[!] ", $fmt)));
+        (println!(concat!("[!] ", $fmt)))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (println!(concat!("[!] ", $fmt), $($arg)*));
+        (println!(concat!("[!] ", $fmt), $($arg)*))
     };
 }
 
+#[allow(unused_macros)]
 macro_rules! fatal {
     ($fmt:expr) => {
-        (panic!(concat!("[FATAL] ", $fmt)));
+        (panic!(concat!("[FATAL] ", $fmt)))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (panic!(concat!("[FATAL] ", $fmt), $($arg)*));
+        (panic!(concat!("[FATAL] ", $fmt), $($arg)*))
     };
 }
 
 macro_rules! error {
     ($fmt:expr) => {
-        (eprintln!(concat!("[X] ", $fmt)));
+        (eprintln!(concat!("[X] ", $fmt)))
     };
     ($fmt:expr, $($arg:tt)*) => {
-        (eprintln!(concat!("[X] ", $fmt), $($arg)*));
+        (eprintln!(concat!("[X] ", $fmt), $($arg)*))
     };
 }
 
@@ -20,9 +20,14 @@ extern crate log as logger;
 use std::error::Error;
 
 use manager::WindowManager;
+use x::{XConn, XError, RecordingConn, ReplayConn};
+#[cfg(feature = "xcb-backend")]
+use x::XCBConnection;
+#[cfg(all(feature = "x11rb-backend", not(feature = "xcb-backend")))]
+use x::X11RBConnection;
 
-use xcb::base::Connection;
-use xcb_util::ewmh;
+#[cfg(not(any(feature = "xcb-backend", feature = "x11rb-backend")))]
+compile_error!("rcwm needs a backend: enable either `xcb-backend` or `x11rb-backend`");
 
 use nix::sys::signal::{
     signal, Signal, SigHandler
@@ -34,16 +39,81 @@ fn main() -> Result<(), Box<dyn Error>> {
         signal(Signal::SIGQUIT, SigHandler::SigIgn)?;
     }
 
+    let args: Vec<String> = std::env::args().collect();
+
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("--record"), Some(path)) => {
+            let (conn, screen_idx) = connect()?;
+            info!("Recording session to {}", path);
+            run(RecordingConn::create(conn, path)?, screen_idx)
+        }
+        (Some("--replay"), Some(path)) => {
+            info!("Replaying session from {}", path);
+            match run(ReplayConn::open(path)?, 0) {
+                // the end of the recording closes the connection
+                Err(e) if matches!(
+                    e.downcast_ref(),
+                    Some(types::WMError::XError(XError::ConnectionClosed))
+                ) => Ok(()),
+                res => res,
+            }
+        }
+        (None, _) => {
+            let (conn, screen_idx) = connect()?;
+            run(conn, screen_idx)
+        }
+        _ => {
+            eprintln!("usage: {} [--record FILE | --replay FILE]", args[0]);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Registers the window manager on the given connection and runs it.
+fn run<X: XConn>(conn: X, screen_idx: i32) -> Result<(), Box<dyn Error>> {
+    let mut wm = WindowManager::register(conn, screen_idx)
+        .map_err(|e| {
+            error!("{}", e);
+            e
+        })?;
+
+    wm.run()?;
+
+    Ok(())
+}
+
+/// Connects to the X server using the XCB backend.
+#[cfg(feature = "xcb-backend")]
+fn connect() -> Result<(XCBConnection, i32), Box<dyn Error>> {
+    use xcb::base::Connection;
+    use xcb_util::ewmh;
+
     let (conn, screen_idx) = Connection::connect(None)
-        .expect("Failed to connect to X server");
+        .map_err(|e| {
+            error!("Could not connect to the X server: {}", e);
+            XError::Connection
+        })?;
     
     let conn = ewmh::Connection::connect(conn)
-        .map_err(|(err, _)| { err })
-        .expect("Failed to connect via EWMH");
+        .map_err(|(e, _)| {
+            error!("Could not connect via EWMH: {}", e);
+            XError::Connection
+        })?;
 
-    let mut wm = WindowManager::register(conn, screen_idx);
+    Ok((XCBConnection::new(conn, screen_idx)?, screen_idx))
+}
 
-    wm.run();
+/// Connects to the X server using the x11rb backend.
+#[cfg(all(feature = "x11rb-backend", not(feature = "xcb-backend")))]
+fn connect() -> Result<(X11RBConnection, i32), Box<dyn Error>> {
+    use x11rb::rust_connection::RustConnection;
 
-    Ok(())
+    let (conn, screen_idx) = RustConnection::connect(None)
+        .map_err(|e| {
+            error!("Could not connect to the X server: {}", e);
+            XError::Connection
+        })?;
+    let screen_idx = screen_idx as i32;
+
+    Ok((X11RBConnection::new(conn, screen_idx)?, screen_idx))
 }
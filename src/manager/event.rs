@@ -1,7 +1,41 @@
-use crate::x::{core::{XEvent, XWindowID}, Ewmh};
-use crate::core::types::{Geometry, Keybind};
-use crate::manager::WMState;
+//! This module defines `EventAction`, the set of actions the
+//! window manager can take in response to an X event.
+//!
+//! Deciding what to do with an event is kept separate from
+//! actually doing it: `EventAction::from_xevent` only inspects
+//! the event and the current state of the window manager,
+//! and `WindowManager` then executes the resulting actions.
 
+use crate::x::core::{
+    XConn,
+    XEvent,
+    XWindowID,
+    Timestamp,
+    KeypressEvent,
+    KeySym,
+    ButtonIndex,
+    ConfigureRequestData,
+    ClientMessageData,
+    ClientMessageEvent,
+    XErrorEvent,
+    UnmapEvent,
+    SyncAlarm,
+};
+use crate::x::consts;
+use crate::core::types::{
+    Geometry,
+    Point,
+    SourceIndication,
+    ActivationPolicy,
+    NetWindowState,
+    StateChange,
+    ResizeEdges,
+    keysym,
+};
+use crate::config;
+use crate::manager::{WMState, MouseMode};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventAction {
     /// Focus the specified client.
     ClientFocus(XWindowID),
@@ -9,85 +43,673 @@ pub enum EventAction {
     ClientUnfocus(XWindowID),
     /// Change the WM_NAME property of the specified client.
     ClientNameChange(XWindowID),
-    /// Send the focused client to the specified workspace.
-    ClientToWorkspace(usize),
+    /// Send the specified client to the specified workspace.
+    ClientToWorkspace(XWindowID, usize),
     /// Switch to the specified workspace.
     GotoWorkspace(usize),
-    /// Destroy the specified client.
+    /// Switch to the workspace of the specified client, then focus and raise it.
+    ActivateClient(XWindowID),
+    /// Mark the specified client as demanding attention.
+    MarkClientUrgent(XWindowID),
+    /// Close the specified client, killing it if it stopped responding.
     DestroyClient(XWindowID),
+    /// The specified client answered the _NET_WM_PING sent at the given time.
+    ClientPong(XWindowID, Timestamp),
     /// Map the specified client and track it internally.
-    /// 
+    ///
     /// Applies to normal windows.
     MapTrackedClient(XWindowID),
+    /// Map the specified client and track it as floating.
+    ///
+    /// Used for dialogue boxes.
+    MapFloatingClient(XWindowID),
     /// Map the specified client and manage it without tracking.
-    /// 
-    /// Used for dialogue boxes and other windows that have
-    /// WM_TRANSIENT_FOR set.
+    ///
+    /// Used for docks, splash screens and other windows
+    /// that should not be laid out.
     MapUntrackedClient(XWindowID),
-    /// Unmap the specified client.
+    /// Map a dock and reserve the space given by its struts.
+    MapDock(XWindowID),
+    /// Read the struts of a dock again after they changed.
+    UpdateDockStrut(XWindowID),
+    /// Stop reserving space for a dock that was unmapped or destroyed.
+    RemoveDock(XWindowID),
+    /// Map a desktop window below every other window, covering the screen.
+    MapDesktopWindow(XWindowID),
+    /// Size a desktop window to the screen again and lower it to the bottom.
+    PlaceDesktopWindow(XWindowID),
+    /// Stop tracking a desktop window that was unmapped or destroyed.
+    RemoveDesktopWindow(XWindowID),
+    /// Hide or show the clients on the current workspace to show the desktop.
+    ShowDesktop(bool),
+    /// Read the size hints of a client again after they changed.
+    UpdateClientSizeHints(XWindowID),
+    /// Read the icons of a client again after they changed.
+    UpdateClientIcon(XWindowID),
+    /// Note that the window manager's unmap of the specified client was received.
+    ExpectedUnmap(XWindowID),
+    /// Stop tracking a client the client withdrew, as described in ICCCM section 4.1.4.
     UnmapClient(XWindowID),
+    /// Stop tracking a client whose window no longer exists.
+    RemoveStaleClient(XWindowID),
     /// Configure the specified client with the given geometry.
     ConfigureClient(XWindowID, Geometry),
-    /// Run the specified keybind.
-    RunKeybind(Keybind),
-    /// Toggle the client in or out of fullscreen.
-    /// 
-    /// Also toggles _NET_WM_STATE_FULLSCREEN.
-    ToggleClientFullscreen(XWindowID, bool),
+    /// Refuse a configure request from a tiled client.
+    ///
+    /// The client is sent back its current geometry, and the
+    /// requested geometry is kept for when it is made floating.
+    DenyConfigureRequest(XWindowID, Geometry),
+    /// Configure a window that is not tracked by the window manager.
+    ConfigureUntracked(XWindowID, Geometry),
+    /// Raise or lower a window, using one of the `STACK_MODE_*` constants.
+    RestackWindow(XWindowID, u32),
+    /// The root window changed size.
+    ScreenReconfigure(Geometry),
+    /// Start dragging the specified client with the mouse.
+    BeginMouseDrag(XWindowID, Point, MouseMode),
+    /// Start moving or resizing the specified client without a button held.
+    ///
+    /// The client is moved with the arrow keys or follows the pointer,
+    /// until Return or a button is pressed, or Escape cancels it.
+    BeginKeyboardDrag(XWindowID, MouseMode),
+    /// Move the selected client so that it follows the pointer.
+    MoveClient(XWindowID, Point),
+    /// Resize the selected client so that it follows the pointer.
+    ResizeClient(XWindowID, Point),
+    /// Move or resize the selected client by the given amounts.
+    DragClientBy(XWindowID, i32, i32),
+    /// Stop dragging the selected client.
+    EndMouseDrag,
+    /// Stop dragging the selected client, putting it back where it started.
+    CancelDrag,
+    /// An XSync alarm reported its counter reaching the given value.
+    SyncAlarmTriggered(SyncAlarm, i64),
+    /// Run the keybind associated with the key press.
+    RunKeybind(KeypressEvent),
+    /// Add, remove or toggle a _NET_WM_STATE of the specified client.
+    ChangeClientState(XWindowID, NetWindowState, StateChange),
 }
 
 impl EventAction {
-
-    #[allow(unused_variables)]
-    pub(crate) fn from_xevent(event: XEvent, state: WMState<'_>) -> Vec<Self> {
+    /// Works out the actions to take in response to an event.
+    ///
+    /// This does not change any state, either internal or on the X server.
+    pub(crate) fn from_xevent<X: XConn>(event: XEvent, state: WMState<'_, X>) -> Vec<Self> {
         use EventAction::*;
         use XEvent::*;
         match event {
-            ConfigureNotify(event) | ConfigureRequest(event) => {
-                if !event.is_root {
-                    vec![ConfigureClient(event.id, event.geom)]
+            ConfigureNotify(event) => {
+                if event.is_root {
+                    vec![ScreenReconfigure(event.geom)]
+                } else {
+                    vec![]
+                }
+            },
+            ConfigureRequest(event) if state.desktop_windows.contains(&event.id) => {
+                vec![PlaceDesktopWindow(event.id)]
+            },
+            ConfigureRequest(event) => process_config_request(event, state),
+            MapRequest(event) => {
+                process_map_request(event.id, event.override_redirect, state)
+            },
+            MapNotify(_) => vec![],
+            UnmapNotify(event) => process_unmap_notify(event, state),
+            DestroyNotify(event) if state.docks.contains_key(&event.id) => {
+                vec![RemoveDock(event.id)]
+            },
+            DestroyNotify(event) if state.desktop_windows.contains(&event.id) => {
+                vec![RemoveDesktopWindow(event.id)]
+            },
+            DestroyNotify(event) => {
+                if state.desktop.retrieve(event.id).is_some() {
+                    vec![RemoveStaleClient(event.id)]
+                } else {
+                    debug!("Destroy notify for untracked window {}", event.id);
+                    vec![]
+                }
+            },
+            CreateNotify(event) => {
+                debug!("Window {} created", event.id);
+                vec![]
+            },
+            CirculateRequest(event) if state.desktop_windows.contains(&event.id) => {
+                vec![PlaceDesktopWindow(event.id)]
+            },
+            CirculateRequest(event) => {
+                let mode = if event.place == consts::PLACE_ON_TOP {
+                    consts::STACK_MODE_ABOVE
+                } else {
+                    consts::STACK_MODE_BELOW
+                };
+                vec![RestackWindow(event.id, mode)]
+            },
+            CirculateNotify(_) | GravityNotify(..) => vec![],
+            EnterNotify(event) => {
+                if !event.is_normal() {
+                    return vec![]
+                }
+                if state.desktop.current().contains(event.window).is_some() {
+                    vec![ClientFocus(event.window)]
+                } else {
+                    warn!("On enter notify for untracked window {}", event.window);
+                    vec![]
+                }
+            },
+            LeaveNotify(_) => {
+                debug!("Leave notify");
+                vec![]
+            },
+            FocusIn(event) => {
+                debug!("Window {} gained focus", event.window);
+                vec![]
+            },
+            FocusOut(event) => {
+                debug!("Window {} lost focus", event.window);
+                vec![]
+            },
+            MotionNotify(event) => process_motion_notify(event.root_pt, state),
+            ReparentNotify(event) => {
+                debug!("Window {} reparented to {}", event.id, event.parent);
+                vec![]
+            },
+            PropertyNotify(event) if state.docks.contains_key(&event.id) => {
+                let atoms = state.conn.get_atoms();
+                if event.atom == atoms.WM_STRUT || event.atom == atoms.WM_STRUT_PARTIAL {
+                    vec![UpdateDockStrut(event.id)]
+                } else {
+                    vec![]
+                }
+            },
+            PropertyNotify(event) if event.atom == consts::ATOM_WM_NORMAL_HINTS => {
+                if state.desktop.client(event.id).is_some() {
+                    vec![UpdateClientSizeHints(event.id)]
+                } else {
+                    vec![]
+                }
+            },
+            PropertyNotify(event) if event.atom == state.conn.get_atoms().WM_ICON => {
+                if state.desktop.client(event.id).is_some() {
+                    vec![UpdateClientIcon(event.id)]
                 } else {
-                    //todo: add root configure actions
                     vec![]
                 }
             },
-            MapRequest(id, override_redirect) => {
-                if override_redirect {
-                    
+            PropertyNotify(event) => {
+                if let Some(win) = state.desktop.current().windows.lookup(event.id) {
+                    debug!("Property change for window {:#?}", win);
                 }
+                vec![]
             },
-            MapNotify(id) => vec![],
-            UnmapNotify(id) => vec![],
-            DestroyNotify(id) => vec![],
-            EnterNotify(id) => vec![],
-            LeaveNotify(id) => {},
-            MotionNotify(pt) => {},
-            ReparentNotify(id) => {},
-            PropertyNotify(event) => {},
-            KeyPress(event) => {},
-            KeyRelease => {},
-            ButtonPress(id, pt) => {},
-            ButtonRelease => {},
-            ClientMessage(id, data) => {},
-            Unknown(smth) => {},
-        }
-        todo!()
+            KeyPress(event) if state.keyboard_drag => process_drag_key(event.keysym, state),
+            KeyPress(event) => vec![RunKeybind(event)],
+            KeyRelease(_) => vec![],
+            ButtonPress(event) => {
+                process_button_press(event.child, event.root_pt, event.button, state)
+            },
+            ButtonRelease(_) => vec![EndMouseDrag],
+            Expose(_) => vec![],
+            MappingNotify(event) => {
+                debug!("Mapping changed for {} keycodes from {}", event.count, event.first_keycode);
+                vec![]
+            },
+            ClientMessage(event) => on_client_message(event, state),
+            SyncAlarmNotify(event) => vec![SyncAlarmTriggered(event.alarm, event.counter_value)],
+            Error(error) => process_error(error, state),
+            Extension(event) => {
+                debug!("Unhandled extension event {}", event.response_type);
+                vec![]
+            },
+            Unknown(smth) => {
+                debug!("Unhandled event {}", smth);
+                vec![]
+            },
+        }
     }
 }
 
-#[allow(unused_imports)]
-fn process_map_request(
-    id: XWindowID, ovrd: bool, state: WMState<'_>
+/// Works out whether an unmap was caused by the window manager,
+/// or by the client withdrawing the window.
+/// 
+/// Clients withdraw windows that are already unmapped by sending
+/// a synthetic UnmapNotify, as described in ICCCM section 4.1.4.
+fn process_unmap_notify<X: XConn>(
+    event: UnmapEvent, state: WMState<'_, X>
 ) -> Vec<EventAction> {
     use EventAction::*;
-    use XEvent::*;
-    
+
+    let id = event.id;
+    if state.docks.contains_key(&id) {
+        return vec![RemoveDock(id)]
+    }
+    if state.desktop_windows.contains(&id) {
+        return vec![RemoveDesktopWindow(id)]
+    }
+
+    match state.desktop.client(id) {
+        Some(win) if win.expects_unmap() && !event.synthetic => {
+            debug!("Unmap notify for window {} unmapped by the window manager", id);
+            vec![ExpectedUnmap(id)]
+        }
+        Some(_) => {
+            debug!("Window {} was withdrawn", id);
+            vec![UnmapClient(id)]
+        }
+        None => {
+            debug!("Unmap notify for untracked window {}", id);
+            vec![]
+        }
+    }
+}
+
+fn process_map_request<X: XConn>(
+    id: XWindowID, ovrd: bool, state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    if let Some(client) = state.desktop.client(id) {
+        // clients leave the Iconic state by mapping their window again
+        if client.is_hidden() {
+            debug!("Map request for hidden window {}, unhiding it", id);
+            return vec![ChangeClientState(id, NetWindowState::Hidden, StateChange::Remove)]
+        }
+        debug!("Map request for existing window");
+        return vec![]
+    }
+
+    debug!("On map request for window {}", id);
+
     if let Some(window_type) = state.conn.get_window_type(id) {
         let atoms = state.conn.get_atoms();
 
+        if window_type.contains(&atoms.WM_WINDOW_TYPE_DOCK) {
+            debug!("Window {} is a dock", id);
+            return vec![MapDock(id)]
+        }
+        if window_type.contains(&atoms.WM_WINDOW_TYPE_DESKTOP) {
+            debug!("Window {} is a desktop window", id);
+            return vec![MapDesktopWindow(id)]
+        }
+
+        // windows to not manage at all
+        if !(window_type.contains(&atoms.WM_WINDOW_TYPE_NORMAL)||
+            window_type.contains(&atoms.WM_WINDOW_TYPE_UTILITY)||
+            window_type.contains(&atoms.WM_WINDOW_TYPE_DIALOG) ||
+            window_type.contains(&atoms.WM_WINDOW_TYPE_TOOLBAR)
+            ) || window_type.contains(&atoms.WM_WINDOW_TYPE_SPLASH) {
+            debug!("Mapping but not tracking window {}", id);
+            return vec![MapUntrackedClient(id)]
+        }
+        // special case for dialog windows
+        if window_type.contains(&atoms.WM_WINDOW_TYPE_DIALOG) {
+            debug!("Window is dialog, managing but not tiling");
+            return vec![MapFloatingClient(id)]
+        }
+        // windows that cannot be resized cannot be tiled either
+        if !ovrd && state.conn.get_wm_size_hints(id).is_some_and(|hints| hints.is_fixed()) {
+            debug!("Window {} has a fixed size, managing but not tiling", id);
+            return vec![MapFloatingClient(id)]
+        }
+        // do not manage if window is not top-level
+        if ovrd {
+            debug!("Window is not top-level, mapping but not tracking");
+            return vec![MapUntrackedClient(id)]
+        }
+
+        vec![MapTrackedClient(id)]
+    } else {
+        error!("Unable to get type for window {}, mapping but not tracking", id);
+        vec![MapUntrackedClient(id)]
+    }
+}
+
+fn process_config_request<X: XConn>(
+    event: ConfigureRequestData, state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    debug!("On configure request for window {}", event.id);
+
+    // fields left out of the request keep their current value
+    let mut geom = if let Some(win) = state.desktop.client(event.id) {
+        win.xwindow.geom
+    } else if let Ok(geom) = state.conn.get_geometry(event.id) {
+        geom
+    } else {
+        warn!("Could not get geometry of window {}", event.id);
+        return vec![]
+    };
+    let config_window_geom = event.changes_geometry();
+
+    if let Some(y) = event.y {
+        geom.y = y;
+    }
+    if let Some(x) = event.x {
+        geom.x = x;
+    }
+    if let Some(width) = event.width {
+        geom.width = width;
+    }
+    if let Some(height) = event.height {
+        geom.height = height;
+    }
+    if event.stack_mode.is_some() {
+        debug!("Configure window stack mode");
+    }
+    if event.border_width.is_some() {
+        debug!("Configure window border width");
+    }
+    if event.sibling.is_some() {
+        debug!("Configure window sibling");
+    }
+
+    if let Some((ws, idx)) = state.desktop.retrieve(event.id) {
+        // if we are tiling the window
+        if ws.is_tiling() && ws[idx].is_tiled() {
+            if config_window_geom {
+                debug!("Workspace is tiling, rejecting request");
+                return vec![DenyConfigureRequest(event.id, geom)]
+            }
+            //todo: else honour other requests
+            return vec![]
+        }
+
+        if config_window_geom {
+            vec![ConfigureClient(event.id, geom)]
+        } else {
+            vec![]
+        }
+    } else {
+        debug!("Config request is for untracked window");
+        vec![ConfigureUntracked(event.id, geom)]
+    }
+}
+
+fn process_button_press<X: XConn>(
+    id: XWindowID, pt: Point, button: ButtonIndex, state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    debug!("Button press for window {}", id);
+    // a click ends a move or resize started without a button
+    if state.selected.is_some() {
+        return vec![EndMouseDrag]
+    }
+    if id == consts::NONE {
+        return vec![]
+    }
+
+    let mode = match button {
+        consts::BUTTON_INDEX_1 => MouseMode::Move,
+        consts::BUTTON_INDEX_3 => MouseMode::Resize(ResizeEdges::BOTTOM_RIGHT),
+        consts::BUTTON_INDEX_2 => {
+            debug!("Middle mouse button selected");
+            MouseMode::None
+        }
+        _ => {
+            warn!("Unhandled mouse button event");
+            MouseMode::None
+        }
+    };
+
+    let mut actions = vec![BeginMouseDrag(id, pt, mode)];
+
+    if !state.desktop.current().windows.is_focused(id) {
+        actions.push(ClientFocus(id));
+    }
+
+    actions
+}
+
+fn process_motion_notify<X: XConn>(pt: Point, state: WMState<'_, X>) -> Vec<EventAction> {
+    use EventAction::*;
 
+    if let Some(selected) = state.selected {
+        debug!("On motion notify");
+        match state.mousemode {
+            MouseMode::None => {
+                error!("Encountered no button press while grabbing mouse");
+                vec![]
+            }
+            MouseMode::Move => vec![MoveClient(selected, pt)],
+            MouseMode::Resize(_) => vec![ResizeClient(selected, pt)],
+        }
+    } else {
+        vec![]
     }
+}
 
-    todo!("process_map_request")
+/// Moves or resizes the window being dragged with the keyboard.
+fn process_drag_key<X: XConn>(keysym: KeySym, state: WMState<'_, X>) -> Vec<EventAction> {
+    use EventAction::*;
+
+    let selected = if let Some(selected) = state.selected {
+        selected
+    } else {
+        return vec![]
+    };
+    let step = config::KEYBOARD_DRAG_STEP;
+
+    match keysym {
+        keysym::XK_Left => vec![DragClientBy(selected, -step, 0)],
+        keysym::XK_Right => vec![DragClientBy(selected, step, 0)],
+        keysym::XK_Up => vec![DragClientBy(selected, 0, -step)],
+        keysym::XK_Down => vec![DragClientBy(selected, 0, step)],
+        keysym::XK_Return | keysym::XK_KP_Enter => vec![EndMouseDrag],
+        keysym::XK_Escape => vec![CancelDrag],
+        _ => vec![],
+    }
 }
 
+fn on_client_message<X: XConn>(
+    event: ClientMessageEvent, state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    debug!("On client message for window {}", event.window);
+
+    let atoms = state.conn.get_atoms();
+
+    let data = if let ClientMessageData::U32(data) = event.data {
+        data
+    } else {
+        warn!("Client message of type {} is not 32-bit", event.type_);
+        return vec![]
+    };
+
+    if event.type_ == atoms.WM_STATE {
+        debug!("Type is _NET_WM_STATE");
+        if state.desktop.retrieve(event.window).is_none() {
+            // unmanaged windows set the property themselves
+            debug!("Window {} is not managed", event.window);
+            return vec![]
+        }
+        let change = if let Some(change) = StateChange::from_action(data[0]) {
+            change
+        } else {
+            warn!("Unknown _NET_WM_STATE action {}", data[0]);
+            return vec![]
+        };
+        data[1..3].iter()
+            .filter(|&&atom| atom != consts::NONE)
+            .filter_map(|&atom| {
+                let window_state = NetWindowState::from_atom(atom, &atoms);
+                if window_state.is_none() {
+                    debug!("Unsupported state {}", atom);
+                }
+                window_state
+            })
+            .map(|window_state| ChangeClientState(event.window, window_state, change))
+            .collect()
+    } else if event.type_ == atoms.SHOWING_DESKTOP {
+        debug!("Type is _NET_SHOWING_DESKTOP");
+        vec![ShowDesktop(data[0] != 0)]
+    } else if event.type_ == atoms.CURRENT_DESKTOP {
+        debug!("Type is _NET_CURRENT_DESKTOP");
+        vec![GotoWorkspace(data[0] as usize)]
+    } else if event.type_ == atoms.WM_DESKTOP {
+        debug!("Type is _NET_WM_DESKTOP");
+        if state.desktop.retrieve(event.window).is_none() {
+            debug!("Window {} is not managed", event.window);
+            return vec![]
+        }
+        if data[0] == consts::ALL_DESKTOPS {
+            return vec![ChangeClientState(event.window, NetWindowState::Sticky, StateChange::Add)]
+        }
+        vec![ClientToWorkspace(event.window, data[0] as usize)]
+    } else if event.type_ == atoms.ACTIVE_WINDOW {
+        debug!("Type is _NET_ACTIVE_WINDOW");
+        if state.desktop.retrieve(event.window).is_none() {
+            debug!("Window {} is not managed", event.window);
+            return vec![]
+        }
+        let policy = match SourceIndication::from(data[0]) {
+            SourceIndication::Pager => config::PAGER_ACTIVATION,
+            _ => config::APPLICATION_ACTIVATION,
+        };
+        match policy {
+            ActivationPolicy::Focus => vec![ActivateClient(event.window)],
+            ActivationPolicy::MarkUrgent => vec![MarkClientUrgent(event.window)],
+            ActivationPolicy::Ignore => vec![],
+        }
+    } else if event.type_ == atoms.WM_PROTOCOLS && data[0] == atoms.WM_PING {
+        // pings are sent back to the root window, with the client in data[2]
+        // and the timestamp of the ping in data[1]
+        debug!("Type is _NET_WM_PING reply");
+        if state.desktop.retrieve(data[2]).is_none() {
+            debug!("Window {} is not managed", data[2]);
+            return vec![]
+        }
+        vec![ClientPong(data[2], data[1])]
+    } else if event.type_ == atoms.CLOSE_WINDOW {
+        debug!("Type is _NET_CLOSE_WINDOW");
+        if state.desktop.retrieve(event.window).is_none() {
+            debug!("Window {} is not managed", event.window);
+            return vec![]
+        }
+        vec![DestroyClient(event.window)]
+    } else if event.type_ == atoms.MOVERESIZE_WINDOW {
+        debug!("Type is _NET_MOVERESIZE_WINDOW");
+        process_moveresize_window(event.window, data, state)
+    } else if event.type_ == atoms.WM_MOVERESIZE {
+        debug!("Type is _NET_WM_MOVERESIZE");
+        process_wm_moveresize(event.window, data, state)
+    } else {
+        debug!("Unhandled type {}", event.type_);
+        vec![]
+    }
+}
+
+/// Works out the geometry asked for by a _NET_MOVERESIZE_WINDOW message.
+///
+/// The message is treated like a configure request, with the
+/// values it does not set taken from the window's current geometry.
+fn process_moveresize_window<X: XConn>(
+    id: XWindowID, data: [u32; 5], state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    let current = if let Some(win) = state.desktop.client(id) {
+        win.xwindow.geom
+    } else if let Ok(geom) = state.conn.get_geometry(id) {
+        geom
+    } else {
+        warn!("Could not get geometry of window {}", id);
+        return vec![]
+    };
+
+    let flags = data[0];
+    let value = |flag, idx: usize, default| {
+        if flags & flag != 0 {data[idx] as i32} else {default}
+    };
+    let geom = Geometry {
+        x: value(consts::NET_MOVERESIZE_WINDOW_X, 1, current.x),
+        y: value(consts::NET_MOVERESIZE_WINDOW_Y, 2, current.y),
+        width: value(consts::NET_MOVERESIZE_WINDOW_WIDTH, 3, current.width),
+        height: value(consts::NET_MOVERESIZE_WINDOW_HEIGHT, 4, current.height),
+    };
+
+    if let Some((ws, idx)) = state.desktop.retrieve(id) {
+        if ws.is_tiling() && ws[idx].is_tiled() {
+            debug!("Workspace is tiling, rejecting request");
+            vec![DenyConfigureRequest(id, geom)]
+        } else {
+            vec![ConfigureClient(id, geom)]
+        }
+    } else {
+        vec![ConfigureUntracked(id, geom)]
+    }
+}
+
+/// Starts or cancels an interactive move or resize asked for by a client,
+/// usually because its client-side titlebar or border was dragged.
+fn process_wm_moveresize<X: XConn>(
+    id: XWindowID, data: [u32; 5], state: WMState<'_, X>
+) -> Vec<EventAction> {
+    use EventAction::*;
+
+    let direction = data[2];
+    let button = data[3];
+
+    if direction == consts::NET_WM_MOVERESIZE_CANCEL {
+        return if state.selected == Some(id) {
+            vec![EndMouseDrag]
+        } else {
+            vec![]
+        }
+    }
+
+    if state.desktop.current().contains(id).is_none() {
+        debug!("Window {} is not on the current workspace", id);
+        return vec![]
+    }
+    if state.selected.is_some() {
+        debug!("A window is already being dragged");
+        return vec![]
+    }
+
+    let mode = match direction {
+        consts::NET_WM_MOVERESIZE_MOVE |
+        consts::NET_WM_MOVERESIZE_MOVE_KEYBOARD => MouseMode::Move,
+        _ => if let Some(edges) = ResizeEdges::from_moveresize(direction) {
+            MouseMode::Resize(edges)
+        } else {
+            warn!("Unknown _NET_WM_MOVERESIZE direction {}", direction);
+            return vec![]
+        }
+    };
+
+    let keyboard = direction == consts::NET_WM_MOVERESIZE_MOVE_KEYBOARD
+        || direction == consts::NET_WM_MOVERESIZE_SIZE_KEYBOARD
+        || button == consts::BUTTON_INDEX_ANY;
+
+    let mut actions = if keyboard {
+        vec![BeginKeyboardDrag(id, mode)]
+    } else {
+        let pt = Point {x: data[0] as i32, y: data[1] as i32};
+        vec![BeginMouseDrag(id, pt, mode)]
+    };
+
+    if !state.desktop.current().windows.is_focused(id) {
+        actions.push(ClientFocus(id));
+    }
+
+    actions
+}
+
+fn process_error<X: XConn>(error: XErrorEvent, state: WMState<'_, X>) -> Vec<EventAction> {
+    use EventAction::*;
+
+    if !error.is_missing_window() {
+        error!("X error: {}", error);
+        return vec![]
+    }
+
+    // the window was most likely destroyed before the request reached the server
+    if state.desktop.retrieve(error.resource).is_some() {
+        warn!("X error for destroyed client: {}", error);
+        vec![RemoveStaleClient(error.resource)]
+    } else {
+        debug!("X error for untracked window: {}", error);
+        vec![]
+    }
+}
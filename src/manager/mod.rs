@@ -2,89 +2,128 @@
 //! event loop in RaccoonWM.
 pub mod event;
 pub mod state;
+mod netstate;
+mod resize;
+mod showdesktop;
 
 pub(crate) use state::WMState;
+pub use event::EventAction;
 
-use xcb_util::{
-    ewmh::Connection,
-    cursor,
-};
+use std::collections::HashMap;
+
+use crate::x::consts::{self, cursor};
 
 use crate::utils;
 use crate::core::Client;
 use crate::types::{
     Direction, 
-    Geometry, 
+    FocusStealingPrevention,
+    Geometry,
+    NetWindowState,
+    StateChange,
+    Strut,
+    ResizeEdges,
+    WindowState,
+    Point,
+    Result,
+    WMError,
 };
 use crate::x::core::{
-    XCBConnection, 
+    XConn,
+    XError,
+    XEvent,
     XWindowID, 
-    ClientMessageData as CMData
+    MapState,
+    MapRequestData,
+    Timestamp,
 };
-use crate::x::{ewmh, Ewmh};
 use crate::desktop::{Desktop, Screen};
 use crate::layout::LayoutType;
 use crate::config;
 
+/// The name the window manager identifies itself with, set as
+/// _NET_WM_NAME on the _NET_SUPPORTING_WM_CHECK window.
+pub const WM_NAME: &str = "rcwm";
+
 /// Whether the mouse button is pressed.
-#[derive(Debug, Clone, Copy)]
-pub(super) enum MouseMode {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseMode {
     None,
     Move,
-    Resize,
+    /// Resizing by moving the given edges.
+    Resize(ResizeEdges),
 }
 
 /// The main manager struct that receives and responds to events.
 #[allow(dead_code)]
-pub struct WindowManager {
-    pub(crate) conn: XCBConnection,
+pub struct WindowManager<X: XConn> {
+    pub(crate) conn: X,
     pub(crate) desktop: Desktop,
     pub(crate) screen: Screen,
+    /// Dock windows and the space they reserve.
+    docks: HashMap<XWindowID, Strut>,
+    /// Desktop windows, from the bottom of the stack up.
+    desktop_windows: Vec<XWindowID>,
+    /// The clients hidden to show the desktop, if it is being shown.
+    showing_desktop: Option<Vec<XWindowID>>,
     root: i32,
     mousemode: MouseMode,
     selected: Option<XWindowID>,
+    /// The window last set as _NET_ACTIVE_WINDOW.
+    active: XWindowID,
     last_mouse_x: i32,
     last_mouse_y: i32,
+    /// Where the window being dragged with the keyboard started,
+    /// to put it back if the drag is cancelled.
+    drag_origin: Option<Geometry>,
+    /// The _NET_WM_SYNC_REQUEST state of the client being resized.
+    resize_sync: Option<resize::ResizeSync>,
+    /// The most recent server time seen in an event.
+    last_time: Timestamp,
+    /// The time of the last key or button press.
+    last_input: Timestamp,
     to_quit: bool,
 }
 
-impl WindowManager {
+impl<X: XConn> WindowManager<X> {
     /// Performs setup, registering for substructure redirect and substructure
     /// notify on the root window, grabbing mouse buttons and keys, etc.
-    pub fn register(conn: Connection, screen_idx: i32) -> Self {
-        let mut xconn = XCBConnection::new(conn, screen_idx);
+    pub fn register(conn: X, screen_idx: i32) -> Result<Self> {
+        let mut xconn = conn;
+        let atoms = xconn.get_atoms();
 
         let root_id = xconn.get_root_id();
 
         debug!("Got root id of {}", root_id);
 
         // register for substructure redirect and substructure notify on root window 
+        // if this fails, someone else has already selected substructure redirect
         xconn.change_window_attributes_checked(root_id, &utils::ROOT_ATTRS)
-            // we panic here because this is a fatal error
-            .unwrap_or_else(|_e| {
-                error!("Another window manager is running.");
-                std::process::exit(1)
-            });
+            .map_err(|_| WMError::OtherWMRunning)?;
+
+        xconn.set_supported(screen_idx, &atoms.supported());
+
+        // let clients know an EWMH compliant window manager is running
+        let check = xconn.create_hidden_window()?;
+        xconn.set_supporting_wm_check(root_id, check);
+        xconn.set_supporting_wm_check(check, check);
+        xconn.set_wm_name(check, WM_NAME);
 
-        xconn.set_supported(screen_idx, &[
-            xconn.atoms.WM_PROTOCOLS, 
-            xconn.atoms.WM_DELETE_WINDOW,
-            xconn.atoms.WM_TAKE_FOCUS,
-            xconn.atoms.WM_STATE,
-        ]);
+        // get the server time, so that focus can be set with a real
+        // timestamp before the first timed event arrives
+        xconn.change_window_attributes(check, &[(consts::CW_EVENT_MASK, consts::EVENT_MASK_PROPERTY_CHANGE)]);
+        xconn.touch_property(check, atoms.WM_NAME, atoms.UTF8_STRING);
+        let (time, pending) = wait_for_property_notify(&xconn, check)?;
+        xconn.set_timestamp(time);
 
-        xconn.grab_button(root_id, utils::ROOT_BUTTON_GRAB_MASK, xcb::BUTTON_INDEX_1, xcb::MOD_MASK_4, true);
-        xconn.grab_button(root_id, utils::ROOT_BUTTON_GRAB_MASK, xcb::BUTTON_INDEX_3, xcb::MOD_MASK_4, true);
+        xconn.grab_button(root_id, utils::ROOT_BUTTON_GRAB_MASK, consts::BUTTON_INDEX_1, consts::MOD_MASK_4, true);
+        xconn.grab_button(root_id, utils::ROOT_BUTTON_GRAB_MASK, consts::BUTTON_INDEX_3, consts::MOD_MASK_4, true);
 
-        for (mask, ks, _) in config::KEYBINDS {
-            xconn.grab_key(root_id, *mask, *ks);
+        for (mask, ks, _) in config::keybinds::<X>() {
+            xconn.grab_key(root_id, mask, ks);
         }
 
-        // fatal because this is the WM setup process
-        xconn.create_cursor(cursor::LEFT_PTR)
-            .unwrap_or_else(|_e| {
-                fatal!("Could not create cursor");
-        });
+        xconn.create_cursor(cursor::LEFT_PTR)?;
 
         xconn.set_cursor(root_id);
 
@@ -95,91 +134,142 @@ impl WindowManager {
         let mut new = Self {
             conn: xconn,
             desktop: Desktop::new(LayoutType::DTiled),
-            screen: screen,
+            screen,
+            docks: HashMap::new(),
+            desktop_windows: Vec::new(),
+            showing_desktop: None,
             root: screen_idx,
             mousemode: MouseMode::None,
             selected: None,
+            active: consts::NONE,
             last_mouse_x: 0,
             last_mouse_y: 0,
+            drag_origin: None,
+            resize_sync: None,
+            last_time: time,
+            last_input: consts::CURRENT_TIME,
             to_quit: false,
         };
 
+        new.publish_desktops();
+        new.publish_workarea();
+        new.conn.set_active_window(screen_idx, consts::NONE);
+        new.conn.set_showing_desktop(screen_idx, false);
+
         // find existing windows and map them
-        for &existing in &new.conn.query_tree(root_id).unwrap() {
+        for &existing in &new.conn.query_tree(root_id)? {
             let attr = if let Some(attr) = new.conn.get_window_attributes(existing) {
                 attr
             } else {
                 continue
             };
 
-            if attr.override_redirect() || attr.map_state() as u32 != xcb::MAP_STATE_VIEWABLE {
+            if attr.override_redirect || attr.map_state != MapState::Viewable {
                 continue
             }
 
             debug!("Mapping window {}", existing);
 
-            new.map_window(existing);
+            new.process_event(XEvent::MapRequest(MapRequestData::new(existing, root_id)));
         }
 
-        new
+        for event in pending {
+            new.process_event(event);
+        }
 
+        Ok(new)
     }
     
     /// Runs the window manager.
     /// 
     /// Contains the main event loop.
+    /// Errors encountered while handling events are logged,
+    /// and only returns an error if the connection to the X server is lost.
     //using a mutable reference statically ensures there is only one instance running
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> Result<()> {
         info!("Running WM");
 
-        self.desktop.current_mut().activate(&self.conn, &self.screen);
+        if let Err(e) = self.desktop.current_mut().activate(&self.conn, &self.screen) {
+            error!("Could not activate workspace: {}", e);
+        }
+        self.update_root_properties();
 
         loop {
-            let event = self.conn.next_event();
-
-            unsafe {
-                match event.response_type() & !0x80 {
-                    xcb::CONFIGURE_NOTIFY => self.on_config_notify(xcb::cast_event(&event)),
-                    xcb::CONFIGURE_REQUEST => self.on_config_request(xcb::cast_event(&event)),
-                    xcb::MAP_REQUEST => self.on_map_request(xcb::cast_event(&event)),
-                    xcb::UNMAP_NOTIFY => self.on_unmap_notify(xcb::cast_event(&event)),
-                    xcb::DESTROY_NOTIFY => self.on_destroy_notify(xcb::cast_event(&event)),
-                    xcb::ENTER_NOTIFY => self.on_enter_notify(xcb::cast_event(&event)),
-                    xcb::LEAVE_NOTIFY => {debug!("Leave notify");}
-                    xcb::MOTION_NOTIFY => self.on_motion_notify(xcb::cast_event(&event)),
-                    xcb::REPARENT_NOTIFY => {debug!("Reparent notify");}
-                    xcb::PROPERTY_NOTIFY => self.on_property_notify(xcb::cast_event(&event)),
-                    xcb::KEY_PRESS => self.on_key_press(xcb::cast_event(&event)),
-                    xcb::BUTTON_PRESS => self.on_button_press(xcb::cast_event(&event)),
-                    xcb::BUTTON_RELEASE => self.on_button_release(xcb::cast_event(&event)),
-                    xcb::CLIENT_MESSAGE => self.on_client_message(xcb::cast_event(&event)),
-                    unhandled => {
-                        debug!("Unhandled event {}", unhandled);
-                    }
+            match self.conn.next_event() {
+                Ok(event) => self.process_event(event),
+                Err(XError::ConnectionClosed) => {
+                    error!("The X server closed the connection, exiting");
+                    return Err(XError::ConnectionClosed.into())
                 }
+                Err(e) => error!("Could not get next event: {}", e),
             }
 
-            self.update_windows();
-
             if self.to_quit {
                 info!("Quitting!");
-                break;
+                return Ok(())
+            }
+        }
+    }
+
+    /// Processes a single event.
+    /// 
+    /// Works out the actions to take in response to the event,
+    /// then executes them in order.
+    pub fn process_event(&mut self, event: XEvent) {
+        // keep the last known server time over events that carry none
+        if let Some(time) = event.time().filter(|&time| time != consts::CURRENT_TIME) {
+            self.last_time = time;
+            self.conn.set_timestamp(time);
+            if let XEvent::KeyPress(_) | XEvent::ButtonPress(_) = event {
+                self.last_input = time;
+            }
+            // Pings are timed against the server clock, so catch up
+            // before the event is handled: a close request arriving
+            // after the timeout must already see the client as frozen.
+            self.check_pings();
+        }
+
+        let actions = EventAction::from_xevent(event, self.state());
+
+        for action in actions {
+            if let Err(e) = self.handle_action(action) {
+                error!("{}", e);
             }
         }
+
+        self.update_windows();
+        self.desktop.restack(&self.conn);
+        self.update_root_properties();
+    }
+
+    /// Returns a reference to the underlying connection.
+    pub fn conn(&self) -> &X {
+        &self.conn
+    }
+
+    /// Returns a view into the desktop.
+    pub fn desktop(&self) -> &Desktop {
+        &self.desktop
     }
 
     /// Go to workspace `idx`.
     pub fn goto_workspace(&mut self, idx: usize) {
-        self.desktop.goto(&self.conn, &self.screen, idx);
+        if let Err(e) = self.desktop.goto(&self.conn, &self.screen, idx) {
+            error!("Could not go to workspace {}: {}", idx, e);
+        }
     }
 
     pub fn cycle_workspace(&mut self, direction: Direction) {
-        self.desktop.cycle_workspace(&self.conn, &self.screen, direction);
+        if let Err(e) = self.desktop.cycle_workspace(&self.conn, &self.screen, direction) {
+            error!("Could not cycle workspace: {}", e);
+        }
     }
 
     /// Sends the focused window to workspace `idx`.
     pub fn send_window_to(&mut self, idx: usize) {
-        self.desktop.send_window_to(&self.conn, &self.screen, idx);
+        if let Err(e) = self.desktop.send_window_to(&self.conn, &self.screen, idx) {
+            error!("Could not send window to workspace {}: {}", idx, e);
+        }
     }
 
     /// Cycles the focus in the given direction.
@@ -188,12 +278,66 @@ impl WindowManager {
     }
 
     pub fn cycle_master(&mut self, direction: Direction) {
-        self.desktop.current_mut().cycle_master(&self.conn, &self.screen, direction);
+        if let Err(e) = self.desktop.current_mut().cycle_master(&self.conn, &self.screen, direction) {
+            error!("Could not cycle master: {}", e);
+        }
+    }
+
+    /// Changes the layout of the current workspace.
+    pub fn set_layout(&mut self, layout: LayoutType) {
+        if let Err(e) = self.desktop.current_mut().set_layout(layout, &self.conn, &self.screen) {
+            error!("Could not set layout {:?}: {}", layout, e);
+        }
     }
 
     /// Toggles the focused window into floating or tiled.
     pub fn toggle_focused_state(&mut self) {
-        self.desktop.current_mut().toggle_focused_state(&self.conn, &self.screen)
+        if let Err(e) = self.desktop.current_mut().toggle_focused_state(&self.conn, &self.screen) {
+            error!("Could not toggle window state: {}", e);
+        }
+
+        // floating windows are stacked on top
+        if let Some(win) = self.desktop.current().windows.focused() {
+            if win.is_floating() {
+                let id = win.id();
+                self.desktop.clients.raise(id);
+            }
+        }
+    }
+
+    /// Asks a client to close.
+    ///
+    /// Clients that did not answer their last _NET_WM_PING in time
+    /// are killed instead. Other clients are pinged, so that closing
+    /// them again kills them if they turn out to be frozen.
+    pub fn close_client(&mut self, id: XWindowID) {
+        let time = self.last_time;
+        let window = if let Some(window) = self.desktop.client_mut(id) {
+            window
+        } else {
+            warn!("Cannot close untracked window {}", id);
+            return
+        };
+
+        if !window.is_responding() {
+            info!("Window {} is not responding, killing it", id);
+            self.conn.kill_client(id);
+            return
+        }
+
+        self.conn.destroy_window(window);
+        if window.supports(self.conn.get_atoms().WM_DELETE_WINDOW) {
+            window.ping(&self.conn, time);
+        }
+    }
+
+    /// Checks whether a client is still responding, by sending it a _NET_WM_PING.
+    pub fn ping_client(&mut self, id: XWindowID) -> Result<()> {
+        let time = self.last_time;
+        self.desktop.client_mut(id)
+            .ok_or(WMError::NoSuchWindow(id))?
+            .ping(&self.conn, time);
+        Ok(())
     }
 
     pub fn quit(&mut self) {
@@ -210,299 +354,425 @@ impl WindowManager {
         }
     }
 
-    fn on_config_notify(&mut self, event: &xcb::ConfigureNotifyEvent) {
-        if event.window() == self.screen.xwindow.id {
-            debug!("On configure notify for root window");
-
-            self.screen.xwindow.geom.x = event.x() as i32;
-            self.screen.xwindow.geom.y = event.y() as i32;
-            self.screen.xwindow.geom.width = event.width() as i32;
-            self.screen.xwindow.geom.height = event.height() as i32;
-
-            self.desktop.current_mut().deactivate(&self.conn);
-            self.desktop.current_mut().activate(&self.conn, &self.screen);
-        }
-    }
-
-    fn on_config_request(&mut self, event: &xcb::ConfigureRequestEvent) {
-        debug!("On configure request for window {}", event.window());
-
-        let mut geom = Geometry::from((0, 0, 160, 100));
-        let mut config_window_geom = false;
-        
-        if xcb::CONFIG_WINDOW_Y as u16 & event.value_mask() != 0 {
-            config_window_geom = true;
-            geom.y = event.y() as i32;
-        }
-        if xcb::CONFIG_WINDOW_X as u16 & event.value_mask() != 0 {
-            config_window_geom = true;
-            geom.x = event.x() as i32;
-        }
-        if xcb::CONFIG_WINDOW_WIDTH as u16 & event.value_mask() != 0 {
-            config_window_geom = true;
-            geom.width = event.width() as i32;
-        }
-        if xcb::CONFIG_WINDOW_HEIGHT as u16 & event.value_mask() != 0 {
-            config_window_geom = true;
-            geom.height = event.height() as i32;
-        }
-        if xcb::CONFIG_WINDOW_STACK_MODE as u16 & event.value_mask() != 0 {
-            debug!("Configure window stack mode");
-        }
-        if xcb::CONFIG_WINDOW_BORDER_WIDTH as u16 & event.value_mask() != 0 {
-            debug!("Configure window border width");
-        }
-        if xcb::CONFIG_WINDOW_SIBLING as u16 & event.value_mask() != 0 {
-            debug!("Configure window sibling");
+    /// Whether a newly mapped window may take the focus, according to
+    /// its _NET_WM_USER_TIME and `config::FOCUS_STEALING_PREVENTION`.
+    ///
+    /// A window may only take the focus if the user has not given any
+    /// input to the focused window since the window's user time.
+    fn should_focus_new(&self, id: XWindowID) -> bool {
+        if config::FOCUS_STEALING_PREVENTION == FocusStealingPrevention::Off {
+            return true
         }
+        let focused = if let Some(win) = self.desktop.current().windows.focused() {
+            win.id()
+        } else {
+            return true
+        };
 
-        if let Some((ws, idx)) = self.desktop.retrieve_mut(event.window()) {
-
-            let is_tiling = ws.is_tiling();
-            
-            let ref mut window = ws[idx];
-
-            // if we are tiling the window
-            if is_tiling && window.is_tiled() {
-                if config_window_geom {
-                    // reject the request
-                    debug!("Workspace is tiling, rejecting request");
-                    // send back unchanged geometry
-                    window.update_geometry(&self.conn);
-                    // however, set initial geom for when floating
-                    window.set_initial_geom(geom);
-                }
-                //todo: else honour other requests
-                return
-            }
-
-            if config_window_geom {
-                window.set_and_update_geometry(&self.conn, geom);
-                debug!("{:#?}", window);
+        match self.conn.get_wm_user_time(id) {
+            // the client asks not to be focused on map
+            Some(0) => false,
+            Some(time) => {
+                let last_input = self.conn.get_wm_user_time(focused)
+                    .map_or(self.last_input, |t| later(t, self.last_input));
+                later(time, last_input) == time
             }
-        } else {
-            debug!("Config request is for untracked window");
-            self.conn.set_geometry(event.window(), geom);
+            None => config::FOCUS_STEALING_PREVENTION != FocusStealingPrevention::Strict,
         }
     }
 
-    fn on_map_request(&mut self, event: &xcb::MapRequestEvent) {
-        if self.desktop.retrieve(event.window()).is_none() {
-            debug!("On map request for window {}", event.window());
-
-            self.map_window(event.window());
-        } else {
-            debug!("Map request for existing window");
+    /// Marks clients that did not answer a _NET_WM_PING in time as not responding.
+    fn check_pings(&mut self) {
+        let now = self.last_time;
+        for win in self.desktop.clients_mut() {
+            if win.check_ping(now) {
+                warn!("Window {} is not responding, close it again to kill it", win.id());
+            }
         }
     }
 
-    fn map_window(&mut self, window: XWindowID) {
-        if let Some(window_type) = self.conn.get_window_type(window) {
-            // windows to not manage at all
-            if !(window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_NORMAL)||
-                window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_UTILITY)||
-                window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_DIALOG) ||
-                window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_TOOLBAR)
-                ) || window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_SPLASH) {
-                debug!("Mapping but not tracking window {}", window);
-
-                self.map_untracked(window);
-                debug!("Geometry of window {}: {:?}", 
-                    window, self.conn.get_geometry(window).unwrap()
-                );
-                return
-            }
-            // special case for dialog windows
-            if window_type.contains(&self.conn.atoms.WM_WINDOW_TYPE_DIALOG) {
-                debug!("Window is dialog, managing but not tiling");
-                let mut dialog = Client::floating(window, &self.conn);
-                dialog.map(&self.conn);
-                dialog.configure(&self.conn, &utils::stack_above());
-                self.desktop.current_mut().push_window(dialog);
-                return
-            }
-            // do not manage if window is not top-level
-            if let Some(attrs) = self.conn.get_window_attributes(window) {
-                if attrs.override_redirect() {
-                    debug!("Window is not top-level, mapping but not tracking");
-                    self.map_untracked(window);
-                    debug!("Geometry of window {}: {:?}", 
-                    window, self.conn.get_geometry(window).unwrap()
-                );
-                    return
-                }
-            }
-            self.desktop.current_mut().add_window(&self.conn, &self.screen, window);
-        } else {
-            error!("Unable to get type for window {}, mapping but not tracking", window);
-            self.conn.map_window(window);
+    /// Brings the EWMH properties on the root window up to date
+    /// with the internal state of the window manager.
+    fn update_root_properties(&mut self) {
+        if self.desktop.clients.take_changed() {
+            self.publish_client_list();
         }
-    }
 
-    fn map_untracked(&mut self, window: XWindowID) {
-        let mut win = Client::floating(window, &self.conn);
-        win.map(&self.conn);
+        let active = self.desktop.current().windows.focused()
+            .map(|win| win.id())
+            .unwrap_or(consts::NONE);
+        if active != self.active {
+            self.active = active;
+            self.conn.set_active_window(self.root, active);
+        }
     }
 
-    fn on_unmap_notify(&mut self, event: &xcb::UnmapNotifyEvent) {
-        debug!("On unmap notify");
-        self.unmap_window(event.window());
+    /// Sets the number, names and current index of the workspaces on the root window.
+    fn publish_desktops(&self) {
+        self.conn.set_number_of_desktops(self.root, self.desktop.workspace_count() as u32);
+        self.conn.set_desktop_names(self.root, &self.desktop.names());
+        self.conn.set_current_desktop(self.root, self.desktop.current_idx() as u32);
     }
 
-    fn on_destroy_notify(&mut self, event: &xcb::DestroyNotifyEvent) {
-        debug!("On destroy notify");
-        self.unmap_window(event.window());
+    /// Sets _NET_WORKAREA to the work area of the screen, for every workspace.
+    fn publish_workarea(&self) {
+        let areas = vec![self.screen.workarea(); self.desktop.workspace_count()];
+        self.conn.set_workarea(self.root, &areas);
     }
 
-    fn unmap_window(&mut self, window: XWindowID) {
-        if let Some((ws, idx)) = self.desktop.retrieve_mut(window) {
-            debug!("Unmap notify for window {}", window);
-            ws.del_window(&self.conn, &self.screen, window, idx);
-        } else {
-            debug!("Unmap notify for untracked window {}", window);
+    /// Recalculates the space reserved by docks, relayouting
+    /// the current workspace if it changed.
+    fn update_workarea(&mut self) -> Result<()> {
+        if !self.screen.set_struts(self.docks.values()) {
+            return Ok(())
         }
-    }
+        debug!("Work area is now {:?}", self.screen.workarea());
 
-    fn on_enter_notify(&mut self, event: &xcb::EnterNotifyEvent) {
-        
-        if !(event.mode() as u32 == xcb::NOTIFY_MODE_NORMAL ||
-             event.mode() as u32 == xcb::NOTIFY_MODE_UNGRAB) {
-            return
-        }
-        
-        if self.desktop.current().contains(event.event()).is_some() {
-            debug!("On enter notify for {}", event.event());
-            self.desktop.current_mut().focus_window(&self.conn, &self.screen, event.event())
-        } else {
-            warn!("On enter notify for untracked window {}", event.event());
-        }
+        self.publish_workarea();
+        self.desktop.current_mut().relayout(&self.conn, &self.screen)
     }
 
-    fn on_key_press(&mut self, event: &xcb::KeyPressEvent) {
-        debug!("Button press for window {}", event.event());
+    /// Sets _NET_CLIENT_LIST and _NET_CLIENT_LIST_STACKING on the root window.
+    fn publish_client_list(&self) {
+        let clients = &self.desktop.clients;
 
-        let (modm, keysym) = self.conn.lookup_keysym(event);
+        self.conn.set_client_list(self.root, clients.mapping_order());
+        self.conn.set_client_list_stacking(self.root, clients.stacking_order());
+    }
 
-        if let Some((_, _, cb)) = utils::find_keybind(modm, keysym) {
-            debug!("Found keybind");
-            cb(self);
-            return
-        }
+    /// Executes an action.
+    /// 
+    /// This is the only place where events cause changes in state.
+    fn handle_action(&mut self, action: EventAction) -> Result<()> {
+        use EventAction::*;
 
-        debug!("No keybind found for key press event");
-    }
+        debug!("Handling action {:?}", action);
 
-    fn on_button_press(&mut self, event: &xcb::ButtonPressEvent) {
-        debug!("Button press for window {}", event.event());
-        if event.child() == xcb::NONE {
-            return
+        // mapping or activating a window stops showing the desktop
+        if self.showing_desktop.is_some() && matches!(action,
+            MapTrackedClient(_) | MapFloatingClient(_) | ActivateClient(_) | GotoWorkspace(_)
+        ) {
+            self.show_desktop(false)?;
         }
 
-        self.selected = Some(event.child());
-
-        self.conn.grab_pointer(self.screen.xwindow.id, utils::ROOT_POINTER_GRAB_MASK);
+        match action {
+            ClientFocus(id) => {
+                self.desktop.current_mut().focus_window(&self.conn, &self.screen, id);
+            }
+            ClientToWorkspace(id, idx) => {
+                self.desktop.move_window(&self.conn, &self.screen, id, idx)?;
+            }
+            GotoWorkspace(idx) => self.desktop.goto(&self.conn, &self.screen, idx)?,
+            ActivateClient(id) => {
+                let idx = self.desktop.workspace_of(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                debug!("Activating window {} on workspace {}", id, idx);
+                self.change_client_state(id, NetWindowState::Hidden, StateChange::Remove)?;
+                self.change_client_state(id, NetWindowState::Urgent, StateChange::Remove)?;
+                self.desktop.goto(&self.conn, &self.screen, idx)?;
+                self.desktop.current_mut().focus_window(&self.conn, &self.screen, id);
+                self.conn.configure_window(id, &utils::stack_above());
+                self.desktop.clients.raise(id);
+            }
+            MarkClientUrgent(id) => {
+                self.change_client_state(id, NetWindowState::Urgent, StateChange::Add)?;
+            }
+            DestroyClient(id) => self.close_client(id),
+            ClientPong(id, time) => {
+                debug!("Window {} answered ping at {}", id, time);
+                self.desktop.client_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?
+                    .pong(time);
+            }
+            MapTrackedClient(id) => {
+                let focus = self.should_focus_new(id);
+                if focus {
+                    self.desktop.current_mut().add_window(&self.conn, &self.screen, id)?;
+                } else {
+                    debug!("Keeping window {} from stealing focus", id);
+                    self.desktop.current_mut().add_window_unfocused(&self.conn, &self.screen, id)?;
+                }
+                self.desktop.clients.insert(id);
+                self.conn.set_wm_desktop(id, self.desktop.current_idx() as u32);
+                self.apply_initial_states(id)?;
+                if !focus {
+                    self.change_client_state(id, NetWindowState::Urgent, StateChange::Add)?;
+                }
+            }
+            MapFloatingClient(id) => {
+                let mut dialog = Client::floating(id, &self.conn);
+                dialog.xwindow.set_geometry_conn(&self.conn);
+                let geom = dialog.requested_geometry(dialog.xwindow.geom);
+                if geom != dialog.xwindow.geom {
+                    dialog.set_and_update_geometry(&self.conn, geom);
+                }
+                dialog.map(&self.conn);
+                dialog.configure(&self.conn, &utils::stack_above());
+                self.desktop.current_mut().push_window(dialog);
+                self.desktop.clients.insert(id);
+                self.conn.set_wm_desktop(id, self.desktop.current_idx() as u32);
+                self.apply_initial_states(id)?;
+            }
+            MapUntrackedClient(id) => {
+                let mut win = Client::floating(id, &self.conn);
+                win.map(&self.conn);
+            }
+            MapDock(id) => {
+                // watch for strut changes, and for the dock going away
+                self.conn.change_window_attributes(id, &[(
+                    consts::CW_EVENT_MASK,
+                    consts::EVENT_MASK_PROPERTY_CHANGE | consts::EVENT_MASK_STRUCTURE_NOTIFY
+                )]);
+                self.conn.map_window(id);
+
+                let strut = self.conn.get_wm_strut(id).unwrap_or_default();
+                debug!("Dock {} reserves {:?}", id, strut);
+                self.docks.insert(id, strut);
+                self.update_workarea()?;
+            }
+            UpdateDockStrut(id) => {
+                let strut = self.conn.get_wm_strut(id).unwrap_or_default();
+                debug!("Dock {} now reserves {:?}", id, strut);
+                self.docks.insert(id, strut);
+                self.update_workarea()?;
+            }
+            RemoveDock(id) => {
+                debug!("Dock {} went away", id);
+                self.docks.remove(&id);
+                self.update_workarea()?;
+            }
+            UpdateClientSizeHints(id) => {
+                debug!("Size hints of window {} changed", id);
+                let (ws, idx) = self.desktop.retrieve_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                ws[idx].update_size_hints(&self.conn);
+                if ws[idx].is_tiled() {
+                    ws.relayout(&self.conn, &self.screen)?;
+                }
+            }
+            UpdateClientIcon(id) => {
+                debug!("Icons of window {} changed", id);
+                self.desktop.client_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?
+                    .update_icons(&self.conn);
+            }
+            MapDesktopWindow(id) => self.map_desktop_window(id),
+            PlaceDesktopWindow(id) => self.place_desktop_window(id),
+            RemoveDesktopWindow(id) => {
+                debug!("Desktop window {} went away", id);
+                self.desktop_windows.retain(|&win| win != id);
+            }
+            ShowDesktop(show) => self.show_desktop(show)?,
+            ExpectedUnmap(id) => {
+                self.desktop.client_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?
+                    .unmap_received();
+            }
+            UnmapClient(id) => {
+                let (ws, idx) = self.desktop.retrieve_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                debug!("Unmapping window {}", id);
+                let mut window = ws.del_window(&self.conn, &self.screen, id, idx)?;
+                window.change_attributes(&self.conn, &utils::disable_events());
+                window.set_mapped_state(&self.conn, WindowState::Withdrawn);
+                self.desktop.clients.remove(id);
+            }
+            RemoveStaleClient(id) => {
+                let (ws, idx) = self.desktop.retrieve_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                info!("Window {} no longer exists, removing it", id);
+                ws.del_window(&self.conn, &self.screen, id, idx)?;
+                self.desktop.clients.remove(id);
+            }
+            ConfigureClient(id, geom) => {
+                let (ws, idx) = self.desktop.retrieve_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                let geom = ws[idx].requested_geometry(geom);
+                ws[idx].set_and_update_geometry(&self.conn, geom);
+            }
+            DenyConfigureRequest(id, geom) => {
+                let (ws, idx) = self.desktop.retrieve_mut(id)
+                    .ok_or(WMError::NoSuchWindow(id))?;
+                let window = &mut ws[idx];
+                // send back unchanged geometry
+                window.update_geometry(&self.conn);
+                // however, set initial geom for when floating
+                window.set_initial_geom(geom);
+            }
+            ConfigureUntracked(id, geom) => self.conn.set_geometry(id, geom),
+            RestackWindow(id, mode) => {
+                self.conn.configure_window(id, &[(consts::CONFIG_WINDOW_STACK_MODE as u16, mode)]);
+
+                if mode == consts::STACK_MODE_ABOVE {
+                    self.desktop.clients.raise(id);
+                } else if mode == consts::STACK_MODE_BELOW {
+                    self.desktop.clients.lower(id);
+                    self.lower_desktop_windows();
+                }
+            }
+            ScreenReconfigure(geom) => {
+                debug!("Root window reconfigured");
+
+                self.screen.xwindow.geom = geom;
+                self.screen.set_struts(self.docks.values());
+                self.publish_workarea();
+                for &id in &self.desktop_windows {
+                    self.place_desktop_window(id);
+                }
 
-        self.last_mouse_x = event.root_x() as i32;
-        self.last_mouse_y = event.root_y() as i32;
+                self.desktop.current_mut().deactivate(&self.conn);
+                self.desktop.current_mut().activate(&self.conn, &self.screen)?;
+            }
+            BeginMouseDrag(id, pt, mode) => {
+                self.selected = Some(id);
 
-        if !self.desktop.current().windows.is_focused(event.child()) {
-            self.desktop.current_mut().focus_window(&self.conn, &self.screen, event.child());
-        }
+                self.conn.grab_pointer(self.screen.xwindow.id, utils::ROOT_POINTER_GRAB_MASK);
 
-        match event.detail() as u32 {
-            xcb::BUTTON_INDEX_1 => {
-                self.mousemode = MouseMode::Move;
+                self.last_mouse_x = pt.x;
+                self.last_mouse_y = pt.y;
+                self.mousemode = mode;
+                if let MouseMode::Resize(_) = mode {
+                    self.begin_resize_sync(id);
+                }
             }
-            xcb::BUTTON_INDEX_3 => {
-                self.mousemode = MouseMode::Resize;
+            BeginKeyboardDrag(id, mode) => {
+                // there is no button press to take the position from
+                let pt = self.conn.query_pointer(self.screen.xwindow.id)?.location;
+                let geom = self.desktop.client(id)
+                    .ok_or(WMError::NoSuchWindow(id))?
+                    .xwindow.geom;
+                self.selected = Some(id);
+                self.drag_origin = Some(geom);
+
+                self.conn.grab_pointer(self.screen.xwindow.id, utils::ROOT_POINTER_MOTION_MASK);
+                self.conn.grab_keyboard(self.screen.xwindow.id);
+
+                self.last_mouse_x = pt.x;
+                self.last_mouse_y = pt.y;
+                self.mousemode = mode;
+                if let MouseMode::Resize(_) = mode {
+                    self.begin_resize_sync(id);
+                }
             }
-            xcb::BUTTON_INDEX_2 => {
-                debug!("Middle mouse button selected");
+            MoveClient(id, pt) => self.drag_client(id, pt)?,
+            ResizeClient(id, pt) => self.drag_client(id, pt)?,
+            DragClientBy(id, dx, dy) => self.drag_client_by(id, dx, dy)?,
+            EndMouseDrag => self.end_drag()?,
+            CancelDrag => {
+                if let (Some(id), Some(geom)) = (self.selected, self.drag_origin) {
+                    debug!("Cancelling drag of window {}", id);
+                    self.desktop.client_mut(id)
+                        .ok_or(WMError::NoSuchWindow(id))?
+                        .set_and_update_geometry(&self.conn, geom);
+                }
+                self.end_drag()?;
             }
-            _ => {
-                warn!("Unhandled mouse button event");
+            SyncAlarmTriggered(alarm, value) => self.resize_sync_alarm(alarm, value)?,
+            RunKeybind(event) => {
+                debug!("Key press with keysym {}", event.keysym);
+
+                if let Some((_, _, cb)) = utils::find_keybind(event.mask, event.keysym) {
+                    debug!("Found keybind");
+                    cb(self);
+                } else {
+                    debug!("No keybind found for key press event");
+                }
+            }
+            ChangeClientState(id, state, change) => {
+                self.change_client_state(id, state, change)?;
+            }
+            unhandled => {
+                warn!("Unhandled action {:?}", unhandled);
             }
-
         }
+
+        Ok(())
     }
 
-    fn on_button_release(&mut self, event: &xcb::ButtonReleaseEvent) {
-        debug!("Button release for {}", event.event());
+    /// Moves or resizes a client to follow the pointer.
+    fn drag_client(&mut self, id: XWindowID, pt: Point) -> Result<()> {
+        let dx = pt.x - self.last_mouse_x;
+        let dy = pt.y - self.last_mouse_y;
 
-        self.selected = None;
-        self.mousemode = MouseMode::None;
+        self.last_mouse_x = pt.x;
+        self.last_mouse_y = pt.y;
 
-        self.conn.ungrab_pointer();
+        self.drag_client_by(id, dx, dy)
     }
 
-    fn on_motion_notify(&mut self, event: &xcb::MotionNotifyEvent) {
-        if let Some(selected) = self.selected {
-            // focus the window
-            self.desktop.current_mut().focus_window(&self.conn, &self.screen, selected);
-            // stack the window on top
-            self.desktop.current_mut().windows.focused().unwrap()
-                .configure(&self.conn, &utils::stack_above());
-            debug!("On motion notify");
-
-            let dx = event.root_x() as i32 - self.last_mouse_x;
-            let dy = event.root_y() as i32 - self.last_mouse_y;
-
-            self.last_mouse_x = event.root_x() as i32;
-            self.last_mouse_y = event.root_y() as i32;
-
-            if let Some(idx) = self.desktop.current().windows.get_idx(selected) {
-                let selected = self.desktop.current_mut().windows.get_mut(idx).unwrap();
-                match self.mousemode {
-                    MouseMode::None => {
-                        error!("Encountered no button press while grabbing mouse")
-                    }
-                    MouseMode::Move => {
-                        selected.do_move(&self.conn, &self.screen, dx, dy);
-                        if selected.is_tiled() {
-                            self.desktop.current_mut().toggle_focused_state(&self.conn, &self.screen);
-                            self.desktop.current_mut().relayout(&self.conn, &self.screen);
-                            assert!(self.desktop.current().is_tiling());
-                        }
-                    }
-                    MouseMode::Resize => {
-                        selected.do_resize(&self.conn, &self.screen, dx, dy);
-                        if selected.is_tiled() {
-                            self.desktop.current_mut().toggle_focused_state(&self.conn, &self.screen);
-                            self.desktop.current_mut().relayout(&self.conn, &self.screen);
-                            assert!(self.desktop.current().is_tiling());
-                        }
-                    }
-                }
-            }
+    /// Moves or resizes the client being dragged by the given amounts.
+    fn drag_client_by(&mut self, id: XWindowID, dx: i32, dy: i32) -> Result<()> {
+        let mode = self.mousemode;
+        // focus the window
+        self.desktop.current_mut().focus_window(&self.conn, &self.screen, id);
+        // stack the window on top
+        if let Some(focused) = self.desktop.current().windows.focused() {
+            focused.configure(&self.conn, &utils::stack_above());
+            let id = focused.id();
+            self.desktop.clients.raise(id);
+        }
 
-        } else {
-            return
+        let synced = self.is_resize_synced();
+        let selected = self.desktop.current_mut().windows.lookup_mut(id)
+            .ok_or(WMError::NoSuchWindow(id))?;
+        match mode {
+            MouseMode::Move => selected.do_move(&self.conn, &self.screen, dx, dy),
+            MouseMode::Resize(edges) if synced => {
+                // the client is only sent its new size once it has caught up
+                selected.resize_by(edges, dx, dy);
+            }
+            MouseMode::Resize(edges) => {
+                selected.do_resize(&self.conn, &self.screen, edges, dx, dy)
+            }
+            MouseMode::None => {}
         }
+        let tiled = selected.is_tiled();
+        if synced && matches!(mode, MouseMode::Resize(_)) {
+            self.configure_synced(id)?;
+        }
+        if tiled {
+            self.desktop.current_mut().toggle_focused_state(&self.conn, &self.screen)?;
+            self.desktop.current_mut().relayout(&self.conn, &self.screen)?;
+        }
+
+        Ok(())
     }
 
-    /// Handles a client message.
-    /// 
-    /// Should normally be used for updating EWMH stuff.
-    fn on_client_message(&mut self, event: &xcb::ClientMessageEvent) {
-        debug!("On client message for window {}", event.window());
-        if event.type_() == self.conn.atoms.WM_STATE {
-            debug!("Type is WM_STATE");
-            ewmh::handle_wm_state(
-                &self.conn, 
-                self.desktop.current_mut(), 
-                event.window(),
-                CMData::from_event(event)
-            )
-        } else {
-            debug!("Unhandled type {}", event.type_());
+    /// Stops dragging the selected client, releasing the grabs taken for it.
+    fn end_drag(&mut self) -> Result<()> {
+        if let Some(id) = self.selected {
+            if let Some(win) = self.desktop.client_mut(id) {
+                win.end_resize();
+            }
+            self.end_resize_sync(id)?;
         }
+        self.selected = None;
+        self.mousemode = MouseMode::None;
 
+        self.conn.ungrab_pointer();
+        if self.drag_origin.take().is_some() {
+            self.conn.ungrab_keyboard();
+        }
+        Ok(())
     }
+}
+
+/// Returns the later of two server timestamps, allowing for wraparound.
+fn later(a: Timestamp, b: Timestamp) -> Timestamp {
+    if (a.wrapping_sub(b) as i32) >= 0 { a } else { b }
+}
 
-    fn on_property_notify(&mut self, event: &xcb::PropertyNotifyEvent) {
-        let window = event.window();
-        if let Some(win) = self.desktop.current_mut().windows.lookup(window) {
-            debug!("Property change for window {:#?}", win);
+/// Waits for a PropertyNotify on the given window, returning its timestamp
+/// along with the events received before it, which are left for later.
+fn wait_for_property_notify<X: XConn>(conn: &X, window: XWindowID) -> Result<(Timestamp, Vec<XEvent>)> {
+    let mut pending = Vec::new();
+
+    loop {
+        match conn.next_event() {
+            Ok(XEvent::PropertyNotify(event)) if event.id == window => return Ok((event.time, pending)),
+            Ok(event) => pending.push(event),
+            Err(XError::ConnectionClosed) => return Err(XError::ConnectionClosed.into()),
+            Err(e) => error!("Could not get next event: {}", e),
         }
     }
-}
\ No newline at end of file
+}
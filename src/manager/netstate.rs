@@ -0,0 +1,240 @@
+//! Handling of _NET_WM_STATE transitions.
+//!
+//! States can be changed by clients through client messages,
+//! or by the user through keybinds. Either way, the change goes
+//! through `WindowManager::change_client_state`, which updates
+//! the client's state, applies its effects, and sets _NET_WM_STATE.
+
+use crate::types::{
+    BorderStyle,
+    NetWindowState,
+    StateChange,
+    Result,
+    WMError,
+};
+use crate::x::core::{XConn, XWindowID};
+use crate::x::consts;
+
+use super::WindowManager;
+
+impl<X: XConn> WindowManager<X> {
+    /// Adds, removes or toggles a state on a managed client.
+    pub fn change_client_state(&mut self,
+        id: XWindowID,
+        state: NetWindowState,
+        change: StateChange
+    ) -> Result<()> {
+        use NetWindowState::*;
+
+        let window = self.desktop.client(id).ok_or(WMError::NoSuchWindow(id))?;
+        let has_state = window.has_state(state);
+        let add = match change {
+            StateChange::Add => true,
+            StateChange::Remove => false,
+            StateChange::Toggle => !has_state,
+        };
+
+        if add == has_state {
+            debug!("Window {} already has state {:?} set to {}", id, state, add);
+            return Ok(())
+        }
+        debug!("Setting state {:?} of window {} to {}", state, id, add);
+
+        match state {
+            Fullscreen => self.set_client_fullscreen(id, add)?,
+            MaxVert | MaxHorz => self.set_client_maximized(id, state, add)?,
+            Sticky => self.set_client_sticky(id, add)?,
+            Hidden => self.set_client_hidden(id, add)?,
+            Urgent => self.set_client_urgent(id, add)?,
+            Above | Below => {
+                let window = self.desktop.client_mut(id).unwrap();
+                if add {
+                    // a window cannot be in both layers at once
+                    window.remove_wm_state(if state == Above {Below} else {Above});
+                    window.add_wm_state(state);
+                } else {
+                    window.remove_wm_state(state);
+                }
+                // the window is moved to its new layer by Desktop::restack
+            }
+            Modal | Shaded | SkipTaskbar | SkipPager => {
+                let window = self.desktop.client_mut(id).unwrap();
+                if add {
+                    window.add_wm_state(state);
+                } else {
+                    window.remove_wm_state(state);
+                }
+            }
+        }
+
+        if let Some(window) = self.desktop.client(id) {
+            window.set_wm_states(&self.conn);
+        }
+        Ok(())
+    }
+
+    /// Toggles a state on the focused window.
+    pub fn toggle_focused(&mut self, state: NetWindowState) {
+        let focused = if let Some(win) = self.desktop.current().windows.focused() {
+            win.id()
+        } else {
+            debug!("No focused window to toggle {:?}", state);
+            return
+        };
+
+        if let Err(e) = self.change_client_state(focused, state, StateChange::Toggle) {
+            error!("Could not toggle state {:?}: {}", state, e);
+        }
+    }
+
+    /// Applies the states a client set on itself before it was mapped.
+    pub(crate) fn apply_initial_states(&mut self, id: XWindowID) -> Result<()> {
+        let states = self.desktop.client_mut(id)
+            .ok_or(WMError::NoSuchWindow(id))?
+            .take_wm_states();
+
+        for &state in states.iter() {
+            self.change_client_state(id, state, StateChange::Add)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the client is on the current workspace.
+    fn is_onscreen(&self, id: XWindowID) -> bool {
+        self.desktop.current().contains(id).is_some()
+    }
+
+    fn set_client_fullscreen(&mut self, id: XWindowID, fullscreen: bool) -> Result<()> {
+        let onscreen = self.is_onscreen(id);
+        let (ws, _) = self.desktop.retrieve_mut(id).unwrap();
+        let window = ws.windows.lookup_mut(id).unwrap();
+
+        if fullscreen {
+            window.add_wm_state(NetWindowState::Fullscreen);
+            // fullscreen windows are taken out of the tiling
+            if window.is_tiled() {
+                window.retile = true;
+                ws.set_window_tiled(id, false)?;
+            }
+            ws.windows.lookup_mut(id).unwrap().set_fullscreen(&self.conn, &self.screen);
+        } else {
+            window.remove_wm_state(NetWindowState::Fullscreen);
+            window.unset_fullscreen(&self.conn);
+            if window.retile && !window.is_hidden() {
+                window.retile = false;
+                ws.set_window_tiled(id, true)?;
+            }
+        }
+
+        if onscreen {
+            ws.relayout(&self.conn, &self.screen)?;
+        }
+        Ok(())
+    }
+
+    fn set_client_maximized(&mut self,
+        id: XWindowID,
+        state: NetWindowState,
+        maximized: bool
+    ) -> Result<()> {
+        let area = self.screen.workarea();
+        let window = self.desktop.client_mut(id).unwrap();
+
+        if maximized {
+            window.add_wm_state(state);
+        } else {
+            window.remove_wm_state(state);
+        }
+
+        // tiled and fullscreen windows already have their size decided for them
+        if window.is_floating() && !window.is_fullscreen() {
+            window.update_maximized(&self.conn, area);
+        } else {
+            debug!("Window {} is not floating, not resizing it", id);
+        }
+        Ok(())
+    }
+
+    fn set_client_sticky(&mut self, id: XWindowID, sticky: bool) -> Result<()> {
+        let current = self.desktop.current_idx();
+
+        if sticky {
+            // sticky windows always live on the current workspace
+            self.desktop.move_window(&self.conn, &self.screen, id, current)?;
+            self.desktop.client_mut(id).unwrap().add_wm_state(NetWindowState::Sticky);
+            self.conn.set_wm_desktop(id, consts::ALL_DESKTOPS);
+        } else {
+            self.desktop.client_mut(id).unwrap().remove_wm_state(NetWindowState::Sticky);
+            let idx = self.desktop.workspace_of(id).unwrap_or(current);
+            self.conn.set_wm_desktop(id, idx as u32);
+        }
+        Ok(())
+    }
+
+    fn set_client_hidden(&mut self, id: XWindowID, hidden: bool) -> Result<()> {
+        let onscreen = self.is_onscreen(id);
+        let (ws, _) = self.desktop.retrieve_mut(id).unwrap();
+        let window = ws.windows.lookup_mut(id).unwrap();
+
+        if hidden {
+            window.add_wm_state(NetWindowState::Hidden);
+            // hidden windows leave a gap in the tiling otherwise
+            if window.is_tiled() {
+                window.retile = true;
+                ws.set_window_tiled(id, false)?;
+            }
+            if !onscreen {
+                return Ok(())
+            }
+
+            ws.windows.lookup_mut(id).unwrap().unmap(&self.conn);
+
+            if ws.windows.is_focused(id) {
+                ws.windows.unset_focused();
+                let next = ws.windows.iter()
+                    .find(|win| !win.is_hidden())
+                    .map(|win| win.id());
+                if let Some(next) = next {
+                    ws.focus_window(&self.conn, &self.screen, next);
+                }
+            }
+            ws.relayout(&self.conn, &self.screen)?;
+        } else {
+            window.remove_wm_state(NetWindowState::Hidden);
+            if window.retile && !window.is_fullscreen() {
+                window.retile = false;
+                ws.set_window_tiled(id, true)?;
+            }
+            if !onscreen {
+                return Ok(())
+            }
+
+            ws.windows.lookup_mut(id).unwrap().show(&self.conn);
+            ws.relayout(&self.conn, &self.screen)?;
+            ws.focus_window(&self.conn, &self.screen, id);
+        }
+        Ok(())
+    }
+
+    fn set_client_urgent(&mut self, id: XWindowID, urgent: bool) -> Result<()> {
+        let (ws, _) = self.desktop.retrieve_mut(id).unwrap();
+        let focused = ws.windows.is_focused(id);
+        let window = ws.windows.lookup_mut(id).unwrap();
+
+        if urgent {
+            window.add_wm_state(NetWindowState::Urgent);
+        } else {
+            window.remove_wm_state(NetWindowState::Urgent);
+        }
+
+        let border = if window.is_urgent() {
+            BorderStyle::Urgent
+        } else if focused {
+            BorderStyle::Focused
+        } else {
+            BorderStyle::Unfocused
+        };
+        window.set_border(&self.conn, border);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Interactive resizing with _NET_WM_SYNC_REQUEST.
+//!
+//! Clients that support the protocol update an XSync counter once
+//! they have handled a configure and redrawn. While a client is
+//! being resized with the pointer, a configure is only sent once
+//! it has caught up with the previous one, so that heavy clients
+//! do not fall behind the pointer.
+//!
+//! An XSync alarm on the counter tells the window manager when the
+//! client has caught up, so that a configure held back in the meantime
+//! is sent right away instead of waiting for the pointer to move.
+
+use crate::types::{Result, WMError};
+use crate::x::core::{XConn, XWindowID, SyncAlarm};
+
+use super::WindowManager;
+
+/// A _NET_WM_SYNC_REQUEST exchange with the client being resized.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ResizeSync {
+    /// The alarm on the client's _NET_WM_SYNC_REQUEST_COUNTER.
+    alarm: SyncAlarm,
+    /// The value the client sets its counter to once it has handled the last configure.
+    value: i64,
+    /// Whether the last configure has not been acknowledged yet.
+    waiting: bool,
+    /// Whether the client was resized while waiting, and needs another configure.
+    pending: bool,
+}
+
+impl<X: XConn> WindowManager<X> {
+    /// Starts synchronising the resize of a client, if it supports it.
+    pub(super) fn begin_resize_sync(&mut self, id: XWindowID) {
+        self.resize_sync = None;
+
+        let supported = self.desktop.client(id)
+            .is_some_and(|win| win.supports(self.conn.get_atoms().WM_SYNC_REQUEST));
+        if !supported {
+            return
+        }
+        let counter = if let Some(counter) = self.conn.get_wm_sync_request_counter(id) {
+            counter
+        } else {
+            debug!("Window {} has no sync request counter", id);
+            return
+        };
+
+        let value = match self.conn.query_sync_counter(counter) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Could not query sync counter of window {}: {}", id, e);
+                return
+            }
+        };
+        // armed for the value of the first sync request
+        match self.conn.create_sync_alarm(counter, value + 1) {
+            Ok(alarm) => {
+                debug!("Synchronising resize of window {} with counter {}", id, counter);
+                self.resize_sync = Some(ResizeSync {
+                    alarm,
+                    value,
+                    waiting: false,
+                    pending: false,
+                });
+            }
+            Err(e) => warn!("Could not create sync alarm for window {}: {}", id, e),
+        }
+    }
+
+    /// Whether the resize of the selected client is being synchronised.
+    pub(super) fn is_resize_synced(&self) -> bool {
+        self.resize_sync.is_some()
+    }
+
+    /// Sends the client its current size, unless it has not
+    /// acknowledged the previous configure yet.
+    pub(super) fn configure_synced(&mut self, id: XWindowID) -> Result<()> {
+        let time = self.last_time;
+        let sync = if let Some(sync) = self.resize_sync.as_mut() {
+            sync
+        } else {
+            return Ok(())
+        };
+
+        if sync.waiting {
+            sync.pending = true;
+            return Ok(())
+        }
+
+        sync.value += 1;
+        sync.waiting = true;
+        sync.pending = false;
+        self.conn.set_sync_alarm(sync.alarm, sync.value);
+        self.conn.send_sync_request(id, sync.value, time);
+        self.configure_size(id)
+    }
+
+    /// Handles the alarm on the counter of the client being resized,
+    /// sending it the configure held back while it was catching up.
+    pub(super) fn resize_sync_alarm(&mut self, alarm: SyncAlarm, value: i64) -> Result<()> {
+        let sync = match self.resize_sync.as_mut() {
+            Some(sync) if sync.alarm == alarm => sync,
+            _ => return Ok(()),
+        };
+        // the alarm may have been triggered for an earlier sync request
+        if !sync.waiting || value < sync.value {
+            return Ok(())
+        }
+
+        sync.waiting = false;
+        match self.selected {
+            Some(id) if sync.pending => self.configure_synced(id),
+            _ => Ok(()),
+        }
+    }
+
+    /// Stops synchronising the resize, sending the final size
+    /// if the client has not been sent it yet.
+    pub(super) fn end_resize_sync(&mut self, id: XWindowID) -> Result<()> {
+        let sync = if let Some(sync) = self.resize_sync.take() {
+            sync
+        } else {
+            return Ok(())
+        };
+
+        self.conn.destroy_sync_alarm(sync.alarm);
+        if sync.pending {
+            self.configure_size(id)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn configure_size(&self, id: XWindowID) -> Result<()> {
+        let window = self.desktop.client(id).ok_or(WMError::NoSuchWindow(id))?;
+
+        // the position changes too when the left or top edge is dragged
+        window.update_geometry(&self.conn);
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+//! Desktop windows and _NET_SHOWING_DESKTOP.
+//!
+//! Desktop windows, such as the icons drawn by a file manager,
+//! cover the screen below every other window on every workspace.
+//! They are not clients, so they are never laid out or focused.
+//! Showing the desktop hides the clients on the current workspace
+//! until it is asked to stop, or a window is mapped or activated.
+
+use crate::types::{NetWindowState, StateChange, Result};
+use crate::x::core::{XConn, XWindowID};
+use crate::x::consts;
+use crate::utils;
+
+use super::WindowManager;
+
+impl<X: XConn> WindowManager<X> {
+    /// Starts tracking a desktop window and maps it below every other window.
+    pub(super) fn map_desktop_window(&mut self, id: XWindowID) {
+        if !self.desktop_windows.contains(&id) {
+            self.desktop_windows.push(id);
+        }
+        // watch for the window going away
+        self.conn.change_window_attributes(id, &[(
+            consts::CW_EVENT_MASK,
+            consts::EVENT_MASK_STRUCTURE_NOTIFY
+        )]);
+        self.place_desktop_window(id);
+        self.conn.map_window(id);
+    }
+
+    /// Sizes a desktop window to the screen and lowers it to the bottom of the stack.
+    pub(super) fn place_desktop_window(&self, id: XWindowID) {
+        debug!("Placing desktop window {}", id);
+        self.conn.set_geometry(id, self.screen.xwindow.geom);
+        self.conn.configure_window(id, &[(consts::CONFIG_WINDOW_BORDER_WIDTH as u16, 0)]);
+        self.conn.configure_window(id, &utils::stack_below());
+    }
+
+    /// Lowers every desktop window below the clients again.
+    pub(super) fn lower_desktop_windows(&self) {
+        // the last window lowered ends up at the bottom
+        for &id in self.desktop_windows.iter().rev() {
+            self.conn.configure_window(id, &utils::stack_below());
+        }
+    }
+
+    /// Whether the clients on the current workspace are hidden to show the desktop.
+    pub fn is_showing_desktop(&self) -> bool {
+        self.showing_desktop.is_some()
+    }
+
+    /// Hides the clients on the current workspace to show the desktop,
+    /// or shows them again.
+    ///
+    /// Only the clients hidden to show the desktop are shown again,
+    /// and the client that was focused gets the focus back.
+    pub fn show_desktop(&mut self, show: bool) -> Result<()> {
+        if show == self.is_showing_desktop() {
+            return Ok(())
+        }
+        debug!("Setting showing desktop to {}", show);
+
+        if show {
+            let ws = self.desktop.current();
+            let focused = ws.windows.focused().map(|win| win.id());
+            let mut hidden: Vec<XWindowID> = ws.windows.iter()
+                .filter(|win| !win.is_hidden() && Some(win.id()) != focused)
+                .map(|win| win.id())
+                .collect();
+            // shown last, so that it is focused again
+            hidden.extend(focused);
+
+            for &id in &hidden {
+                self.change_client_state(id, NetWindowState::Hidden, StateChange::Add)?;
+            }
+            self.showing_desktop = Some(hidden);
+        } else {
+            for id in self.showing_desktop.take().unwrap_or_default() {
+                // the client may have been closed in the meantime
+                if self.desktop.client(id).is_some() {
+                    self.change_client_state(id, NetWindowState::Hidden, StateChange::Remove)?;
+                }
+            }
+        }
+
+        self.conn.set_showing_desktop(self.root, show);
+        Ok(())
+    }
+}
@@ -1,25 +1,39 @@
-use super::{WindowManager, MouseMode};
+use std::collections::HashMap;
 
-use crate::core::{Ring, Workspace};
-use crate::x::{XCBConnection, XWindowID};
+use super::{WindowManager, MouseMode};
 
+use crate::desktop::{Desktop, Screen};
+use crate::types::Strut;
+use crate::x::{XConn, XWindowID};
 
-pub(crate) struct WMState<'a> {
-    pub conn: &'a XCBConnection,
-    pub workspaces: &'a Ring<Workspace>,
+/// A read-only view into the state of the window manager,
+/// used to work out how to respond to events.
+#[allow(dead_code)]
+pub(crate) struct WMState<'a, X: XConn> {
+    pub conn: &'a X,
+    pub desktop: &'a Desktop,
+    pub screen: &'a Screen,
+    pub docks: &'a HashMap<XWindowID, Strut>,
+    pub desktop_windows: &'a [XWindowID],
     pub root: i32,
     pub mousemode: MouseMode,
     pub selected: Option<XWindowID>,
+    /// Whether the selected window is being dragged with the keyboard.
+    pub keyboard_drag: bool,
 }
 
-impl WindowManager {
-    pub(crate) fn state(&self) -> WMState {
+impl<X: XConn> WindowManager<X> {
+    pub(crate) fn state(&self) -> WMState<'_, X> {
         WMState {
             conn: &self.conn,
-            workspaces: &self.desktop.workspaces,
+            desktop: &self.desktop,
+            screen: &self.screen,
+            docks: &self.docks,
+            desktop_windows: &self.desktop_windows,
             root: self.root,
             mousemode: self.mousemode,
             selected: self.selected,
+            keyboard_drag: self.drag_origin.is_some(),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,79 +1,103 @@
 #![allow(dead_code)]
-use xcb;
+use crate::x::consts;
 
 use std::{thread, process::Command};
 
 use crate::config;
 use crate::WindowManager;
-use crate::types::Keybind;
+use crate::types::{Keybind, Geometry};
+use crate::x::XConn;
+use crate::x::core::{ButtonMask, EventMask, ModMask, KeySym};
 
 pub const ROOT_ATTRS: [(u32, u32); 1] = [
     (
-        xcb::CW_EVENT_MASK, 
-        xcb::EVENT_MASK_SUBSTRUCTURE_REDIRECT | 
-        xcb::EVENT_MASK_STRUCTURE_NOTIFY |
-        xcb::EVENT_MASK_PROPERTY_CHANGE
+        consts::CW_EVENT_MASK, 
+        consts::EVENT_MASK_SUBSTRUCTURE_REDIRECT | 
+        consts::EVENT_MASK_STRUCTURE_NOTIFY |
+        consts::EVENT_MASK_PROPERTY_CHANGE
     )
 ];
 
 // Root window mouse button event mask
-pub const ROOT_BUTTON_GRAB_MASK: xcb::ButtonMask = xcb::EVENT_MASK_BUTTON_PRESS|xcb::EVENT_MASK_BUTTON_RELEASE;
+pub const ROOT_BUTTON_GRAB_MASK: ButtonMask = consts::EVENT_MASK_BUTTON_PRESS|consts::EVENT_MASK_BUTTON_RELEASE;
 
 // Root window pointer event mask
-pub const ROOT_POINTER_GRAB_MASK: xcb::EventMask = xcb::EVENT_MASK_BUTTON_RELEASE|xcb::EVENT_MASK_BUTTON_MOTION;
+pub const ROOT_POINTER_GRAB_MASK: EventMask = consts::EVENT_MASK_BUTTON_RELEASE|consts::EVENT_MASK_BUTTON_MOTION;
+
+// Root window pointer event mask for moves and resizes started without a button held
+pub const ROOT_POINTER_MOTION_MASK: EventMask =
+    consts::EVENT_MASK_BUTTON_PRESS|consts::EVENT_MASK_BUTTON_RELEASE|consts::EVENT_MASK_POINTER_MOTION;
 
 pub fn cursor_attrs(cursor_id: u32) -> [(u32, u32); 1] {
     //debug!("Getting cursor attrs for cursor {}", cursor_id);
-    return [(xcb::CW_CURSOR, cursor_id)]
+    [(consts::CW_CURSOR, cursor_id)]
 }
 
 pub fn disable_events() -> [(u32, u32); 1] {
     //debug!("VALUES: attributes no events");
-    return [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_NO_EVENT)];
+    [(consts::CW_EVENT_MASK, consts::EVENT_MASK_NO_EVENT)]
 }
 
 pub fn child_events() -> [(u32, u32); 1] {
     //debug!("VALUES: attributes child events");
-    return [(xcb::CW_EVENT_MASK,
-        xcb::EVENT_MASK_ENTER_WINDOW|    // -> Self EnterNotify events
-        xcb::EVENT_MASK_STRUCTURE_NOTIFY // -> Self CirculateNotify, ConfigureNotify, DestroyNotify, GravityNotify, MapNotify, ReparentNotify, UnmapNotify events
-    )];
+    [(consts::CW_EVENT_MASK,
+        consts::EVENT_MASK_ENTER_WINDOW|    // -> Self EnterNotify events
+        consts::EVENT_MASK_PROPERTY_CHANGE| // -> Self PropertyNotify events
+        consts::EVENT_MASK_STRUCTURE_NOTIFY // -> Self CirculateNotify, ConfigureNotify, DestroyNotify, GravityNotify, MapNotify, ReparentNotify, UnmapNotify events
+    )]
 }
 
 pub fn configure_move(x: u32, y: u32) -> [(u16, u32); 2] {
     //debug!("VALUES: configure move");
-    return [(xcb::CONFIG_WINDOW_X as u16, x), (xcb::CONFIG_WINDOW_Y as u16, y)];
+    [(consts::CONFIG_WINDOW_X as u16, x), (consts::CONFIG_WINDOW_Y as u16, y)]
 }
 
 pub fn configure_resize(width: u32, height: u32) -> [(u16, u32); 2] {
     //debug!("VALUES: configure resize");
-    return [(xcb::CONFIG_WINDOW_WIDTH as u16, width), (xcb::CONFIG_WINDOW_HEIGHT as u16, height)];
+    [(consts::CONFIG_WINDOW_WIDTH as u16, width), (consts::CONFIG_WINDOW_HEIGHT as u16, height)]
+}
+
+pub fn configure_geometry(geom: Geometry) -> [(u16, u32); 4] {
+    //debug!("VALUES: configure geometry");
+    [
+        (consts::CONFIG_WINDOW_X as u16, geom.x as u32),
+        (consts::CONFIG_WINDOW_Y as u16, geom.y as u32),
+        (consts::CONFIG_WINDOW_WIDTH as u16, geom.width as u32),
+        (consts::CONFIG_WINDOW_HEIGHT as u16, geom.height as u32),
+    ]
 }
 
 pub fn stack_above() -> [(u16, u32); 1] {
     //debug!("VALUES: configure stack above sibling {}", window_id);
-    return [
-        (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE),
-        //(xcb::CONFIG_WINDOW_SIBLING as u16, window_id),
-    ];
+    [
+        (consts::CONFIG_WINDOW_STACK_MODE as u16, consts::STACK_MODE_ABOVE),
+        //(consts::CONFIG_WINDOW_SIBLING as u16, window_id),
+    ]
+}
+
+pub fn stack_below() -> [(u16, u32); 1] {
+    [
+        (consts::CONFIG_WINDOW_STACK_MODE as u16, consts::STACK_MODE_BELOW),
+    ]
 }
 
 // helper functions for config things
 
-pub fn find_keybind(modm: xcb::ModMask, key: xcb::Keysym) -> Option<Keybind> {
+pub fn find_keybind<X: XConn>(modm: ModMask, key: KeySym) -> Option<Keybind<X>> {
     
-    for kb in config::KEYBINDS {
+    for kb in config::keybinds() {
         if kb.0 == modm && kb.1 == key {
-            return Some(*kb)
+            return Some(kb)
         }
     }
     
     None
 }
 
-pub fn close_window(wm: &mut WindowManager) {
+pub fn close_window<X: XConn>(wm: &mut WindowManager<X>) {
     if let Some(window) = wm.desktop.current_mut().windows.focused() {
-        wm.conn.destroy_window(&window);
+        let id = window.id();
+        wm.close_client(id);
     }
 }
 
@@ -0,0 +1,66 @@
+//! Batching of configure requests.
+//!
+//! Laying out a workspace touches the geometry, border and stacking
+//! of many windows at once. `ConfigureBatch` collects all of these
+//! changes and merges them, so that each window is sent a single
+//! `ConfigureWindow` request when the batch is submitted.
+
+use std::collections::BTreeMap;
+
+use crate::types::Geometry;
+use crate::utils;
+use super::core::{XConn, XWindowID};
+
+/// A set of pending configure requests, at most one per window.
+#[derive(Debug, Default)]
+pub struct ConfigureBatch {
+    /// Windows and their pending values, in the order they were first configured.
+    windows: Vec<(XWindowID, BTreeMap<u16, u32>)>,
+}
+
+impl ConfigureBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues configure values for a window.
+    ///
+    /// Values replace any previously queued values for the same field.
+    pub fn configure(&mut self, window: XWindowID, attrs: &[(u16, u32)]) {
+        let values = match self.windows.iter().position(|(id, _)| *id == window) {
+            Some(idx) => &mut self.windows[idx].1,
+            None => {
+                self.windows.push((window, BTreeMap::new()));
+                &mut self.windows.last_mut().unwrap().1
+            }
+        };
+
+        for (field, value) in attrs {
+            values.insert(*field, *value);
+        }
+    }
+
+    /// Queues a change of position and size for a window.
+    pub fn set_geometry(&mut self, window: XWindowID, geom: Geometry) {
+        self.configure(window, &utils::configure_geometry(geom));
+    }
+
+    /// Whether there are no pending requests.
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Sends one configure request per window and flushes the connection.
+    pub fn submit<X: XConn>(self, conn: &X) {
+        if self.is_empty() {
+            return
+        }
+
+        for (window, values) in self.windows {
+            let attrs: Vec<(u16, u32)> = values.into_iter().collect();
+            conn.configure_window(window, &attrs);
+        }
+
+        conn.flush();
+    }
+}
@@ -0,0 +1,195 @@
+//! X protocol constants used by RaccoonWM.
+//!
+//! These mirror the values defined by the X11 core protocol, ICCCM
+//! and EWMH, so that code outside the backends does not depend on
+//! any particular X library.
+
+#![allow(dead_code)]
+
+/// Cursor font glyphs.
+pub mod cursor {
+    pub const LEFT_PTR: u16 = 68;
+}
+
+pub const NONE: u32 = 0;
+pub const CURRENT_TIME: u32 = 0;
+
+// Window attribute masks, for `change_window_attributes`.
+pub const CW_BACK_PIXMAP: u32 = 1;
+pub const CW_BACK_PIXEL: u32 = 1 << 1;
+pub const CW_BORDER_PIXMAP: u32 = 1 << 2;
+pub const CW_BORDER_PIXEL: u32 = 1 << 3;
+pub const CW_BIT_GRAVITY: u32 = 1 << 4;
+pub const CW_WIN_GRAVITY: u32 = 1 << 5;
+pub const CW_BACKING_STORE: u32 = 1 << 6;
+pub const CW_BACKING_PLANES: u32 = 1 << 7;
+pub const CW_BACKING_PIXEL: u32 = 1 << 8;
+pub const CW_OVERRIDE_REDIRECT: u32 = 1 << 9;
+pub const CW_SAVE_UNDER: u32 = 1 << 10;
+pub const CW_EVENT_MASK: u32 = 1 << 11;
+pub const CW_DONT_PROPAGATE: u32 = 1 << 12;
+pub const CW_COLORMAP: u32 = 1 << 13;
+pub const CW_CURSOR: u32 = 1 << 14;
+
+// Window classes, used when creating windows.
+pub const WINDOW_CLASS_COPY_FROM_PARENT: u16 = 0;
+pub const WINDOW_CLASS_INPUT_OUTPUT: u16 = 1;
+pub const WINDOW_CLASS_INPUT_ONLY: u16 = 2;
+
+// Event masks.
+pub const EVENT_MASK_NO_EVENT: u32 = 0;
+pub const EVENT_MASK_KEY_PRESS: u32 = 1;
+pub const EVENT_MASK_KEY_RELEASE: u32 = 1 << 1;
+pub const EVENT_MASK_BUTTON_PRESS: u32 = 1 << 2;
+pub const EVENT_MASK_BUTTON_RELEASE: u32 = 1 << 3;
+pub const EVENT_MASK_ENTER_WINDOW: u32 = 1 << 4;
+pub const EVENT_MASK_LEAVE_WINDOW: u32 = 1 << 5;
+pub const EVENT_MASK_POINTER_MOTION: u32 = 1 << 6;
+pub const EVENT_MASK_BUTTON_MOTION: u32 = 1 << 13;
+pub const EVENT_MASK_EXPOSURE: u32 = 1 << 15;
+pub const EVENT_MASK_STRUCTURE_NOTIFY: u32 = 1 << 17;
+pub const EVENT_MASK_SUBSTRUCTURE_NOTIFY: u32 = 1 << 19;
+pub const EVENT_MASK_SUBSTRUCTURE_REDIRECT: u32 = 1 << 20;
+pub const EVENT_MASK_FOCUS_CHANGE: u32 = 1 << 21;
+pub const EVENT_MASK_PROPERTY_CHANGE: u32 = 1 << 22;
+
+// Window configuration masks, for `configure_window`.
+pub const CONFIG_WINDOW_X: u32 = 1;
+pub const CONFIG_WINDOW_Y: u32 = 1 << 1;
+pub const CONFIG_WINDOW_WIDTH: u32 = 1 << 2;
+pub const CONFIG_WINDOW_HEIGHT: u32 = 1 << 3;
+pub const CONFIG_WINDOW_BORDER_WIDTH: u32 = 1 << 4;
+pub const CONFIG_WINDOW_SIBLING: u32 = 1 << 5;
+pub const CONFIG_WINDOW_STACK_MODE: u32 = 1 << 6;
+
+// Stack modes.
+pub const STACK_MODE_ABOVE: u32 = 0;
+pub const STACK_MODE_BELOW: u32 = 1;
+pub const STACK_MODE_TOP_IF: u32 = 2;
+pub const STACK_MODE_BOTTOM_IF: u32 = 3;
+pub const STACK_MODE_OPPOSITE: u32 = 4;
+
+// Modifier masks.
+pub const MOD_MASK_SHIFT: u32 = 1;
+pub const MOD_MASK_LOCK: u32 = 1 << 1;
+pub const MOD_MASK_CONTROL: u32 = 1 << 2;
+pub const MOD_MASK_1: u32 = 1 << 3;
+pub const MOD_MASK_2: u32 = 1 << 4;
+pub const MOD_MASK_3: u32 = 1 << 5;
+pub const MOD_MASK_4: u32 = 1 << 6;
+pub const MOD_MASK_5: u32 = 1 << 7;
+pub const MOD_MASK_ANY: u32 = 1 << 15;
+
+// Mouse buttons.
+pub const BUTTON_INDEX_ANY: u32 = 0;
+pub const BUTTON_INDEX_1: u32 = 1;
+pub const BUTTON_INDEX_2: u32 = 2;
+pub const BUTTON_INDEX_3: u32 = 3;
+pub const BUTTON_INDEX_4: u32 = 4;
+pub const BUTTON_INDEX_5: u32 = 5;
+
+// Crossing event modes.
+pub const NOTIFY_MODE_NORMAL: u32 = 0;
+pub const NOTIFY_MODE_GRAB: u32 = 1;
+pub const NOTIFY_MODE_UNGRAB: u32 = 2;
+pub const NOTIFY_MODE_WHILE_GRABBED: u32 = 3;
+
+// Core protocol error codes.
+pub const BAD_REQUEST: u8 = 1;
+pub const BAD_VALUE: u8 = 2;
+pub const BAD_WINDOW: u8 = 3;
+pub const BAD_PIXMAP: u8 = 4;
+pub const BAD_ATOM: u8 = 5;
+pub const BAD_CURSOR: u8 = 6;
+pub const BAD_FONT: u8 = 7;
+pub const BAD_MATCH: u8 = 8;
+pub const BAD_DRAWABLE: u8 = 9;
+pub const BAD_ACCESS: u8 = 10;
+pub const BAD_ALLOC: u8 = 11;
+pub const BAD_COLORMAP: u8 = 12;
+pub const BAD_G_CONTEXT: u8 = 13;
+pub const BAD_ID_CHOICE: u8 = 14;
+pub const BAD_NAME: u8 = 15;
+pub const BAD_LENGTH: u8 = 16;
+pub const BAD_IMPLEMENTATION: u8 = 17;
+
+// Crossing and focus event details.
+pub const NOTIFY_DETAIL_ANCESTOR: u32 = 0;
+pub const NOTIFY_DETAIL_VIRTUAL: u32 = 1;
+pub const NOTIFY_DETAIL_INFERIOR: u32 = 2;
+pub const NOTIFY_DETAIL_NONLINEAR: u32 = 3;
+pub const NOTIFY_DETAIL_NONLINEAR_VIRTUAL: u32 = 4;
+pub const NOTIFY_DETAIL_POINTER: u32 = 5;
+pub const NOTIFY_DETAIL_POINTER_ROOT: u32 = 6;
+pub const NOTIFY_DETAIL_NONE: u32 = 7;
+
+// Circulate places.
+pub const PLACE_ON_TOP: u32 = 0;
+pub const PLACE_ON_BOTTOM: u32 = 1;
+
+// Mapping notify requests.
+pub const MAPPING_MODIFIER: u32 = 0;
+pub const MAPPING_KEYBOARD: u32 = 1;
+pub const MAPPING_POINTER: u32 = 2;
+
+// Event response types that are not core events.
+pub const GE_GENERIC: u8 = 35;
+pub const FIRST_EXTENSION_EVENT: u8 = 64;
+
+// Window gravities, as used in WM_NORMAL_HINTS.
+pub const GRAVITY_NORTH_WEST: u32 = 1;
+pub const GRAVITY_NORTH: u32 = 2;
+pub const GRAVITY_NORTH_EAST: u32 = 3;
+pub const GRAVITY_WEST: u32 = 4;
+pub const GRAVITY_CENTER: u32 = 5;
+pub const GRAVITY_EAST: u32 = 6;
+pub const GRAVITY_SOUTH_WEST: u32 = 7;
+pub const GRAVITY_SOUTH: u32 = 8;
+pub const GRAVITY_SOUTH_EAST: u32 = 9;
+pub const GRAVITY_STATIC: u32 = 10;
+
+// Predefined atoms, which do not need to be interned.
+pub const ATOM_WM_NORMAL_HINTS: u32 = 40;
+
+// Window map states.
+pub const MAP_STATE_UNMAPPED: u32 = 0;
+pub const MAP_STATE_UNVIEWABLE: u32 = 1;
+pub const MAP_STATE_VIEWABLE: u32 = 2;
+
+// ICCCM WM_STATE values.
+pub const WM_STATE_WITHDRAWN: u32 = 0;
+pub const WM_STATE_NORMAL: u32 = 1;
+pub const WM_STATE_ICONIC: u32 = 3;
+
+// EWMH _NET_WM_STATE client message actions.
+pub const NET_WM_STATE_REMOVE: u32 = 0;
+pub const NET_WM_STATE_ADD: u32 = 1;
+pub const NET_WM_STATE_TOGGLE: u32 = 2;
+
+// EWMH source indications, sent with requests from clients.
+pub const SOURCE_LEGACY: u32 = 0;
+pub const SOURCE_APPLICATION: u32 = 1;
+pub const SOURCE_PAGER: u32 = 2;
+
+// EWMH _NET_WM_DESKTOP value for windows on all desktops.
+pub const ALL_DESKTOPS: u32 = 0xFFFFFFFF;
+
+// EWMH _NET_WM_MOVERESIZE directions.
+pub const NET_WM_MOVERESIZE_SIZE_TOPLEFT: u32 = 0;
+pub const NET_WM_MOVERESIZE_SIZE_TOP: u32 = 1;
+pub const NET_WM_MOVERESIZE_SIZE_TOPRIGHT: u32 = 2;
+pub const NET_WM_MOVERESIZE_SIZE_RIGHT: u32 = 3;
+pub const NET_WM_MOVERESIZE_SIZE_BOTTOMRIGHT: u32 = 4;
+pub const NET_WM_MOVERESIZE_SIZE_BOTTOM: u32 = 5;
+pub const NET_WM_MOVERESIZE_SIZE_BOTTOMLEFT: u32 = 6;
+pub const NET_WM_MOVERESIZE_SIZE_LEFT: u32 = 7;
+pub const NET_WM_MOVERESIZE_MOVE: u32 = 8;
+pub const NET_WM_MOVERESIZE_SIZE_KEYBOARD: u32 = 9;
+pub const NET_WM_MOVERESIZE_MOVE_KEYBOARD: u32 = 10;
+pub const NET_WM_MOVERESIZE_CANCEL: u32 = 11;
+
+// EWMH _NET_MOVERESIZE_WINDOW flags, saying which values are present.
+pub const NET_MOVERESIZE_WINDOW_X: u32 = 1 << 8;
+pub const NET_MOVERESIZE_WINDOW_Y: u32 = 1 << 9;
+pub const NET_MOVERESIZE_WINDOW_WIDTH: u32 = 1 << 10;
+pub const NET_MOVERESIZE_WINDOW_HEIGHT: u32 = 1 << 11;
@@ -1,41 +1,141 @@
 use std::ops::Index;
 
-use xcb_util::ewmh;
 use thiserror::Error;
+use serde::{Serialize, Deserialize};
 
-use crate::types::Geometry;
+use crate::types::{Geometry, Point};
+use crate::window::Client;
 
+#[cfg(feature = "xcb-backend")]
 pub use super::xserver::XCBConnection;
+#[cfg(feature = "x11rb-backend")]
+pub use super::x11rbserver::X11RBConnection;
 pub use super::event::*;
+use super::{Icccm, Ewmh};
 
 pub type Atom = u32;
 pub type ModMask = u32;
 pub type KeySym = u32;
 pub type KeyCode = u8;
 pub type ButtonMask = u32;
+pub type ButtonIndex = u32;
+pub type EventMask = u32;
+pub type Timestamp = u32;
+pub type SyncCounter = u32;
+pub type SyncAlarm = u32;
 
-/// The list of atoms interned from the X Server by the WM.
-#[allow(non_snake_case)]
-#[derive(Clone, Copy)]
-pub struct InternedAtoms {
-    pub SUPPORTED: Atom,
-    
-    pub WM_DELETE_WINDOW: Atom,
-    pub WM_TAKE_FOCUS: Atom,
-
-    pub WM_PROTOCOLS: Atom,
-
-    pub WM_WINDOW_TYPE_DESKTOP: Atom,
-    pub WM_WINDOW_TYPE_DOCK: Atom,
-    pub WM_WINDOW_TYPE_TOOLBAR: Atom,
-    pub WM_WINDOW_TYPE_MENU: Atom,
-    pub WM_WINDOW_TYPE_UTILITY: Atom,
-    pub WM_WINDOW_TYPE_SPLASH: Atom,
-    pub WM_WINDOW_TYPE_DIALOG: Atom,
-    pub WM_WINDOW_TYPE_DROPDOWN_MENU: Atom,
-    pub WM_WINDOW_TYPE_NOTIFICATION: Atom,
-    pub WM_WINDOW_TYPE_NORMAL: Atom,
-    pub WM_STATE: Atom,
+macro_rules! interned_atoms {
+    (
+        supported { $($sfield:ident => $sname:literal,)* }
+        other { $($field:ident => $name:literal,)* }
+    ) => {
+        /// The list of atoms interned from the X Server by the WM.
+        #[allow(non_snake_case)]
+        #[derive(Clone, Copy)]
+        pub struct InternedAtoms {
+            $(pub $sfield: Atom,)*
+            $(pub $field: Atom,)*
+        }
+
+        impl InternedAtoms {
+            /// The names of all the atoms, in the order the fields are declared.
+            pub const NAMES: &'static [&'static str] = &[$($sname,)* $($name,)*];
+
+            /// Creates the list from atoms interned in the order of `InternedAtoms::NAMES`.
+            /// 
+            /// Panics if fewer atoms than names are given.
+            pub fn from_interned(atoms: &[Atom]) -> Self {
+                let mut atoms = atoms.iter().copied();
+
+                Self {
+                    $($sfield: atoms.next().expect("Not enough atoms interned"),)*
+                    $($field: atoms.next().expect("Not enough atoms interned"),)*
+                }
+            }
+
+            /// Returns the name and value of every atom.
+            pub fn to_list(&self) -> Vec<(&'static str, Atom)> {
+                vec![$(($sname, self.$sfield),)* $(($name, self.$field),)*]
+            }
+
+            /// Returns the atoms that are advertised in _NET_SUPPORTED.
+            pub fn supported(&self) -> Vec<Atom> {
+                vec![$(self.$sfield,)*]
+            }
+        }
+    };
+}
+
+// Field names follow xcb-util's ewmh module, which drops the _NET prefix.
+//
+// Atoms for EWMH features the window manager implements go under `supported`,
+// and are advertised in _NET_SUPPORTED.
+interned_atoms! {
+    supported {
+        SUPPORTED => "_NET_SUPPORTED",
+        CLIENT_LIST => "_NET_CLIENT_LIST",
+        CLIENT_LIST_STACKING => "_NET_CLIENT_LIST_STACKING",
+        NUMBER_OF_DESKTOPS => "_NET_NUMBER_OF_DESKTOPS",
+        CURRENT_DESKTOP => "_NET_CURRENT_DESKTOP",
+        DESKTOP_NAMES => "_NET_DESKTOP_NAMES",
+        ACTIVE_WINDOW => "_NET_ACTIVE_WINDOW",
+        SUPPORTING_WM_CHECK => "_NET_SUPPORTING_WM_CHECK",
+
+        CLOSE_WINDOW => "_NET_CLOSE_WINDOW",
+        MOVERESIZE_WINDOW => "_NET_MOVERESIZE_WINDOW",
+        WM_MOVERESIZE => "_NET_WM_MOVERESIZE",
+
+        WORKAREA => "_NET_WORKAREA",
+        SHOWING_DESKTOP => "_NET_SHOWING_DESKTOP",
+
+        WM_NAME => "_NET_WM_NAME",
+        WM_DESKTOP => "_NET_WM_DESKTOP",
+        WM_STRUT => "_NET_WM_STRUT",
+        WM_STRUT_PARTIAL => "_NET_WM_STRUT_PARTIAL",
+        WM_PING => "_NET_WM_PING",
+        WM_SYNC_REQUEST => "_NET_WM_SYNC_REQUEST",
+        WM_SYNC_REQUEST_COUNTER => "_NET_WM_SYNC_REQUEST_COUNTER",
+        WM_USER_TIME => "_NET_WM_USER_TIME",
+        WM_USER_TIME_WINDOW => "_NET_WM_USER_TIME_WINDOW",
+        WM_ICON => "_NET_WM_ICON",
+
+        WM_WINDOW_TYPE => "_NET_WM_WINDOW_TYPE",
+        WM_WINDOW_TYPE_DESKTOP => "_NET_WM_WINDOW_TYPE_DESKTOP",
+        WM_WINDOW_TYPE_DOCK => "_NET_WM_WINDOW_TYPE_DOCK",
+        WM_WINDOW_TYPE_TOOLBAR => "_NET_WM_WINDOW_TYPE_TOOLBAR",
+        WM_WINDOW_TYPE_UTILITY => "_NET_WM_WINDOW_TYPE_UTILITY",
+        WM_WINDOW_TYPE_SPLASH => "_NET_WM_WINDOW_TYPE_SPLASH",
+        WM_WINDOW_TYPE_DIALOG => "_NET_WM_WINDOW_TYPE_DIALOG",
+        WM_WINDOW_TYPE_NORMAL => "_NET_WM_WINDOW_TYPE_NORMAL",
+
+        WM_STATE => "_NET_WM_STATE",
+        WM_STATE_STICKY => "_NET_WM_STATE_STICKY",
+        WM_STATE_MAXIMIZED_VERT => "_NET_WM_STATE_MAXIMIZED_VERT",
+        WM_STATE_MAXIMIZED_HORZ => "_NET_WM_STATE_MAXIMIZED_HORZ",
+        WM_STATE_HIDDEN => "_NET_WM_STATE_HIDDEN",
+        WM_STATE_FULLSCREEN => "_NET_WM_STATE_FULLSCREEN",
+        WM_STATE_ABOVE => "_NET_WM_STATE_ABOVE",
+        WM_STATE_BELOW => "_NET_WM_STATE_BELOW",
+        WM_STATE_DEMANDS_ATTENTION => "_NET_WM_STATE_DEMANDS_ATTENTION",
+    }
+    other {
+        WM_DELETE_WINDOW => "WM_DELETE_WINDOW",
+        WM_TAKE_FOCUS => "WM_TAKE_FOCUS",
+
+        WM_PROTOCOLS => "WM_PROTOCOLS",
+
+        WM_WINDOW_TYPE_MENU => "_NET_WM_WINDOW_TYPE_MENU",
+        WM_WINDOW_TYPE_DROPDOWN_MENU => "_NET_WM_WINDOW_TYPE_DROPDOWN_MENU",
+        WM_WINDOW_TYPE_NOTIFICATION => "_NET_WM_WINDOW_TYPE_NOTIFICATION",
+
+        WM_STATE_MODAL => "_NET_WM_STATE_MODAL",
+        WM_STATE_SHADED => "_NET_WM_STATE_SHADED",
+        WM_STATE_SKIP_TASKBAR => "_NET_WM_STATE_SKIP_TASKBAR",
+        WM_STATE_SKIP_PAGER => "_NET_WM_STATE_SKIP_PAGER",
+
+        ICCCM_WM_STATE => "WM_STATE",
+        UTF8_STRING => "UTF8_STRING",
+    }
 }
 
 /// You really shouldn't be using this.
@@ -59,40 +159,7 @@ impl Index<usize> for InternedAtoms {
     }
 }
 
-impl InternedAtoms {
-    pub fn new(conn: &ewmh::Connection) -> Self {
-        Self {
-            SUPPORTED: conn.SUPPORTED(),
-
-            WM_DELETE_WINDOW: xcb::intern_atom(conn, false, "WM_DELETE_WINDOW")
-                .get_reply()
-                .unwrap_or_else(|e| panic!("{}", e))
-                .atom(),
-
-            WM_TAKE_FOCUS: xcb::intern_atom(conn, false, "WM_TAKE_FOCUS")
-                .get_reply()
-                .unwrap_or_else(|e| panic!("{}", e))
-                .atom(),
-            
-            WM_PROTOCOLS: conn.WM_PROTOCOLS(),
-            
-            WM_WINDOW_TYPE_DESKTOP: conn.WM_WINDOW_TYPE_DESKTOP(),
-            WM_WINDOW_TYPE_DOCK: conn.WM_WINDOW_TYPE_DOCK(),
-            WM_WINDOW_TYPE_TOOLBAR: conn.WM_WINDOW_TYPE_TOOLBAR(),
-            WM_WINDOW_TYPE_MENU: conn.WM_WINDOW_TYPE_MENU(),
-            WM_WINDOW_TYPE_UTILITY: conn.WM_WINDOW_TYPE_UTILITY(),
-            WM_WINDOW_TYPE_SPLASH: conn.WM_WINDOW_TYPE_SPLASH(),
-            WM_WINDOW_TYPE_DIALOG: conn.WM_WINDOW_TYPE_DIALOG(),
-            WM_WINDOW_TYPE_DROPDOWN_MENU: conn.WM_WINDOW_TYPE_DROPDOWN_MENU(),
-            WM_WINDOW_TYPE_NOTIFICATION: conn.WM_WINDOW_TYPE_NOTIFICATION(),
-            WM_WINDOW_TYPE_NORMAL: conn.WM_WINDOW_TYPE_NORMAL(),
-
-            WM_STATE: conn.WM_STATE(),
-        }
-    }
-}
-
-pub type XWindowID = xcb::Window;
+pub type XWindowID = u32;
 
 /// A low-level window type that contains a window ID and its geometry.
 #[derive(Debug, Clone, Copy)]
@@ -118,8 +185,8 @@ impl From<XWindowID> for XWindow {
 }
 
 impl XWindow {
-    /// Sets the geometry using a provided XCBConnection.
-    pub fn set_geometry_conn(&mut self, conn: &XCBConnection) {
+    /// Sets the geometry using a provided connection.
+    pub fn set_geometry_conn<X: XConn>(&mut self, conn: &X) {
         match conn.get_geometry(self.id) {
             Ok(geom) => {
                 debug!(
@@ -131,7 +198,6 @@ impl XWindow {
 
             Err(e) => {
                 error!("{}", e);
-                return
             }
         }
     }
@@ -162,33 +228,153 @@ impl XWindow {
     }
 }
 
-#[derive(Debug, Error, Clone, Copy)]
+/// The map state of a window, as reported by the X server.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MapState {
+    Unmapped,
+    Unviewable,
+    Viewable,
+}
+
+/// The subset of window attributes that RaccoonWM makes use of.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowAttributes {
+    pub override_redirect: bool,
+    pub map_state: MapState,
+}
+
+/// The result of a pointer query.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PointerQueryReply {
+    /// The root window the pointer is on.
+    pub root: XWindowID,
+    /// The child of the queried window the pointer is on, if any.
+    pub child: XWindowID,
+    /// The pointer location relative to the root window.
+    pub location: Point,
+}
+
+/// Errors arising from communication with the X server.
+#[derive(Debug, Error, Clone)]
 pub enum XError {
     #[error("Could not establish a connection to the X server.")]
     Connection,
+    #[error("The X server closed the connection.")]
+    ConnectionClosed,
     #[error("Could not complete specified request.")]
     RequestError,
+    #[error("Could not intern atom {0}.")]
+    InternAtom(&'static str),
+    #[error("Screen {0} does not exist.")]
+    NoSuchScreen(i32),
+    #[error("Protocol violation: {0}")]
+    ProtocolError(String),
+    #[error("Could not read or write recording: {0}")]
+    Recording(String),
 }
 
 pub type Result<T> = ::core::result::Result<T, XError>;
 
-pub trait XConn {
-    fn get_root(&self) -> XWindowID;
-    fn get_geometry(&self, window: XWindowID) -> Geometry;
-    fn get_window_attributes(&self, window: XWindowID);
-    fn get_root_geom(&self) -> Geometry {
-        self.get_geometry(self.get_root())
+/// The interface RaccoonWM uses to talk to the X server.
+/// 
+/// Everything above the `x` module only interacts with the X server
+/// through this trait, so any type implementing it can be used as a
+/// backend for `WindowManager`. ICCCM and EWMH functionality is
+/// provided through the `Icccm` and `Ewmh` supertraits.
+/// 
+/// Implemented by XCBConnection and X11RBConnection, depending on
+/// the backend features enabled, and by DummyConn for testing.
+pub trait XConn: Icccm + Ewmh {
+    /// Returns the atoms interned by the connection.
+    fn get_atoms(&self) -> InternedAtoms;
+
+    /// Get the root ID of the current screen.
+    fn get_root_id(&self) -> XWindowID;
+
+    /// Get the root geometry (which is usually the screen resolution)
+    fn get_root_geom(&self) -> Result<Geometry> {
+        self.get_geometry(self.get_root_id())
     }
+
+    /// Set the screen of the current root.
     fn set_root_scr(&mut self, scr: i32);
-    fn query_tree(&self) -> Vec<XWindowID>;
+
+    /// Get the next event from the X server.
+    /// 
+    /// Blocks until an event is received.
+    /// Returns `XError::ConnectionClosed` once no more events can be received.
+    fn next_event(&self) -> Result<XEvent>;
+
+    /// Get a list of all children of the given window.
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>>;
+
+    /// Query the pointer location with respect to the given window.
+    fn query_pointer(&self, window: XWindowID) -> Result<PointerQueryReply>;
+
+    /// Get the value of an XSync counter.
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64>;
+
+    /// Create an XSync alarm that sends a SyncAlarmNotify once
+    /// the counter reaches the given value.
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm>;
+
+    /// Set the value an alarm waits for, and arm it again if it has triggered.
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64);
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm);
+
+    /// Create the cursor from the given cursor font glyph.
+    fn create_cursor(&mut self, glyph: u16) -> Result<()>;
+
+    /// Set the cursor for the given window.
+    fn set_cursor(&mut self, window: XWindowID);
+
+    /// Create an unmapped, input-only child of the root window,
+    /// for the window manager's own use.
+    fn create_hidden_window(&self) -> Result<XWindowID>;
+
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes>;
     fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]);
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()>;
     fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]);
     fn reparent_window(&self, window: XWindowID, parent: XWindowID);
-    // fn create_window(&self);
     fn map_window(&self, window: XWindowID);
     fn unmap_window(&self, window: XWindowID);
-    fn destroy_window(&self, window: XWindowID);
+
+    /// Close the window, via WM_DELETE_WINDOW if the client supports it.
+    fn destroy_window(&self, window: &Client);
+
+    /// Forcibly close the client that created the window, with XKillClient.
+    fn kill_client(&self, window: XWindowID);
+
+    /// Append nothing to a property of the window, which has the given type.
+    ///
+    /// The property is left as it was, but the server still reports a
+    /// PropertyNotify, whose timestamp is the current server time.
+    fn touch_property(&self, window: XWindowID, property: Atom, type_: Atom);
+
+    /// Set the server time of the event being handled, which timestamps
+    /// focus changes and WM_TAKE_FOCUS messages.
+    fn set_timestamp(&self, time: Timestamp);
     fn set_input_focus(&self, window: XWindowID);
     fn set_geometry(&self, window: XWindowID, geom: Geometry);
-    fn set_property(&self, window: XWindowID);
+    fn get_geometry(&self, window: XWindowID) -> Result<Geometry>;
+
+    fn grab_key(&self, window: XWindowID, mask: ModMask, keysym: KeySym);
+    fn grab_button(&self, 
+        window: XWindowID, 
+        mask: ButtonMask, 
+        button: ButtonIndex, 
+        modmask: ModMask, 
+        confine: bool
+    );
+    fn grab_pointer(&self, window: XWindowID, mask: EventMask);
+    fn ungrab_pointer(&self);
+    fn grab_keyboard(&self, window: XWindowID);
+    fn ungrab_keyboard(&self);
+
+    /// Send all buffered requests to the X server.
+    /// 
+    /// Requests are buffered until the connection is flushed,
+    /// which also happens before waiting for the next event.
+    fn flush(&self);
 }
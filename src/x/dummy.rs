@@ -0,0 +1,853 @@
+//! An in-memory X server, for testing RaccoonWM without a display.
+//!
+//! `DummyConn` implements `XConn` by keeping track of windows, their
+//! geometries, properties, map state, input focus and stacking order
+//! internally. Every request it receives from the window manager
+//! is recorded, and can be inspected afterwards.
+//!
+//! `DummyConn` does not generate events by itself. Events are queued
+//! using `DummyConn::push_event` and handed out by `XConn::next_event`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use crate::window::Client;
+use crate::types::{
+    Geometry,
+    Point,
+    NetWindowStates,
+    XWinProperties,
+    WindowState,
+    SizeHints,
+    WmHints,
+    Strut,
+    Icon,
+};
+use super::core::*;
+use super::{Icccm, Ewmh};
+use super::consts;
+
+/// The ID of the root window of a `DummyConn`.
+pub const DUMMY_ROOT: XWindowID = 1;
+
+/// A request received by a `DummyConn`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    QueryTree(XWindowID),
+    QueryPointer(XWindowID),
+    QuerySyncCounter(SyncCounter),
+    CreateSyncAlarm(SyncCounter, i64),
+    SetSyncAlarm(SyncAlarm, i64),
+    DestroySyncAlarm(SyncAlarm),
+    GetGeometry(XWindowID),
+    GetWindowAttributes(XWindowID),
+    /// A request for a property, identified by name.
+    GetProperty(XWindowID, &'static str),
+    SetIcccmWmState(XWindowID, WindowState),
+    ChangeWindowAttributes(XWindowID, Vec<(u32, u32)>),
+    ConfigureWindow(XWindowID, Vec<(u16, u32)>),
+    ReparentWindow(XWindowID, XWindowID),
+    MapWindow(XWindowID),
+    UnmapWindow(XWindowID),
+    DestroyWindow(XWindowID),
+    /// A client message sent to a window.
+    SendEvent(ClientMessageEvent),
+    KillClient(XWindowID),
+    TouchProperty(XWindowID, Atom),
+    SetInputFocus(XWindowID),
+    CreateCursor(u16),
+    CreateWindow(XWindowID),
+    SetCursor(XWindowID),
+    GrabKey(XWindowID, ModMask, KeySym),
+    GrabButton(XWindowID, ButtonIndex, ModMask),
+    GrabPointer(XWindowID),
+    UngrabPointer,
+    GrabKeyboard(XWindowID),
+    UngrabKeyboard,
+    SetSupported(Vec<Atom>),
+    SetWmState(XWindowID, Vec<Atom>),
+    SetClientList(Vec<XWindowID>),
+    SetClientListStacking(Vec<XWindowID>),
+    SetNumberOfDesktops(u32),
+    SetCurrentDesktop(u32),
+    SetDesktopNames(Vec<String>),
+    SetWmDesktop(XWindowID, u32),
+    SetActiveWindow(XWindowID),
+    SetSupportingWmCheck(XWindowID, XWindowID),
+    SetWmName(XWindowID, String),
+    SetWorkarea(Vec<Geometry>),
+    SetShowingDesktop(bool),
+}
+
+/// A window tracked by a `DummyConn`.
+#[derive(Debug, Clone, Default)]
+pub struct DummyWindow {
+    pub geom: Geometry,
+    pub border_width: u32,
+    pub mapped: bool,
+    pub override_redirect: bool,
+    /// Window attributes set with `change_window_attributes`.
+    pub attributes: HashMap<u32, u32>,
+
+    pub name: String,
+    pub icon_name: String,
+    pub class: (String, String),
+    pub protocols: Vec<Atom>,
+    pub hints: Option<WmHints>,
+    pub size_hints: Option<SizeHints>,
+    pub wm_state: WindowState,
+    pub transient_for: Option<XWindowID>,
+    pub window_type: Vec<Atom>,
+    pub net_states: Vec<Atom>,
+    /// The workspace set with _NET_WM_DESKTOP.
+    pub desktop: Option<u32>,
+    /// The name set with _NET_WM_NAME.
+    pub net_name: String,
+    /// The window set as _NET_SUPPORTING_WM_CHECK.
+    pub wm_check: Option<XWindowID>,
+    /// The space reserved with _NET_WM_STRUT_PARTIAL.
+    pub strut: Option<Strut>,
+    /// The counter set as _NET_WM_SYNC_REQUEST_COUNTER.
+    pub sync_counter: Option<SyncCounter>,
+    /// The time set as _NET_WM_USER_TIME.
+    pub user_time: Option<Timestamp>,
+    /// The window set as _NET_WM_USER_TIME_WINDOW.
+    pub user_time_window: Option<XWindowID>,
+    /// The raw contents of _NET_WM_ICON.
+    pub icon: Vec<u32>,
+}
+
+struct DummyState {
+    windows: HashMap<XWindowID, DummyWindow>,
+    /// Children of the root window, bottom to top.
+    stack: Vec<XWindowID>,
+    focus: XWindowID,
+    pointer: Point,
+    supported: Vec<Atom>,
+    client_list: Vec<XWindowID>,
+    client_list_stacking: Vec<XWindowID>,
+    number_of_desktops: u32,
+    current_desktop: u32,
+    desktop_names: Vec<String>,
+    active_window: XWindowID,
+    workarea: Vec<Geometry>,
+    showing_desktop: bool,
+    /// The time set with `set_timestamp`.
+    time: Timestamp,
+    /// The time reported by the server in PropertyNotify events.
+    server_time: Timestamp,
+    /// Values of XSync counters.
+    counters: HashMap<SyncCounter, i64>,
+    /// XSync alarms, with the counter and value each waits for,
+    /// and whether it is still armed.
+    alarms: HashMap<SyncAlarm, (SyncCounter, i64, bool)>,
+    events: VecDeque<XEvent>,
+    requests: Vec<Request>,
+    next_id: XWindowID,
+}
+
+/// An in-memory connection to a fake X server.
+pub struct DummyConn {
+    atoms: InternedAtoms,
+    current_scr: i32,
+    state: RefCell<DummyState>,
+}
+
+impl DummyConn {
+    /// Creates a new DummyConn whose root window has the given geometry.
+    pub fn new(root_geom: Geometry) -> Self {
+        let mut windows = HashMap::new();
+        windows.insert(DUMMY_ROOT, DummyWindow {
+            geom: root_geom,
+            mapped: true,
+            ..DummyWindow::default()
+        });
+
+        Self {
+            atoms: dummy_atoms(),
+            current_scr: 0,
+            state: RefCell::new(DummyState {
+                windows,
+                stack: Vec::new(),
+                focus: DUMMY_ROOT,
+                pointer: Point {x: 0, y: 0},
+                supported: Vec::new(),
+                client_list: Vec::new(),
+                client_list_stacking: Vec::new(),
+                number_of_desktops: 0,
+                current_desktop: 0,
+                desktop_names: Vec::new(),
+                active_window: consts::NONE,
+                workarea: Vec::new(),
+                showing_desktop: false,
+                time: consts::CURRENT_TIME,
+                server_time: consts::CURRENT_TIME,
+                counters: HashMap::new(),
+                alarms: HashMap::new(),
+                events: VecDeque::new(),
+                requests: Vec::new(),
+                next_id: DUMMY_ROOT + 1,
+            }),
+        }
+    }
+
+    /// Creates a window as a child of the root window, placing it on top
+    /// of the stack.
+    ///
+    /// This is the equivalent of a client creating a window,
+    /// and is not recorded as a request.
+    pub fn create_window(&self, window: DummyWindow) -> XWindowID {
+        let mut state = self.state.borrow_mut();
+        let id = state.next_id;
+
+        state.next_id += 1;
+        state.windows.insert(id, window);
+        state.stack.push(id);
+
+        id
+    }
+
+    /// Creates an unmapped window of type _NET_WM_WINDOW_TYPE_NORMAL.
+    pub fn create_normal_window(&self) -> XWindowID {
+        self.create_window(DummyWindow {
+            window_type: vec![self.atoms.WM_WINDOW_TYPE_NORMAL],
+            ..DummyWindow::default()
+        })
+    }
+
+    /// Removes a window, as if its client had destroyed it.
+    pub fn remove_window(&self, id: XWindowID) {
+        let mut state = self.state.borrow_mut();
+
+        state.windows.remove(&id);
+        state.stack.retain(|win| *win != id);
+        if state.focus == id {
+            state.focus = DUMMY_ROOT;
+        }
+    }
+
+    /// Returns a snapshot of the given window.
+    pub fn window(&self, id: XWindowID) -> Option<DummyWindow> {
+        self.state.borrow().windows.get(&id).cloned()
+    }
+
+    /// Applies a change to a window, as if its client had done it.
+    pub fn modify_window<F: FnOnce(&mut DummyWindow)>(&self, id: XWindowID, f: F) {
+        if let Some(win) = self.state.borrow_mut().windows.get_mut(&id) {
+            f(win)
+        }
+    }
+
+    /// Whether the given window is mapped.
+    pub fn is_mapped(&self, id: XWindowID) -> bool {
+        self.window(id).map(|win| win.mapped).unwrap_or(false)
+    }
+
+    /// The window that currently has input focus.
+    pub fn focused(&self) -> XWindowID {
+        self.state.borrow().focus
+    }
+
+    /// The children of the root window, from bottom to top.
+    pub fn stacking_order(&self) -> Vec<XWindowID> {
+        self.state.borrow().stack.clone()
+    }
+
+    /// The atoms set as _NET_SUPPORTED.
+    pub fn supported(&self) -> Vec<Atom> {
+        self.state.borrow().supported.clone()
+    }
+
+    /// The windows set as _NET_CLIENT_LIST.
+    pub fn client_list(&self) -> Vec<XWindowID> {
+        self.state.borrow().client_list.clone()
+    }
+
+    /// The windows set as _NET_CLIENT_LIST_STACKING.
+    pub fn client_list_stacking(&self) -> Vec<XWindowID> {
+        self.state.borrow().client_list_stacking.clone()
+    }
+
+    /// The value of _NET_NUMBER_OF_DESKTOPS.
+    pub fn number_of_desktops(&self) -> u32 {
+        self.state.borrow().number_of_desktops
+    }
+
+    /// The value of _NET_CURRENT_DESKTOP.
+    pub fn current_desktop(&self) -> u32 {
+        self.state.borrow().current_desktop
+    }
+
+    /// The names set as _NET_DESKTOP_NAMES.
+    pub fn desktop_names(&self) -> Vec<String> {
+        self.state.borrow().desktop_names.clone()
+    }
+
+    /// The window set as _NET_ACTIVE_WINDOW.
+    pub fn active_window(&self) -> XWindowID {
+        self.state.borrow().active_window
+    }
+
+    /// Returns the areas set as _NET_WORKAREA.
+    pub fn workarea(&self) -> Vec<Geometry> {
+        self.state.borrow().workarea.clone()
+    }
+
+    /// The value of _NET_SHOWING_DESKTOP.
+    pub fn showing_desktop(&self) -> bool {
+        self.state.borrow().showing_desktop
+    }
+
+    /// Sets an XSync counter, as a client does to acknowledge a sync request.
+    ///
+    /// Armed alarms on the counter that it reaches are triggered.
+    pub fn set_sync_counter(&self, counter: SyncCounter, value: i64) {
+        self.state.borrow_mut().counters.insert(counter, value);
+        self.check_alarms();
+    }
+
+    /// Triggers the armed alarms whose counter has reached their value.
+    fn check_alarms(&self) {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        let time = state.server_time;
+
+        for (&alarm, (counter, alarm_value, armed)) in state.alarms.iter_mut() {
+            let counter_value = match state.counters.get(counter) {
+                Some(&value) if *armed && value >= *alarm_value => value,
+                _ => continue,
+            };
+            *armed = false;
+            state.events.push_back(XEvent::SyncAlarmNotify(SyncAlarmEvent {
+                alarm,
+                counter_value,
+                alarm_value: *alarm_value,
+                time,
+            }));
+        }
+    }
+
+    /// Moves the pointer to the given location.
+    pub fn set_pointer(&self, pt: Point) {
+        self.state.borrow_mut().pointer = pt;
+    }
+
+    /// Sets the time the server reports in PropertyNotify events
+    /// caused by the window manager.
+    pub fn set_server_time(&self, time: Timestamp) {
+        self.state.borrow_mut().server_time = time;
+    }
+
+    /// Queues an event to be returned by `next_event`.
+    pub fn push_event(&self, event: XEvent) {
+        self.state.borrow_mut().events.push_back(event);
+    }
+
+    /// Returns all requests received so far, in order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.borrow().requests.clone()
+    }
+
+    /// Clears the record of received requests.
+    pub fn clear_requests(&self) {
+        self.state.borrow_mut().requests.clear();
+    }
+
+    fn record(&self, request: Request) {
+        self.state.borrow_mut().requests.push(request);
+    }
+
+    fn with_window<T, F: FnOnce(&DummyWindow) -> T>(&self, id: XWindowID, f: F) -> Option<T> {
+        self.state.borrow().windows.get(&id).map(f)
+    }
+
+    fn restack(&self, window: XWindowID, sibling: Option<XWindowID>, mode: u32) {
+        let mut state = self.state.borrow_mut();
+
+        if !state.stack.contains(&window) {
+            return
+        }
+        state.stack.retain(|win| *win != window);
+
+        let sibling_idx = sibling.and_then(|sib| state.stack.iter().position(|win| *win == sib));
+
+        match (mode, sibling_idx) {
+            (consts::STACK_MODE_ABOVE, Some(idx)) => state.stack.insert(idx + 1, window),
+            (consts::STACK_MODE_BELOW, Some(idx)) => state.stack.insert(idx, window),
+            (consts::STACK_MODE_BELOW, None) => state.stack.insert(0, window),
+            _ => state.stack.push(window),
+        }
+    }
+}
+
+impl XConn for DummyConn {
+    fn get_atoms(&self) -> InternedAtoms {
+        self.atoms
+    }
+
+    fn get_root_id(&self) -> XWindowID {
+        DUMMY_ROOT
+    }
+
+    fn set_root_scr(&mut self, scr: i32) {
+        self.current_scr = scr;
+    }
+
+    fn next_event(&self) -> Result<XEvent> {
+        // behave as if the server went away once all events are consumed
+        self.state.borrow_mut().events.pop_front()
+            .ok_or(XError::ConnectionClosed)
+    }
+
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
+        self.record(Request::QueryTree(window));
+
+        if window == DUMMY_ROOT {
+            Ok(self.stacking_order())
+        } else if self.window(window).is_some() {
+            Ok(Vec::new())
+        } else {
+            Err(XError::RequestError)
+        }
+    }
+
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64> {
+        self.record(Request::QuerySyncCounter(counter));
+
+        self.state.borrow().counters.get(&counter)
+            .copied()
+            .ok_or(XError::RequestError)
+    }
+
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm> {
+        self.record(Request::CreateSyncAlarm(counter, value));
+
+        let alarm = {
+            let mut state = self.state.borrow_mut();
+            let alarm = state.next_id;
+            state.next_id += 1;
+            state.alarms.insert(alarm, (counter, value, true));
+            alarm
+        };
+        self.check_alarms();
+
+        Ok(alarm)
+    }
+
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64) {
+        self.record(Request::SetSyncAlarm(alarm, value));
+
+        if let Some((_, alarm_value, armed)) = self.state.borrow_mut().alarms.get_mut(&alarm) {
+            *alarm_value = value;
+            *armed = true;
+        }
+        self.check_alarms();
+    }
+
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm) {
+        self.record(Request::DestroySyncAlarm(alarm));
+        self.state.borrow_mut().alarms.remove(&alarm);
+    }
+
+    fn query_pointer(&self, window: XWindowID) -> Result<PointerQueryReply> {
+        self.record(Request::QueryPointer(window));
+
+        let state = self.state.borrow();
+        let pt = state.pointer;
+        let child = state.stack.iter().rev()
+            .filter_map(|id| state.windows.get(id).map(|win| (*id, win)))
+            .find(|(_, win)| {
+                win.mapped &&
+                pt.x >= win.geom.x && pt.x < win.geom.x + win.geom.width &&
+                pt.y >= win.geom.y && pt.y < win.geom.y + win.geom.height
+            })
+            .map(|(id, _)| id)
+            .unwrap_or(consts::NONE);
+
+        Ok(PointerQueryReply {
+            root: DUMMY_ROOT,
+            child,
+            location: pt,
+        })
+    }
+
+    fn create_cursor(&mut self, glyph: u16) -> Result<()> {
+        self.record(Request::CreateCursor(glyph));
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, window: XWindowID) {
+        self.record(Request::SetCursor(window));
+    }
+
+    /// Hidden windows are kept out of the stacking order, since they are never mapped.
+    fn create_hidden_window(&self) -> Result<XWindowID> {
+        let mut state = self.state.borrow_mut();
+        let id = state.next_id;
+
+        state.next_id += 1;
+        state.windows.insert(id, DummyWindow {
+            geom: Geometry {x: -1, y: -1, width: 1, height: 1},
+            override_redirect: true,
+            ..DummyWindow::default()
+        });
+        state.requests.push(Request::CreateWindow(id));
+
+        Ok(id)
+    }
+
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes> {
+        self.record(Request::GetWindowAttributes(window));
+
+        self.with_window(window, |win| WindowAttributes {
+            override_redirect: win.override_redirect,
+            map_state: if win.mapped {MapState::Viewable} else {MapState::Unmapped},
+        })
+    }
+
+    fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
+        self.record(Request::ChangeWindowAttributes(window, attrs.to_vec()));
+
+        self.modify_window(window, |win| {
+            for (attr, value) in attrs {
+                win.attributes.insert(*attr, *value);
+            }
+        });
+    }
+
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
+        if self.window(window).is_none() {
+            self.record(Request::ChangeWindowAttributes(window, attrs.to_vec()));
+            return Err(XError::RequestError)
+        }
+        self.change_window_attributes(window, attrs);
+        Ok(())
+    }
+
+    fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
+        self.record(Request::ConfigureWindow(window, attrs.to_vec()));
+
+        let mut sibling = None;
+        let mut stack_mode = None;
+
+        self.modify_window(window, |win| {
+            for (attr, value) in attrs {
+                match *attr as u32 {
+                    consts::CONFIG_WINDOW_X => win.geom.x = *value as i32,
+                    consts::CONFIG_WINDOW_Y => win.geom.y = *value as i32,
+                    consts::CONFIG_WINDOW_WIDTH => win.geom.width = *value as i32,
+                    consts::CONFIG_WINDOW_HEIGHT => win.geom.height = *value as i32,
+                    consts::CONFIG_WINDOW_BORDER_WIDTH => win.border_width = *value,
+                    consts::CONFIG_WINDOW_SIBLING => sibling = Some(*value),
+                    consts::CONFIG_WINDOW_STACK_MODE => stack_mode = Some(*value),
+                    _ => {}
+                }
+            }
+        });
+
+        if let Some(mode) = stack_mode {
+            self.restack(window, sibling, mode);
+        }
+    }
+
+    fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
+        self.record(Request::ReparentWindow(window, parent));
+    }
+
+    fn map_window(&self, window: XWindowID) {
+        self.record(Request::MapWindow(window));
+        self.modify_window(window, |win| win.mapped = true);
+    }
+
+    fn unmap_window(&self, window: XWindowID) {
+        self.record(Request::UnmapWindow(window));
+        self.modify_window(window, |win| win.mapped = false);
+    }
+
+    fn destroy_window(&self, window: &Client) {
+        if window.supports(self.atoms.WM_DELETE_WINDOW) {
+            self.record(Request::SendEvent(ClientMessageEvent {
+                window: window.id(),
+                type_: self.atoms.WM_PROTOCOLS,
+                data: ClientMessageData::U32([
+                    self.atoms.WM_DELETE_WINDOW,
+                    consts::CURRENT_TIME,
+                    0, 0, 0
+                ]),
+            }));
+        } else {
+            self.record(Request::DestroyWindow(window.id()));
+            self.remove_window(window.id());
+        }
+    }
+
+    fn kill_client(&self, window: XWindowID) {
+        self.record(Request::KillClient(window));
+        self.remove_window(window);
+    }
+
+    fn touch_property(&self, window: XWindowID, property: Atom, _type: Atom) {
+        self.record(Request::TouchProperty(window, property));
+
+        let selected = self.with_window(window, |win| win.attributes.get(&consts::CW_EVENT_MASK).copied())
+            .flatten()
+            .is_some_and(|mask| mask & consts::EVENT_MASK_PROPERTY_CHANGE != 0);
+        if selected {
+            let time = self.state.borrow().server_time;
+            self.push_event(XEvent::PropertyNotify(PropertyEvent {id: window, atom: property, time}));
+        }
+    }
+
+    fn set_timestamp(&self, time: Timestamp) {
+        self.state.borrow_mut().time = time;
+    }
+
+    fn set_input_focus(&self, window: XWindowID) {
+        self.record(Request::SetInputFocus(window));
+        self.state.borrow_mut().focus = window;
+    }
+
+    fn set_geometry(&self, window: XWindowID, geom: Geometry) {
+        self.configure_window(window, &crate::utils::configure_geometry(geom));
+    }
+
+    fn get_geometry(&self, window: XWindowID) -> Result<Geometry> {
+        self.record(Request::GetGeometry(window));
+        self.with_window(window, |win| win.geom).ok_or(XError::RequestError)
+    }
+
+    fn grab_key(&self, window: XWindowID, mask: ModMask, keysym: KeySym) {
+        self.record(Request::GrabKey(window, mask, keysym));
+    }
+
+    fn grab_button(&self,
+        window: XWindowID,
+        _mask: ButtonMask,
+        button: ButtonIndex,
+        modmask: ModMask,
+        _confine: bool
+    ) {
+        self.record(Request::GrabButton(window, button, modmask));
+    }
+
+    fn grab_pointer(&self, window: XWindowID, _mask: EventMask) {
+        self.record(Request::GrabPointer(window));
+    }
+
+    fn ungrab_pointer(&self) {
+        self.record(Request::UngrabPointer);
+    }
+
+    fn grab_keyboard(&self, window: XWindowID) {
+        self.record(Request::GrabKeyboard(window));
+    }
+
+    fn ungrab_keyboard(&self) {
+        self.record(Request::UngrabKeyboard);
+    }
+
+    // requests take effect immediately, so there is nothing to flush
+    fn flush(&self) {}
+}
+
+impl Icccm for DummyConn {
+    fn get_client_properties(&self, window: XWindowID) -> XWinProperties {
+        XWinProperties {
+            wm_name: self.get_wm_name(window),
+            wm_icon_name: self.get_wm_icon_name(window),
+            wm_size_hints: self.get_wm_size_hints(window),
+            wm_hints: self.get_wm_hints(window),
+            wm_class: self.get_wm_class(window)
+                .unwrap_or_else(|| (String::new(), String::new())),
+            wm_protocols: self.get_wm_protocols(window),
+            wm_state: self.get_wm_state(window),
+        }
+    }
+
+    fn get_wm_name(&self, window: XWindowID) -> String {
+        self.record(Request::GetProperty(window, "WM_NAME"));
+        self.with_window(window, |win| win.name.clone()).unwrap_or_default()
+    }
+
+    fn get_wm_icon_name(&self, window: XWindowID) -> String {
+        self.record(Request::GetProperty(window, "WM_ICON_NAME"));
+        self.with_window(window, |win| win.icon_name.clone()).unwrap_or_default()
+    }
+
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints> {
+        self.record(Request::GetProperty(window, "WM_NORMAL_HINTS"));
+        self.with_window(window, |win| win.size_hints).flatten()
+    }
+
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints> {
+        self.record(Request::GetProperty(window, "WM_HINTS"));
+        self.with_window(window, |win| win.hints).flatten()
+    }
+
+    fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)> {
+        self.record(Request::GetProperty(window, "WM_CLASS"));
+        self.with_window(window, |win| win.class.clone())
+    }
+
+    fn get_wm_protocols(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.record(Request::GetProperty(window, "WM_PROTOCOLS"));
+        self.with_window(window, |win| win.protocols.clone())
+    }
+
+    fn get_wm_state(&self, window: XWindowID) -> WindowState {
+        self.record(Request::GetProperty(window, "WM_STATE"));
+        self.with_window(window, |win| win.wm_state).unwrap_or_default()
+    }
+
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState) {
+        self.record(Request::SetIcccmWmState(window, state));
+        self.modify_window(window, |win| win.wm_state = state);
+    }
+
+    fn send_take_focus(&self, window: XWindowID) {
+        let time = self.state.borrow().time;
+        self.record(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([self.atoms.WM_TAKE_FOCUS, time, 0, 0, 0]),
+        }));
+    }
+
+    fn get_wm_transient_for(&self, window: XWindowID) -> Option<XWindowID> {
+        self.record(Request::GetProperty(window, "WM_TRANSIENT_FOR"));
+        self.with_window(window, |win| win.transient_for).flatten()
+    }
+
+    fn get_urgency(&self, window: XWindowID) -> bool {
+        self.get_wm_hints(window).map(|hints| hints.urgent).unwrap_or(false)
+    }
+}
+
+impl Ewmh for DummyConn {
+    fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.record(Request::GetProperty(window, "_NET_WM_WINDOW_TYPE"));
+        self.with_window(window, |win| win.window_type.clone())
+            .filter(|types| !types.is_empty())
+    }
+
+    fn get_window_states(&self, window: XWindowID) -> NetWindowStates {
+        self.record(Request::GetProperty(window, "_NET_WM_STATE"));
+        self.with_window(window, |win| NetWindowStates::from_atoms(&win.net_states, &self.atoms))
+            .unwrap_or_default()
+    }
+
+    fn set_supported(&self, _screen_idx: i32, atoms: &[Atom]) {
+        self.record(Request::SetSupported(atoms.to_vec()));
+        self.state.borrow_mut().supported = atoms.to_vec();
+    }
+
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates) {
+        let atoms = states.to_atoms(&self.atoms);
+        self.record(Request::SetWmState(window, atoms.clone()));
+        self.modify_window(window, |win| win.net_states = atoms);
+    }
+
+    fn set_client_list(&self, _screen_idx: i32, clients: &[XWindowID]) {
+        self.record(Request::SetClientList(clients.to_vec()));
+        self.state.borrow_mut().client_list = clients.to_vec();
+    }
+
+    fn set_client_list_stacking(&self, _screen_idx: i32, clients: &[XWindowID]) {
+        self.record(Request::SetClientListStacking(clients.to_vec()));
+        self.state.borrow_mut().client_list_stacking = clients.to_vec();
+    }
+
+    fn set_number_of_desktops(&self, _screen_idx: i32, number: u32) {
+        self.record(Request::SetNumberOfDesktops(number));
+        self.state.borrow_mut().number_of_desktops = number;
+    }
+
+    fn set_current_desktop(&self, _screen_idx: i32, idx: u32) {
+        self.record(Request::SetCurrentDesktop(idx));
+        self.state.borrow_mut().current_desktop = idx;
+    }
+
+    fn set_desktop_names(&self, _screen_idx: i32, names: &[&str]) {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        self.record(Request::SetDesktopNames(names.clone()));
+        self.state.borrow_mut().desktop_names = names;
+    }
+
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32) {
+        self.record(Request::SetWmDesktop(window, idx));
+        self.modify_window(window, |win| win.desktop = Some(idx));
+    }
+
+    fn set_active_window(&self, _screen_idx: i32, window: XWindowID) {
+        self.record(Request::SetActiveWindow(window));
+        self.state.borrow_mut().active_window = window;
+    }
+
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID) {
+        self.record(Request::SetSupportingWmCheck(window, check));
+        self.modify_window(window, |win| win.wm_check = Some(check));
+    }
+
+    fn set_wm_name(&self, window: XWindowID, name: &str) {
+        self.record(Request::SetWmName(window, name.into()));
+        self.modify_window(window, |win| win.net_name = name.into());
+    }
+
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut> {
+        self.record(Request::GetProperty(window, "_NET_WM_STRUT_PARTIAL"));
+        self.with_window(window, |win| win.strut).flatten()
+    }
+
+    fn set_workarea(&self, _screen_idx: i32, areas: &[Geometry]) {
+        self.record(Request::SetWorkarea(areas.to_vec()));
+        self.state.borrow_mut().workarea = areas.to_vec();
+    }
+
+    fn set_showing_desktop(&self, _screen_idx: i32, showing: bool) {
+        self.record(Request::SetShowingDesktop(showing));
+        self.state.borrow_mut().showing_desktop = showing;
+    }
+
+    fn send_ping(&self, window: XWindowID, time: Timestamp) {
+        self.record(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([self.atoms.WM_PING, time, window, 0, 0]),
+        }));
+    }
+
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter> {
+        self.record(Request::GetProperty(window, "_NET_WM_SYNC_REQUEST_COUNTER"));
+        self.with_window(window, |win| win.sync_counter).flatten()
+    }
+
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp> {
+        self.record(Request::GetProperty(window, "_NET_WM_USER_TIME"));
+        let window = self.with_window(window, |win| win.user_time_window)
+            .flatten()
+            .unwrap_or(window);
+        self.with_window(window, |win| win.user_time).flatten()
+    }
+
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon> {
+        self.record(Request::GetProperty(window, "_NET_WM_ICON"));
+        self.with_window(window, |win| Icon::decode_all(&win.icon)).unwrap_or_default()
+    }
+
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp) {
+        self.record(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([
+                self.atoms.WM_SYNC_REQUEST, time, value as u32, (value >> 32) as u32, 0
+            ]),
+        }));
+    }
+}
+
+/// Assigns each atom a unique value.
+fn dummy_atoms() -> InternedAtoms {
+    let atoms: Vec<Atom> = (1..=InternedAtoms::NAMES.len() as Atom).collect();
+
+    InternedAtoms::from_interned(&atoms)
+}
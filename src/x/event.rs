@@ -1,49 +1,124 @@
-use xcb::xproto; //todo: decouple this
-use xcb::Keysym;
+use std::fmt;
 
-use std::convert::TryInto;
+use serde::{Serialize, Deserialize};
 
-use super::{XWindowID, Atom};
+use super::core::{
+    XWindowID,
+    Atom,
+    ButtonIndex,
+    KeySym,
+    KeyCode,
+    ModMask,
+    Timestamp,
+    SyncAlarm,
+};
+use super::consts;
 use crate::core::types::{Geometry, Point};
 
 /// Low-level wrapper around actual X server events.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum XEvent {
     /// Notification that a client has changed its configuration.
     ConfigureNotify(ConfigureEvent),
     /// Request for configuration from a client.
-    ConfigureRequest(ConfigureEvent),
+    ConfigureRequest(ConfigureRequestData),
     /// A Client is requesting to be mapped.
-    MapRequest(XWindowID, bool), // bool: override_redirect
-    MapNotify(XWindowID),
-    UnmapNotify(XWindowID),
-    DestroyNotify(XWindowID),
-    EnterNotify(XWindowID),
-    LeaveNotify(XWindowID),
-    MotionNotify(Point),
-    ReparentNotify(XWindowID),
+    MapRequest(MapRequestData),
+    MapNotify(MapEvent),
+    UnmapNotify(UnmapEvent),
+    DestroyNotify(DestroyEvent),
+    /// A window was created as a child of a window we listen on.
+    CreateNotify(CreateEvent),
+    /// A client is requesting to be raised or lowered.
+    CirculateRequest(CirculateEvent),
+    CirculateNotify(CirculateEvent),
+    /// A window was moved because its parent was resized.
+    GravityNotify(XWindowID, Point),
+    EnterNotify(CrossingEvent),
+    LeaveNotify(CrossingEvent),
+    FocusIn(FocusEvent),
+    FocusOut(FocusEvent),
+    MotionNotify(PointerEvent),
+    ReparentNotify(ReparentEvent),
     PropertyNotify(PropertyEvent),
     KeyPress(KeypressEvent),
-    KeyRelease,
-    ButtonPress(XWindowID, Point),
-    ButtonRelease,
-    ClientMessage(XWindowID, ClientMessageData),
+    KeyRelease(KeypressEvent),
+    ButtonPress(PointerEvent),
+    ButtonRelease(PointerEvent),
+    /// Part of a window needs to be redrawn.
+    Expose(ExposeEvent),
+    /// The keyboard, modifier or pointer mapping has changed.
+    MappingNotify(MappingEvent),
+    ClientMessage(ClientMessageEvent),
+    /// An XSync alarm was triggered.
+    SyncAlarmNotify(SyncAlarmEvent),
+    /// An error caused by a request that was not checked.
+    Error(XErrorEvent),
+    /// An event from an extension, kept as it came from the server.
+    Extension(ExtensionEvent),
     Unknown(u8),
 }
 
+impl XEvent {
+    /// The server time the event happened at, for events that carry one.
+    pub fn time(&self) -> Option<Timestamp> {
+        use XEvent::*;
+        match self {
+            KeyPress(event) | KeyRelease(event) => Some(event.time),
+            ButtonPress(event) | ButtonRelease(event) | MotionNotify(event) => Some(event.time),
+            EnterNotify(event) | LeaveNotify(event) => Some(event.time),
+            PropertyNotify(event) => Some(event.time),
+            SyncAlarmNotify(event) => Some(event.time),
+            _ => None,
+        }
+    }
+}
+
 /// Data associated with a configure event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ConfigureEvent {
     /// The window associated with the event.
     pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
     /// The new geometry requested by the window.
     pub geom: Geometry,
+    pub border_width: u32,
+    /// The sibling the window is now stacked above, if any.
+    pub above_sibling: XWindowID,
+    pub override_redirect: bool,
+    /// Is the window the root window
+    pub is_root: bool,
+}
+
+/// Data associated with a configure request.
+/// 
+/// Each field is only `Some` if the client asked for it to be changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ConfigureRequestData {
+    /// The window associated with the event.
+    pub id: XWindowID,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub border_width: Option<u32>,
+    pub sibling: Option<XWindowID>,
+    pub stack_mode: Option<u32>,
     /// Is the window the root window
     pub is_root: bool,
 }
 
+impl ConfigureRequestData {
+    /// Whether the request asks for the window geometry to change.
+    pub fn changes_geometry(&self) -> bool {
+        self.x.is_some() || self.y.is_some() || 
+        self.width.is_some() || self.height.is_some()
+    }
+}
+
 /// Data associated with a property change event.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct PropertyEvent {
     /// The window associated with the event.
     pub id: XWindowID,
@@ -53,18 +128,356 @@ pub struct PropertyEvent {
     pub time: u32,
 }
 
-/// Data associated with a key press event.
-#[derive(Debug, Clone, Copy)]
+/// An error reported by the X server.
+/// 
+/// Errors from requests whose replies are not waited on
+/// are delivered alongside regular events.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct XErrorEvent {
+    /// The error code.
+    pub code: u8,
+    /// The sequence number of the failed request.
+    pub sequence: u16,
+    /// The value that caused the error, usually a resource ID.
+    pub resource: u32,
+    /// The major opcode of the failed request.
+    pub major_opcode: u8,
+    /// The minor opcode of the failed request, used by extensions.
+    pub minor_opcode: u16,
+}
+
+impl XErrorEvent {
+    /// The name of the error, as given by the X protocol.
+    pub fn error_name(&self) -> &'static str {
+        ERROR_NAMES.get(self.code as usize).copied().unwrap_or("Unknown")
+    }
+
+    /// The name of the failed request, if it is a core protocol request.
+    pub fn request_name(&self) -> &'static str {
+        REQUEST_NAMES.get(self.major_opcode as usize).copied().unwrap_or("Extension")
+    }
+
+    /// Whether the error was caused by a window that does not exist.
+    /// 
+    /// Requests that take drawables report missing windows as BadDrawable.
+    pub fn is_missing_window(&self) -> bool {
+        self.code == consts::BAD_WINDOW || self.code == consts::BAD_DRAWABLE
+    }
+}
+
+impl fmt::Display for XErrorEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}) on request {} ({}.{}) for resource {:#x}, sequence {}",
+            self.error_name(), self.code,
+            self.request_name(), self.major_opcode, self.minor_opcode,
+            self.resource, self.sequence,
+        )
+    }
+}
+
+const ERROR_NAMES: &[&str] = &[
+    "Success", "BadRequest", "BadValue", "BadWindow", "BadPixmap",
+    "BadAtom", "BadCursor", "BadFont", "BadMatch", "BadDrawable",
+    "BadAccess", "BadAlloc", "BadColormap", "BadGContext", "BadIDChoice",
+    "BadName", "BadLength", "BadImplementation",
+];
+
+// Indexed by major opcode; opcodes 120-127 are unused and 128+ are extensions.
+const REQUEST_NAMES: &[&str] = &[
+    "None", "CreateWindow", "ChangeWindowAttributes", "GetWindowAttributes",
+    "DestroyWindow", "DestroySubwindows", "ChangeSaveSet", "ReparentWindow",
+    "MapWindow", "MapSubwindows", "UnmapWindow", "UnmapSubwindows",
+    "ConfigureWindow", "CirculateWindow", "GetGeometry", "QueryTree",
+    "InternAtom", "GetAtomName", "ChangeProperty", "DeleteProperty",
+    "GetProperty", "ListProperties", "SetSelectionOwner", "GetSelectionOwner",
+    "ConvertSelection", "SendEvent", "GrabPointer", "UngrabPointer",
+    "GrabButton", "UngrabButton", "ChangeActivePointerGrab", "GrabKeyboard",
+    "UngrabKeyboard", "GrabKey", "UngrabKey", "AllowEvents",
+    "GrabServer", "UngrabServer", "QueryPointer", "GetMotionEvents",
+    "TranslateCoordinates", "WarpPointer", "SetInputFocus", "GetInputFocus",
+    "QueryKeymap", "OpenFont", "CloseFont", "QueryFont",
+    "QueryTextExtents", "ListFonts", "ListFontsWithInfo", "SetFontPath",
+    "GetFontPath", "CreatePixmap", "FreePixmap", "CreateGC",
+    "ChangeGC", "CopyGC", "SetDashes", "SetClipRectangles",
+    "FreeGC", "ClearArea", "CopyArea", "CopyPlane",
+    "PolyPoint", "PolyLine", "PolySegment", "PolyRectangle",
+    "PolyArc", "FillPoly", "PolyFillRectangle", "PolyFillArc",
+    "PutImage", "GetImage", "PolyText8", "PolyText16",
+    "ImageText8", "ImageText16", "CreateColormap", "FreeColormap",
+    "CopyColormapAndFree", "InstallColormap", "UninstallColormap", "ListInstalledColormaps",
+    "AllocColor", "AllocNamedColor", "AllocColorCells", "AllocColorPlanes",
+    "FreeColors", "StoreColors", "StoreNamedColor", "QueryColors",
+    "LookupColor", "CreateCursor", "CreateGlyphCursor", "FreeCursor",
+    "RecolorCursor", "QueryBestSize", "QueryExtension", "ListExtensions",
+    "ChangeKeyboardMapping", "GetKeyboardMapping", "ChangeKeyboardControl", "GetKeyboardControl",
+    "Bell", "ChangePointerControl", "GetPointerControl", "SetScreenSaver",
+    "GetScreenSaver", "ChangeHosts", "ListHosts", "SetAccessControl",
+    "SetCloseDownMode", "KillClient", "RotateProperties", "ForceScreenSaver",
+    "SetPointerMapping", "GetPointerMapping", "SetModifierMapping", "GetModifierMapping",
+];
+
+/// Data associated with a key press or release.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct KeypressEvent {
     /// What modmask was active at the time.
-    pub mask: u32,
+    pub mask: ModMask,
     /// The key pressed.
-    pub keysym: Keysym,
+    pub keysym: KeySym,
+    /// The keycode of the key pressed.
+    pub keycode: KeyCode,
+    /// The window the event was reported on.
+    pub window: XWindowID,
+    /// The root window of the screen the event happened on.
+    pub root: XWindowID,
+    /// The child of `window` the pointer was on, if any.
+    pub child: XWindowID,
+    /// The time of event.
+    pub time: Timestamp,
+}
+
+/// Data associated with a button press, button release or pointer motion.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PointerEvent {
+    /// The window the event was reported on.
+    pub window: XWindowID,
+    /// The root window of the screen the event happened on.
+    pub root: XWindowID,
+    /// The child of `window` the pointer was on, if any.
+    pub child: XWindowID,
+    /// The pointer location relative to the root window.
+    pub root_pt: Point,
+    /// The pointer location relative to `window`.
+    pub event_pt: Point,
+    /// The button pressed or released.
+    /// 
+    /// Always `BUTTON_INDEX_ANY` for motion events.
+    pub button: ButtonIndex,
+    /// The modifier and button mask before the event.
+    pub state: ModMask,
+    /// The time of event.
+    pub time: Timestamp,
+}
+
+/// Data associated with the pointer entering or leaving a window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CrossingEvent {
+    /// The window the pointer entered or left.
+    pub window: XWindowID,
+    /// The root window of the screen the event happened on.
+    pub root: XWindowID,
+    /// The child of `window` the pointer is on, if any.
+    pub child: XWindowID,
+    /// The pointer location relative to the root window.
+    pub root_pt: Point,
+    /// The pointer location relative to `window`.
+    pub event_pt: Point,
+    /// One of the `NOTIFY_MODE_*` constants.
+    pub mode: u32,
+    /// One of the `NOTIFY_DETAIL_*` constants.
+    pub detail: u32,
+    /// The modifier and button mask at the time.
+    pub state: ModMask,
+    /// The time of event.
+    pub time: Timestamp,
+}
+
+impl CrossingEvent {
+    /// Whether the crossing was caused by the pointer moving,
+    /// rather than by a grab starting.
+    pub fn is_normal(&self) -> bool {
+        self.mode == consts::NOTIFY_MODE_NORMAL || self.mode == consts::NOTIFY_MODE_UNGRAB
+    }
+}
+
+/// Data associated with a window gaining or losing input focus.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocusEvent {
+    /// The window that gained or lost focus.
+    pub window: XWindowID,
+    /// One of the `NOTIFY_MODE_*` constants.
+    pub mode: u32,
+    /// One of the `NOTIFY_DETAIL_*` constants.
+    pub detail: u32,
+}
+
+/// Data associated with an unmap notification.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UnmapEvent {
+    /// The window that was unmapped.
+    pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
+    /// Whether the event was sent by a client, as a withdrawn
+    /// window does when it is already unmapped.
+    pub synthetic: bool,
+}
+
+impl UnmapEvent {
+    /// An unmap notification generated by the X server.
+    pub fn new(id: XWindowID) -> Self {
+        Self {id, event: id, synthetic: false}
+    }
+}
+
+/// Data associated with a map request.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapRequestData {
+    /// The window to be mapped.
+    pub id: XWindowID,
+    /// The parent of the window, whose substructure redirect caught the request.
+    pub parent: XWindowID,
+    /// Whether the window is override-redirect.
+    /// 
+    /// The event does not carry this, so it is read from the window attributes.
+    pub override_redirect: bool,
+}
+
+impl MapRequestData {
+    /// A map request for a window that is not override-redirect.
+    pub fn new(id: XWindowID, parent: XWindowID) -> Self {
+        Self {id, parent, override_redirect: false}
+    }
+}
+
+/// Data associated with a map notification.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapEvent {
+    /// The window that was mapped.
+    pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
+    pub override_redirect: bool,
+}
+
+/// Data associated with a destroy notification.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DestroyEvent {
+    /// The window that was destroyed.
+    pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
+}
+
+impl DestroyEvent {
+    /// A destroy notification reported on the window itself.
+    pub fn new(id: XWindowID) -> Self {
+        Self {id, event: id}
+    }
+}
+
+/// Data associated with a reparent notification.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReparentEvent {
+    /// The window that was reparented.
+    pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
+    /// The new parent of the window.
+    pub parent: XWindowID,
+    /// The position of the window relative to its new parent.
+    pub pt: Point,
+    pub override_redirect: bool,
+}
+
+/// An event from an extension.
+/// 
+/// Extension events are not decoded, so the event is kept as it
+/// was sent. Generic events can be longer than 32 bytes, and only
+/// their first 32 bytes are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExtensionEvent {
+    /// The response type, identifying the event.
+    pub response_type: u8,
+    /// The sequence number of the last request processed by the server.
+    pub sequence: u16,
+    /// The raw bytes of the event, in the byte order of the connection.
+    pub data: [u8; 32],
+}
+
+impl ExtensionEvent {
+    /// Reads the header of a raw event.
+    pub fn from_raw(data: [u8; 32]) -> Self {
+        Self {
+            response_type: data[0] & !0x80,
+            sequence: u16::from_ne_bytes([data[2], data[3]]),
+            data,
+        }
+    }
+}
+
+/// Data associated with an XSync alarm event.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SyncAlarmEvent {
+    /// The alarm that was triggered.
+    pub alarm: SyncAlarm,
+    /// The value of the alarm's counter when it was triggered.
+    pub counter_value: i64,
+    /// The value the alarm was waiting for.
+    pub alarm_value: i64,
+    /// The time of event.
+    pub time: Timestamp,
+}
+
+/// Data associated with a window creation event.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CreateEvent {
+    /// The window that was created.
+    pub id: XWindowID,
+    /// The parent of the window.
+    pub parent: XWindowID,
+    /// The initial geometry of the window.
+    pub geom: Geometry,
+    pub border_width: u32,
+    pub override_redirect: bool,
+}
+
+/// Data associated with a circulate request or notification.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CirculateEvent {
+    /// The window being restacked.
+    pub id: XWindowID,
+    /// The window the event was reported on.
+    pub event: XWindowID,
+    /// One of the `PLACE_ON_*` constants.
+    pub place: u32,
+}
+
+/// Data associated with an expose event.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExposeEvent {
+    /// The window that needs to be redrawn.
+    pub id: XWindowID,
+    /// The area of the window that needs to be redrawn.
+    pub geom: Geometry,
+    /// The number of expose events that follow for the same window.
+    pub count: u32,
+}
+
+/// Data associated with a mapping change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MappingEvent {
+    /// One of the `MAPPING_*` constants.
+    pub request: u32,
+    /// The first keycode whose mapping changed.
+    pub first_keycode: KeyCode,
+    /// The number of keycodes whose mapping changed.
+    pub count: u32,
+}
+
+/// Data associated with a client message.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClientMessageEvent {
+    /// The window the message is about.
+    pub window: XWindowID,
+    /// The type of the message.
+    pub type_: Atom,
+    /// The message data.
+    pub data: ClientMessageData,
 }
 
 /// The different formats of a Client message's data,
 /// as specified by ICCCM.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ClientMessageData {
     U8([u8; 20]),
     U16([u16; 10]),
@@ -72,38 +485,18 @@ pub enum ClientMessageData {
 }
 
 impl ClientMessageData {
-    //todo: deprecate this
-    pub fn from_event(event: &xproto::ClientMessageEvent) -> Self {
-        let data = event.data();
-        match event.format() {
-            8 => {
-                Self::U8(data.data8()[0..20]
-                .try_into().expect("Byte: Incorrect conversion"))
-            }
-            16 => {
-                Self::U16(data.data16()[0..10]
-                .try_into().expect("Word: Incorrect conversion"))
-            }
-            32 => {
-                Self::U32(data.data32()[0..5]
-                .try_into().expect("DWord: Incorrect conversion"))
-            }
-            _ => {unreachable!()}
-        }
-    }
-
     #[inline(always)]
     pub fn is_u8(&self) -> bool {
-        if let Self::U8(_) = self {true} else {false}
+        matches!(self, Self::U8(_))
     }
 
     #[inline(always)]
     pub fn is_u16(&self) -> bool {
-        if let Self::U16(_) = self {true} else {false}
+        matches!(self, Self::U16(_))
     }
 
     pub fn is_u32(&self) -> bool {
-        if let Self::U32(_) = self {true} else {false}
+        matches!(self, Self::U32(_))
     }
 }
 
@@ -1,27 +1,84 @@
 //! This module contains the Ewmh trait, which implements methods 
 //! that expose EWMH functionality.
-//! Currently implemented by XCBConnection.
+//! Implemented by each backend.
 
+#[cfg(feature = "xcb-backend")]
 use xcb_util::ewmh;
 
 use crate::x::core::{
     Atom,
-    XCBConnection, 
     XWindowID, 
-    ClientMessageData as CMData
-};
-use crate::core::Workspace;
-use crate::types::{
-    NetWindowStates
+    Timestamp,
+    SyncCounter,
 };
+#[cfg(feature = "xcb-backend")]
+use crate::x::core::XCBConnection;
+use crate::types::{NetWindowStates, Strut, Geometry, Icon};
 
 pub trait Ewmh {
     fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>>;
     fn get_window_states(&self, window: XWindowID) -> NetWindowStates;
     fn set_supported(&self, screen_idx: i32, atoms: &[Atom]);
-    fn set_wm_state(&self, window: XWindowID, atoms: &[Atom]);
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates);
+
+    /// Set _NET_CLIENT_LIST, with clients in mapping order.
+    fn set_client_list(&self, screen_idx: i32, clients: &[XWindowID]);
+
+    /// Set _NET_CLIENT_LIST_STACKING, with clients from bottom to top.
+    fn set_client_list_stacking(&self, screen_idx: i32, clients: &[XWindowID]);
+
+    /// Set _NET_NUMBER_OF_DESKTOPS.
+    fn set_number_of_desktops(&self, screen_idx: i32, number: u32);
+
+    /// Set _NET_CURRENT_DESKTOP, the index of the current workspace.
+    fn set_current_desktop(&self, screen_idx: i32, idx: u32);
+
+    /// Set _NET_DESKTOP_NAMES, with names in workspace order.
+    fn set_desktop_names(&self, screen_idx: i32, names: &[&str]);
+
+    /// Set _NET_WM_DESKTOP, the index of the workspace the window is on.
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32);
+
+    /// Set _NET_ACTIVE_WINDOW, or clear it by passing `consts::NONE`.
+    fn set_active_window(&self, screen_idx: i32, window: XWindowID);
+
+    /// Set _NET_SUPPORTING_WM_CHECK on `window`, pointing to `check`.
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID);
+
+    /// Set _NET_WM_NAME.
+    fn set_wm_name(&self, window: XWindowID, name: &str);
+
+    /// Get the space reserved by a dock, from _NET_WM_STRUT_PARTIAL
+    /// or else from _NET_WM_STRUT.
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut>;
+
+    /// Set _NET_WORKAREA, with one area per workspace.
+    fn set_workarea(&self, screen_idx: i32, areas: &[Geometry]);
+
+    /// Set _NET_SHOWING_DESKTOP, whether windows are hidden to show the desktop.
+    fn set_showing_desktop(&self, screen_idx: i32, showing: bool);
+
+    /// Send a _NET_WM_PING to the window, which the client should send
+    /// back to the root window with the same timestamp.
+    fn send_ping(&self, window: XWindowID, time: Timestamp);
+
+    /// Get the XSync counter the client updates after handling a configure,
+    /// from _NET_WM_SYNC_REQUEST_COUNTER.
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter>;
+
+    /// Send a _NET_WM_SYNC_REQUEST, asking the client to set its counter
+    /// to `value` once it has handled the next configure.
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp);
+
+    /// Get the time of the last user input in the window, from _NET_WM_USER_TIME
+    /// on the window set as _NET_WM_USER_TIME_WINDOW, or else on the window itself.
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp>;
+
+    /// Get the icons of the window at each size it provides, from _NET_WM_ICON.
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon>;
 }
 
+#[cfg(feature = "xcb-backend")]
 impl Ewmh for XCBConnection {
     //fn get_wm_name()
     fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>> {
@@ -38,9 +95,9 @@ impl Ewmh for XCBConnection {
         if let Some(states) = ewmh::get_wm_state(&self.conn, window)
         .get_reply().ok()
         .map(|ok| ok.atoms().to_owned()) {
-            return NetWindowStates::from(states)
+            NetWindowStates::from_atoms(&states, &self.atoms)
         } else {
-            return NetWindowStates::new()
+            NetWindowStates::new()
         }
     }
 
@@ -48,114 +105,157 @@ impl Ewmh for XCBConnection {
         ewmh::set_supported(&self.conn, screen_idx, atoms);
     }
 
-    fn set_wm_state(&self, window: XWindowID, atoms: &[Atom]) {
-        ewmh::set_wm_state(&self.conn, window, atoms);
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates) {
+        ewmh::set_wm_state(&self.conn, window, &states.to_atoms(&self.atoms));
     }
-}
 
-// impl dyn Ewmh {
-//     fn change_window_state(&)
-// }
-
-/// Called from `wm::clientmessage`.
-pub(crate) fn handle_wm_state(
-    conn: &XCBConnection, 
-    ws: &mut Workspace, 
-    window: XWindowID,
-    data: CMData,
-) {
-    //todo: implement handling
-    assert!(data.is_u32());
-
-    let raw = conn.get_raw();
-
-    if let CMData::U32(data) = data {
-        let action = data[0];
-        let prop1 = data[1];
-        let prop2 = data[2];
-
-        if prop1 != 0 {
-            debug!("Prop1 is not empty: {}", prop1);
-            resolve_state(raw, prop1);
-        }
-        if prop2 != 0 {
-            debug!("Prop2 is not empty: {}", prop2);
-            resolve_state(raw, prop2);
+    fn set_client_list(&self, screen_idx: i32, clients: &[XWindowID]) {
+        ewmh::set_client_list(&self.conn, screen_idx, clients);
+    }
+
+    fn set_client_list_stacking(&self, screen_idx: i32, clients: &[XWindowID]) {
+        ewmh::set_client_list_stacking(&self.conn, screen_idx, clients);
+    }
+
+    fn set_number_of_desktops(&self, screen_idx: i32, number: u32) {
+        ewmh::set_number_of_desktops(&self.conn, screen_idx, number);
+    }
+
+    fn set_current_desktop(&self, screen_idx: i32, idx: u32) {
+        ewmh::set_current_desktop(&self.conn, screen_idx, idx);
+    }
+
+    fn set_desktop_names(&self, screen_idx: i32, names: &[&str]) {
+        ewmh::set_desktop_names(&self.conn, screen_idx, names.iter().copied());
+    }
+
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32) {
+        ewmh::set_wm_desktop(&self.conn, window, idx);
+    }
+
+    fn set_active_window(&self, screen_idx: i32, window: XWindowID) {
+        ewmh::set_active_window(&self.conn, screen_idx, window);
+    }
+
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID) {
+        ewmh::set_supporting_wm_check(&self.conn, window, check);
+    }
+
+    fn set_wm_name(&self, window: XWindowID, name: &str) {
+        ewmh::set_wm_name(&self.conn, window, name);
+    }
+
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut> {
+        debug!("Getting struts for window {}", window);
+
+        if let Ok(partial) = ewmh::get_wm_strut_partial(&self.conn, window).get_reply() {
+            return Some(Strut {
+                left: partial.left(),
+                right: partial.right(),
+                top: partial.top(),
+                bottom: partial.bottom(),
+                left_start_y: partial.left_start_y(),
+                left_end_y: partial.left_end_y(),
+                right_start_y: partial.right_start_y(),
+                right_end_y: partial.right_end_y(),
+                top_start_x: partial.top_start_x(),
+                top_end_x: partial.top_end_x(),
+                bottom_start_x: partial.bottom_start_x(),
+                bottom_end_x: partial.bottom_end_x(),
+            })
         }
 
-        let win = ws.windows.lookup_mut(window);
-
-        match action {
-            ewmh::STATE_ADD => {
-                if let Some(win) = win {
-                    if prop1 != 0 {
-                        win.add_wm_state(prop1);
-                    }
-                    if prop2 != 0 {
-                        win.add_wm_state(prop2);
-                    }
-                    win.set_wm_states(conn);
-                } else {
-                    let mut states = conn.get_window_states(window);
-                    if prop1 != 0 {states.add(prop1);}
-                    if prop2 != 0 {states.add(prop2);}
-                    conn.set_wm_state(window, &states);
-                }
-            }
-            ewmh::STATE_REMOVE => {
-                if let Some(win) = win {
-                    if prop1 != 0 {
-                        win.remove_wm_state(prop1);
-                    }
-                    if prop2 != 0 {
-                        win.remove_wm_state(prop2);
-                    }
-                    win.set_wm_states(conn);
-                } else {
-                    let mut states = conn.get_window_states(window);
-                    if prop1 != 0 {states.remove(prop1);}
-                    if prop2 != 0 {states.remove(prop2);}
-                    conn.set_wm_state(window, &states);
-                }
-            }
-            ewmh::STATE_TOGGLE => {
-                //todo: we need to remember the previous state of the window
-                debug!("Toggle");
-            }
-            _ => {unreachable!()}
+        ewmh::get_wm_strut(&self.conn, window).get_reply().ok()
+            .map(|strut| Strut::from_edges(strut.left(), strut.right(), strut.top(), strut.bottom()))
+    }
+
+    fn set_workarea(&self, screen_idx: i32, areas: &[Geometry]) {
+        let areas: Vec<ewmh::Geometry> = areas.iter().map(|area| ewmh::Geometry {
+            x: area.x as u32,
+            y: area.y as u32,
+            width: area.width as u32,
+            height: area.height as u32,
+        }).collect();
+
+        ewmh::set_work_area(&self.conn, screen_idx, &areas);
+    }
+
+    fn set_showing_desktop(&self, screen_idx: i32, showing: bool) {
+        ewmh::set_showing_desktop(&self.conn, screen_idx, showing as u32);
+    }
+
+    fn send_ping(&self, window: XWindowID, time: Timestamp) {
+        debug!("Pinging window {}", window);
+
+        ewmh::send_wm_ping(&self.conn, window, time);
+    }
+
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter> {
+        debug!("Getting sync request counter for window {}", window);
+
+        self.get_u32_property(window, self.atoms.WM_SYNC_REQUEST_COUNTER, xcb::ATOM_CARDINAL)
+    }
+
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp) {
+        debug!("Sending sync request {} to window {}", value, window);
+
+        let msg_data = xcb::ClientMessageData::from_data32([
+            self.atoms.WM_SYNC_REQUEST,
+            time,
+            value as u32,
+            (value >> 32) as u32,
+            0,
+        ]);
+
+        let msg_event = xcb::ClientMessageEvent::new(32, window, self.atoms.WM_PROTOCOLS, msg_data);
+
+        xcb::send_event(&self.conn, false, window, xcb::EVENT_MASK_NO_EVENT, &msg_event);
+    }
+
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp> {
+        debug!("Getting user time for window {}", window);
+
+        let window = self.get_u32_property(window, self.atoms.WM_USER_TIME_WINDOW, xcb::ATOM_WINDOW)
+            .unwrap_or(window);
+
+        self.get_u32_property(window, self.atoms.WM_USER_TIME, xcb::ATOM_CARDINAL)
+    }
+
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon> {
+        debug!("Getting icons for window {}", window);
+
+        let reply = xcb::get_property(
+            &self.conn,
+            false,
+            window,
+            self.atoms.WM_ICON,
+            xcb::ATOM_CARDINAL,
+            0, u32::MAX,
+        ).get_reply();
+
+        match reply {
+            Ok(reply) if reply.format() == 32 => Icon::decode_all(reply.value::<u32>()),
+            _ => Vec::new(),
         }
-    } else {
-        error!("Client message data incorrectly formatted")
     }
 }
 
-//todo: make this return shit
-fn resolve_state(conn: &ewmh::Connection, state: Atom) {
-    if state == conn.WM_STATE_MODAL() {
-        debug!("State is modal");
-    } else if state == conn.WM_STATE_STICKY() {
-        debug!("State is sticky");
-    } else if state == conn.WM_STATE_MAXIMIZED_VERT() {
-        debug!("State is max-vert");
-    } else if state == conn.WM_STATE_MAXIMIZED_HORZ() {
-        debug!("State is max-horz");
-    } else if state == conn.WM_STATE_SHADED() {
-        debug!("State is shaded");
-    } else if state == conn.WM_STATE_SKIP_TASKBAR() {
-        debug!("State is skip taskbar");
-    } else if state == conn.WM_STATE_SKIP_PAGER() {
-        debug!("State is skip pager");
-    } else if state == conn.WM_STATE_HIDDEN() {
-        debug!("State is hidden");
-    } else if state == conn.WM_STATE_FULLSCREEN() {
-        debug!("State is fullscreen");
-    } else if state == conn.WM_STATE_ABOVE() {
-        debug!("State is above");
-    } else if state == conn.WM_STATE_BELOW() {
-        debug!("State is below");
-    } else if state == conn.WM_STATE_DEMANDS_ATTENTION() {
-        debug!("State is urgent");
-    } else {
-        error!("Unknown WM_STATE value {}", state);
-    }
-}
\ No newline at end of file
+#[cfg(feature = "xcb-backend")]
+impl XCBConnection {
+    /// Gets the first value of a 32-bit property.
+    fn get_u32_property(&self, window: XWindowID, property: Atom, type_: Atom) -> Option<u32> {
+        let reply = xcb::get_property(
+            &self.conn,
+            false,
+            window,
+            property,
+            type_,
+            0, 1,
+        ).get_reply().ok()?;
+
+        if reply.format() != 32 || reply.value_len() == 0 {
+            return None
+        }
+        reply.value::<u32>().first().copied()
+    }
+}
@@ -1,11 +1,14 @@
 //! This module contains the Icccm trait, which implements methods 
 //! that expose ICCCM functionality.
-//! Currently implemented by XCBConnection.
+//! Implemented by each backend.
 
+#[cfg(feature = "xcb-backend")]
 use xcb_util::icccm;
 
-use crate::x::{XCBConnection, XWindowID, Atom};
-use crate::types::{XWinProperties, WindowState};
+use crate::x::{XWindowID, Atom};
+#[cfg(feature = "xcb-backend")]
+use crate::x::XCBConnection;
+use crate::types::{XWinProperties, WindowState, SizeHints, WmHints};
 
 /// Exposes ICCCM functionality for an object holding an XCB connection.
 /// Mainly used to expose ICCCM functionality for XCBConnection only when needed.
@@ -19,15 +22,24 @@ pub trait Icccm {
     fn get_client_properties(&self, window: XWindowID) -> XWinProperties;
     fn get_wm_name(&self, window: XWindowID) -> String;
     fn get_wm_icon_name(&self, window: XWindowID) -> String;
-    fn get_wm_size_hints(&self, window: XWindowID) -> Option<icccm::SizeHints>;
-    fn get_wm_hints(&self, window: XWindowID) -> Option<icccm::WmHints>;    
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints>;
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints>;
     fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)>;
     fn get_wm_protocols(&self, window: XWindowID) -> Option<Vec<Atom>>;
     fn get_wm_state(&self, window: XWindowID) -> WindowState;
     fn get_wm_transient_for(&self, window: XWindowID) -> Option<XWindowID>;
     fn get_urgency(&self, window: XWindowID) -> bool;
+
+    /// Sets WM_STATE, which only the window manager may change.
+    /// 
+    /// Named so as not to clash with `Ewmh::set_wm_state`.
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState);
+
+    /// Sends WM_TAKE_FOCUS, with the time set with `XConn::set_timestamp`.
+    fn send_take_focus(&self, window: XWindowID);
 }
 
+#[cfg(feature = "xcb-backend")]
 impl Icccm for XCBConnection {
     fn get_client_properties(&self, window: XWindowID) -> XWinProperties {
         debug!("Getting client properties for window {}", window);
@@ -49,8 +61,10 @@ impl Icccm for XCBConnection {
             Ok(reply) => reply.name().into(),
             Err(_) => String::new()
         };
-        let wm_size_hints = wm_size_hints_cookie.get_reply().ok();
-        let wm_hints = wm_hints_cookie.get_reply().ok();
+        let wm_size_hints = wm_size_hints_cookie.get_reply().ok()
+            .map(|reply| size_hints_from_reply(&reply));
+        let wm_hints = wm_hints_cookie.get_reply().ok()
+            .map(|reply| wm_hints_from_reply(&reply));
         let wm_class = match wm_class_cookie.get_reply() {
             Ok(reply) => (reply.instance().into(), reply.class().into()),
             Err(_) => (String::new(), String::new())
@@ -60,18 +74,18 @@ impl Icccm for XCBConnection {
             Err(_) => None
         };
         let wm_state = match wm_state_cookie.get_reply() {
-            Ok(reply) => reply.state().into(),
+            Ok(reply) => (reply.state() as u32).into(),
             Err(_) => WindowState::default()
         };
 
         XWinProperties {
-            wm_name: wm_name,
-            wm_icon_name: wm_icon_name,
-            wm_size_hints: wm_size_hints,
-            wm_hints: wm_hints,
-            wm_class: wm_class,
-            wm_protocols: wm_protocols,
-            wm_state: wm_state,
+            wm_name,
+            wm_icon_name,
+            wm_size_hints,
+            wm_hints,
+            wm_class,
+            wm_protocols,
+            wm_state,
         }
     }
 
@@ -89,12 +103,14 @@ impl Icccm for XCBConnection {
         }
     }
 
-    fn get_wm_size_hints(&self, window: XWindowID) -> Option<icccm::SizeHints> {
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints> {
         icccm::get_wm_normal_hints(&self.conn, window).get_reply().ok()
+            .map(|reply| size_hints_from_reply(&reply))
     }
 
-    fn get_wm_hints(&self, window: XWindowID) -> Option<icccm::WmHints> {
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints> {
         icccm::get_wm_hints(&self.conn, window).get_reply().ok()
+            .map(|reply| wm_hints_from_reply(&reply))
     }
 
     fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)> {
@@ -135,26 +151,89 @@ impl Icccm for XCBConnection {
                 reply.value_len() == 0 {
                     return None
                 }
-                return Some(reply.value()[0]) 
+                Some(reply.value()[0]) 
             }
             Err(e) => {
                 warn!("Error: {}", e);
-                return None 
+                None 
             }
         }
     }
 
     fn get_wm_state(&self, window: XWindowID) -> WindowState {
         match icccm::get_wm_state(&self.conn, window).get_reply() {
-            Ok(reply) => reply.state().into(),
+            Ok(reply) => (reply.state() as u32).into(),
             Err(_) => WindowState::default()
         }
     }
 
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState) {
+        debug!("Setting WM_STATE of window {} to {:?}", window, state);
+        // icccm::set_wm_state passes the wrong mode, so set it directly
+        xcb::change_property(
+            &self.conn,
+            xcb::PROP_MODE_REPLACE as u8,
+            window,
+            self.atoms.ICCCM_WM_STATE,
+            self.atoms.ICCCM_WM_STATE,
+            32,
+            &[u32::from(state), xcb::NONE],
+        );
+    }
+
+    fn send_take_focus(&self, window: XWindowID) {
+        debug!("Sending WM_TAKE_FOCUS to window {}", window);
+
+        let msg_data = xcb::ClientMessageData::from_data32([
+            self.atoms.WM_TAKE_FOCUS,
+            self.time.get(),
+            0, 0, 0,
+        ]);
+
+        let msg_event = xcb::ClientMessageEvent::new(32, window, self.atoms.WM_PROTOCOLS, msg_data);
+
+        xcb::send_event(&self.conn, false, window, xcb::EVENT_MASK_NO_EVENT, &msg_event);
+    }
+
     fn get_urgency(&self, window: XWindowID) -> bool {
         if let Some(hints) = self.get_wm_hints(window) {
-            return if let Some(u) = hints.is_urgent() {u} else {false}
+            return hints.urgent
         }
         false
     }
+}
+
+#[cfg(feature = "xcb-backend")]
+fn size_hints_from_reply(hints: &icccm::SizeHints) -> SizeHints {
+    SizeHints {
+        position: hints.position(),
+        size: hints.size(),
+        min_size: hints.min_size(),
+        max_size: hints.max_size(),
+        resize: hints.resize(),
+        min_aspect: hints.aspect().map(|(i,_)| i),
+        max_aspect: hints.aspect().map(|(_,i)| i),
+        base: hints.base(),
+        gravity: hints.gravity(),
+    }
+}
+
+#[cfg(feature = "xcb-backend")]
+fn wm_hints_from_reply(hints: &icccm::WmHints) -> WmHints {
+    use WindowState::*;
+
+    WmHints {
+        state: if hints.is_normal() {
+            Normal
+        } else if hints.is_iconic() {
+            Iconic
+        } else if hints.is_withdrawn() {
+            Withdrawn
+        } else {
+            Normal
+        },
+        urgent: hints.is_urgent().unwrap_or(false),
+        // clients that do not say otherwise are given the focus
+        input: hints.input().unwrap_or(true),
+    }
 }
\ No newline at end of file
@@ -1,15 +1,33 @@
 //! This module provides RaccoonWM's main interface to the X server.
-//! Built on top of the XCB crate, it exposes a connection handle
-//! and provides the basic methods for retrieving data from and setting data
-//! on the X server, that are called by other modules within RaccoonWM.
+//! It exposes a connection handle and provides the basic methods for
+//! retrieving data from and setting data on the X server, that are called
+//! by other modules within RaccoonWM.
+//!
+//! Two backends are available, selected with cargo features:
+//! `xcb-backend` (the default) is built on top of the XCB crate,
+//! and `x11rb-backend` is built on top of the pure-Rust x11rb crate.
 
 pub mod core;
+#[cfg(feature = "xcb-backend")]
 pub mod xserver;
+#[cfg(feature = "x11rb-backend")]
+pub mod x11rbserver;
+pub mod consts;
 pub mod ewmh;
 pub mod icccm;
 pub mod event;
+pub mod dummy;
+pub mod batch;
+pub mod record;
 
-pub use self::core::{XCBConnection, XWindow, XWindowID, Atom};
+pub use self::core::{XConn, XError, XWindow, XWindowID, Atom};
+#[cfg(feature = "xcb-backend")]
+pub use self::core::XCBConnection;
+#[cfg(feature = "x11rb-backend")]
+pub use self::core::X11RBConnection;
 pub use self::event::XEvent;
 pub use self::icccm::Icccm;
-pub use self::ewmh::Ewmh;
\ No newline at end of file
+pub use self::ewmh::Ewmh;
+pub use self::dummy::DummyConn;
+pub use self::batch::ConfigureBatch;
+pub use self::record::{RecordingConn, ReplayConn};
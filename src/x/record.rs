@@ -0,0 +1,891 @@
+//! Recording and replaying of X event streams.
+//!
+//! `RecordingConn` wraps another connection and writes every event
+//! the window manager receives to a file, along with the replies to
+//! every query it makes (window attributes, properties, geometry, etc.)
+//! while handling that event.
+//!
+//! `ReplayConn` reads such a file back and plays the events to the
+//! window manager, answering its queries from the recorded replies.
+//! Requests that change the state of the X server are not sent anywhere;
+//! they are recorded, and can be inspected afterwards in the same way
+//! as with `DummyConn`.
+//!
+//! Recordings are stored as JSON, one entry per line.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::window::Client;
+use crate::types::{
+    Geometry,
+    NetWindowStates,
+    XWinProperties,
+    WindowState,
+    SizeHints,
+    WmHints,
+    Strut,
+    Icon,
+};
+use super::core::*;
+use super::dummy::Request;
+use super::{Icccm, Ewmh};
+
+/// A query made by the window manager, whose reply is recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Query {
+    QueryTree(XWindowID),
+    QueryPointer(XWindowID),
+    GetWindowAttributes(XWindowID),
+    ChangeWindowAttributesChecked(XWindowID),
+    GetGeometry(XWindowID),
+    ClientProperties(XWindowID),
+    WmName(XWindowID),
+    WmIconName(XWindowID),
+    WmSizeHints(XWindowID),
+    WmHints(XWindowID),
+    WmClass(XWindowID),
+    WmProtocols(XWindowID),
+    WmState(XWindowID),
+    WmTransientFor(XWindowID),
+    Urgency(XWindowID),
+    WindowType(XWindowID),
+    WindowStates(XWindowID),
+    WmStrut(XWindowID),
+    WmSyncRequestCounter(XWindowID),
+    SyncCounter(SyncCounter),
+    CreateSyncAlarm(SyncCounter),
+    WmUserTime(XWindowID),
+    WmIcon(XWindowID),
+    CreateHiddenWindow,
+}
+
+/// A single line of a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Entry {
+    /// Details of the connection, always the first entry.
+    Setup {
+        root: XWindowID,
+        atoms: Vec<(String, Atom)>,
+    },
+    /// An event received from the X server.
+    Event(XEvent),
+    /// The reply to a query.
+    Reply(Query, Value),
+}
+
+fn recording_error<E: std::fmt::Display>(e: E) -> XError {
+    XError::Recording(e.to_string())
+}
+
+/// A connection that records the events and replies passing through it.
+pub struct RecordingConn<X: XConn> {
+    conn: X,
+    writer: RefCell<Box<dyn Write>>,
+}
+
+impl<X: XConn> RecordingConn<X> {
+    /// Wraps a connection, recording to the given writer.
+    pub fn new<W: Write + 'static>(conn: X, writer: W) -> Result<Self> {
+        let new = Self {
+            conn,
+            writer: RefCell::new(Box::new(writer)),
+        };
+
+        let setup = Entry::Setup {
+            root: new.conn.get_root_id(),
+            atoms: new.conn.get_atoms().to_list().into_iter()
+                .map(|(name, atom)| (name.to_string(), atom))
+                .collect(),
+        };
+        new.write(&setup)?;
+
+        Ok(new)
+    }
+
+    /// Wraps a connection, recording to the file at the given path.
+    pub fn create<P: AsRef<Path>>(conn: X, path: P) -> Result<Self> {
+        Self::new(conn, File::create(path).map_err(recording_error)?)
+    }
+
+    /// Get a reference to the underlying connection.
+    pub fn inner(&self) -> &X {
+        &self.conn
+    }
+
+    fn write(&self, entry: &Entry) -> Result<()> {
+        let mut writer = self.writer.borrow_mut();
+
+        serde_json::to_writer(&mut *writer, entry).map_err(recording_error)?;
+        writer.write_all(b"\n").map_err(recording_error)?;
+        // flush on every event, so that a crash leaves a usable recording
+        if let Entry::Event(_) = entry {
+            writer.flush().map_err(recording_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Records the reply to a query and passes it on.
+    fn record<T: Serialize>(&self, query: Query, reply: T) -> T {
+        let res = serde_json::to_value(&reply)
+            .map_err(recording_error)
+            .and_then(|value| self.write(&Entry::Reply(query, value)));
+        if let Err(e) = res {
+            error!("{}", e);
+        }
+
+        reply
+    }
+
+    /// Records a fallible reply, without the details of any error.
+    fn record_result<T: Serialize>(&self, query: Query, reply: Result<T>) -> Result<T> {
+        let _ = self.record(query, reply.as_ref().map_err(|_| ()));
+
+        reply
+    }
+}
+
+impl<X: XConn> XConn for RecordingConn<X> {
+    fn get_atoms(&self) -> InternedAtoms {
+        self.conn.get_atoms()
+    }
+
+    fn get_root_id(&self) -> XWindowID {
+        self.conn.get_root_id()
+    }
+
+    fn set_root_scr(&mut self, scr: i32) {
+        self.conn.set_root_scr(scr)
+    }
+
+    fn next_event(&self) -> Result<XEvent> {
+        let event = self.conn.next_event()?;
+
+        if let Err(e) = self.write(&Entry::Event(event)) {
+            error!("{}", e);
+        }
+
+        Ok(event)
+    }
+
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
+        self.record_result(Query::QueryTree(window), self.conn.query_tree(window))
+    }
+
+    fn query_pointer(&self, window: XWindowID) -> Result<PointerQueryReply> {
+        self.record_result(Query::QueryPointer(window), self.conn.query_pointer(window))
+    }
+
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64> {
+        self.record_result(Query::SyncCounter(counter), self.conn.query_sync_counter(counter))
+    }
+
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm> {
+        self.record_result(Query::CreateSyncAlarm(counter), self.conn.create_sync_alarm(counter, value))
+    }
+
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64) {
+        self.conn.set_sync_alarm(alarm, value)
+    }
+
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm) {
+        self.conn.destroy_sync_alarm(alarm)
+    }
+
+    fn create_cursor(&mut self, glyph: u16) -> Result<()> {
+        self.conn.create_cursor(glyph)
+    }
+
+    fn set_cursor(&mut self, window: XWindowID) {
+        self.conn.set_cursor(window)
+    }
+
+    fn create_hidden_window(&self) -> Result<XWindowID> {
+        self.record_result(Query::CreateHiddenWindow, self.conn.create_hidden_window())
+    }
+
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes> {
+        self.record(Query::GetWindowAttributes(window), self.conn.get_window_attributes(window))
+    }
+
+    fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
+        self.conn.change_window_attributes(window, attrs)
+    }
+
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
+        self.record_result(
+            Query::ChangeWindowAttributesChecked(window),
+            self.conn.change_window_attributes_checked(window, attrs)
+        )
+    }
+
+    fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
+        self.conn.configure_window(window, attrs)
+    }
+
+    fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
+        self.conn.reparent_window(window, parent)
+    }
+
+    fn map_window(&self, window: XWindowID) {
+        self.conn.map_window(window)
+    }
+
+    fn unmap_window(&self, window: XWindowID) {
+        self.conn.unmap_window(window)
+    }
+
+    fn destroy_window(&self, window: &Client) {
+        self.conn.destroy_window(window)
+    }
+
+    fn kill_client(&self, window: XWindowID) {
+        self.conn.kill_client(window)
+    }
+
+    fn touch_property(&self, window: XWindowID, property: Atom, type_: Atom) {
+        self.conn.touch_property(window, property, type_)
+    }
+
+    fn set_timestamp(&self, time: Timestamp) {
+        self.conn.set_timestamp(time)
+    }
+
+    fn set_input_focus(&self, window: XWindowID) {
+        self.conn.set_input_focus(window)
+    }
+
+    fn set_geometry(&self, window: XWindowID, geom: Geometry) {
+        self.conn.set_geometry(window, geom)
+    }
+
+    fn get_geometry(&self, window: XWindowID) -> Result<Geometry> {
+        self.record_result(Query::GetGeometry(window), self.conn.get_geometry(window))
+    }
+
+    fn grab_key(&self, window: XWindowID, mask: ModMask, keysym: KeySym) {
+        self.conn.grab_key(window, mask, keysym)
+    }
+
+    fn grab_button(&self,
+        window: XWindowID,
+        mask: ButtonMask,
+        button: ButtonIndex,
+        modmask: ModMask,
+        confine: bool
+    ) {
+        self.conn.grab_button(window, mask, button, modmask, confine)
+    }
+
+    fn grab_pointer(&self, window: XWindowID, mask: EventMask) {
+        self.conn.grab_pointer(window, mask)
+    }
+
+    fn ungrab_pointer(&self) {
+        self.conn.ungrab_pointer()
+    }
+
+    fn grab_keyboard(&self, window: XWindowID) {
+        self.conn.grab_keyboard(window)
+    }
+
+    fn ungrab_keyboard(&self) {
+        self.conn.ungrab_keyboard()
+    }
+
+    fn flush(&self) {
+        self.conn.flush()
+    }
+}
+
+impl<X: XConn> Icccm for RecordingConn<X> {
+    fn get_client_properties(&self, window: XWindowID) -> XWinProperties {
+        self.record(Query::ClientProperties(window), self.conn.get_client_properties(window))
+    }
+
+    fn get_wm_name(&self, window: XWindowID) -> String {
+        self.record(Query::WmName(window), self.conn.get_wm_name(window))
+    }
+
+    fn get_wm_icon_name(&self, window: XWindowID) -> String {
+        self.record(Query::WmIconName(window), self.conn.get_wm_icon_name(window))
+    }
+
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints> {
+        self.record(Query::WmSizeHints(window), self.conn.get_wm_size_hints(window))
+    }
+
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints> {
+        self.record(Query::WmHints(window), self.conn.get_wm_hints(window))
+    }
+
+    fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)> {
+        self.record(Query::WmClass(window), self.conn.get_wm_class(window))
+    }
+
+    fn get_wm_protocols(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.record(Query::WmProtocols(window), self.conn.get_wm_protocols(window))
+    }
+
+    fn get_wm_state(&self, window: XWindowID) -> WindowState {
+        self.record(Query::WmState(window), self.conn.get_wm_state(window))
+    }
+
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState) {
+        self.conn.set_icccm_wm_state(window, state)
+    }
+
+    fn send_take_focus(&self, window: XWindowID) {
+        self.conn.send_take_focus(window)
+    }
+
+    fn get_wm_transient_for(&self, window: XWindowID) -> Option<XWindowID> {
+        self.record(Query::WmTransientFor(window), self.conn.get_wm_transient_for(window))
+    }
+
+    fn get_urgency(&self, window: XWindowID) -> bool {
+        self.record(Query::Urgency(window), self.conn.get_urgency(window))
+    }
+}
+
+impl<X: XConn> Ewmh for RecordingConn<X> {
+    fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.record(Query::WindowType(window), self.conn.get_window_type(window))
+    }
+
+    fn get_window_states(&self, window: XWindowID) -> NetWindowStates {
+        self.record(Query::WindowStates(window), self.conn.get_window_states(window))
+    }
+
+    fn set_supported(&self, screen_idx: i32, atoms: &[Atom]) {
+        self.conn.set_supported(screen_idx, atoms)
+    }
+
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates) {
+        self.conn.set_wm_state(window, states)
+    }
+
+    fn set_client_list(&self, screen_idx: i32, clients: &[XWindowID]) {
+        self.conn.set_client_list(screen_idx, clients)
+    }
+
+    fn set_client_list_stacking(&self, screen_idx: i32, clients: &[XWindowID]) {
+        self.conn.set_client_list_stacking(screen_idx, clients)
+    }
+
+    fn set_number_of_desktops(&self, screen_idx: i32, number: u32) {
+        self.conn.set_number_of_desktops(screen_idx, number)
+    }
+
+    fn set_current_desktop(&self, screen_idx: i32, idx: u32) {
+        self.conn.set_current_desktop(screen_idx, idx)
+    }
+
+    fn set_desktop_names(&self, screen_idx: i32, names: &[&str]) {
+        self.conn.set_desktop_names(screen_idx, names)
+    }
+
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32) {
+        self.conn.set_wm_desktop(window, idx)
+    }
+
+    fn set_active_window(&self, screen_idx: i32, window: XWindowID) {
+        self.conn.set_active_window(screen_idx, window)
+    }
+
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID) {
+        self.conn.set_supporting_wm_check(window, check)
+    }
+
+    fn set_wm_name(&self, window: XWindowID, name: &str) {
+        self.conn.set_wm_name(window, name)
+    }
+
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut> {
+        self.record(Query::WmStrut(window), self.conn.get_wm_strut(window))
+    }
+
+    fn set_workarea(&self, screen_idx: i32, areas: &[Geometry]) {
+        self.conn.set_workarea(screen_idx, areas)
+    }
+
+    fn set_showing_desktop(&self, screen_idx: i32, showing: bool) {
+        self.conn.set_showing_desktop(screen_idx, showing)
+    }
+
+    fn send_ping(&self, window: XWindowID, time: Timestamp) {
+        self.conn.send_ping(window, time)
+    }
+
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter> {
+        self.record(
+            Query::WmSyncRequestCounter(window),
+            self.conn.get_wm_sync_request_counter(window)
+        )
+    }
+
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp) {
+        self.conn.send_sync_request(window, value, time)
+    }
+
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp> {
+        self.record(Query::WmUserTime(window), self.conn.get_wm_user_time(window))
+    }
+
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon> {
+        self.record(Query::WmIcon(window), self.conn.get_wm_icon(window))
+    }
+}
+
+/// Replies to queries, in the order they were made.
+type Replies = HashMap<Query, VecDeque<Value>>;
+
+/// A connection that replays a recording.
+///
+/// Events are handed out in the order they were recorded, and queries
+/// made while handling an event are answered with the replies recorded
+/// while handling that same event. If a query was not made during
+/// recording, the most recent reply to it is used instead, so that
+/// a recording stays usable as the window manager changes.
+pub struct ReplayConn {
+    atoms: InternedAtoms,
+    root: XWindowID,
+    events: RefCell<VecDeque<(XEvent, Replies)>>,
+    /// Replies for the event currently being handled.
+    replies: RefCell<Replies>,
+    /// The most recent reply to each query.
+    latest: RefCell<HashMap<Query, Value>>,
+    requests: RefCell<Vec<Request>>,
+    /// The time set with `set_timestamp`.
+    time: Cell<Timestamp>,
+}
+
+impl ReplayConn {
+    /// Reads a recording.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+
+        let first = lines.next()
+            .ok_or_else(|| XError::Recording("Recording is empty".into()))?
+            .map_err(recording_error)?;
+        let (root, recorded_atoms) = match serde_json::from_str(&first) {
+            Ok(Entry::Setup {root, atoms}) => (root, atoms),
+            Ok(_) => return Err(XError::Recording("Recording has no setup entry".into())),
+            Err(e) => return Err(recording_error(e)),
+        };
+
+        // atoms added since the recording was made get values of their own
+        let mut next_atom = recorded_atoms.iter().map(|(_, atom)| *atom).max().unwrap_or(0);
+        let atoms: Vec<Atom> = InternedAtoms::NAMES.iter()
+            .map(|name| recorded_atoms.iter()
+                .find(|(recorded, _)| recorded == name)
+                .map(|(_, atom)| *atom)
+                .unwrap_or_else(|| {next_atom += 1; next_atom}))
+            .collect();
+
+        let mut setup_replies = Replies::new();
+        let mut events: VecDeque<(XEvent, Replies)> = VecDeque::new();
+
+        for line in lines {
+            let line = line.map_err(recording_error)?;
+            if line.trim().is_empty() {
+                continue
+            }
+            match serde_json::from_str(&line).map_err(recording_error)? {
+                Entry::Event(event) => events.push_back((event, Replies::new())),
+                Entry::Reply(query, value) => {
+                    let replies = match events.back_mut() {
+                        Some((_, replies)) => replies,
+                        None => &mut setup_replies,
+                    };
+                    replies.entry(query).or_default().push_back(value);
+                }
+                Entry::Setup {..} => {
+                    return Err(XError::Recording("Duplicate setup entry".into()))
+                }
+            }
+        }
+
+        Ok(Self {
+            atoms: InternedAtoms::from_interned(&atoms),
+            root,
+            events: RefCell::new(events),
+            replies: RefCell::new(setup_replies),
+            latest: RefCell::new(HashMap::new()),
+            requests: RefCell::new(Vec::new()),
+            time: Cell::new(0),
+        })
+    }
+
+    /// Reads a recording from the file at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path).map_err(recording_error)?))
+    }
+
+    /// The number of events that have not been replayed yet.
+    pub fn remaining_events(&self) -> usize {
+        self.events.borrow().len()
+    }
+
+    /// Returns all requests made by the window manager so far, in order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.borrow().clone()
+    }
+
+    fn request(&self, request: Request) {
+        self.requests.borrow_mut().push(request);
+    }
+
+    /// Looks up the reply to a query.
+    fn reply<T: DeserializeOwned>(&self, query: Query) -> Option<T> {
+        let value = {
+            let mut replies = self.replies.borrow_mut();
+            match replies.get_mut(&query) {
+                // the last reply is reused if the query is made again
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        }.or_else(|| self.latest.borrow().get(&query).cloned());
+
+        let value = match value {
+            Some(value) => value,
+            None => {
+                warn!("No recorded reply for {:?}", query);
+                return None
+            }
+        };
+        self.latest.borrow_mut().insert(query.clone(), value.clone());
+
+        match serde_json::from_value(value) {
+            Ok(reply) => Some(reply),
+            Err(e) => {
+                error!("Invalid recorded reply for {:?}: {}", query, e);
+                None
+            }
+        }
+    }
+
+    /// Looks up the reply to a fallible query.
+    fn reply_result<T: DeserializeOwned>(&self, query: Query) -> Result<T> {
+        self.reply::<::core::result::Result<T, ()>>(query)
+            .and_then(|reply| reply.ok())
+            .ok_or(XError::RequestError)
+    }
+}
+
+impl XConn for ReplayConn {
+    fn get_atoms(&self) -> InternedAtoms {
+        self.atoms
+    }
+
+    fn get_root_id(&self) -> XWindowID {
+        self.root
+    }
+
+    fn set_root_scr(&mut self, _scr: i32) {}
+
+    fn next_event(&self) -> Result<XEvent> {
+        let (event, replies) = self.events.borrow_mut().pop_front()
+            .ok_or(XError::ConnectionClosed)?;
+
+        *self.replies.borrow_mut() = replies;
+
+        Ok(event)
+    }
+
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
+        self.reply_result(Query::QueryTree(window))
+    }
+
+    fn query_pointer(&self, window: XWindowID) -> Result<PointerQueryReply> {
+        self.reply_result(Query::QueryPointer(window))
+    }
+
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64> {
+        self.reply_result(Query::SyncCounter(counter))
+    }
+
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm> {
+        self.request(Request::CreateSyncAlarm(counter, value));
+        self.reply_result(Query::CreateSyncAlarm(counter))
+    }
+
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64) {
+        self.request(Request::SetSyncAlarm(alarm, value));
+    }
+
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm) {
+        self.request(Request::DestroySyncAlarm(alarm));
+    }
+
+    fn create_cursor(&mut self, glyph: u16) -> Result<()> {
+        self.request(Request::CreateCursor(glyph));
+        Ok(())
+    }
+
+    fn set_cursor(&mut self, window: XWindowID) {
+        self.request(Request::SetCursor(window));
+    }
+
+    fn create_hidden_window(&self) -> Result<XWindowID> {
+        let window = self.reply_result(Query::CreateHiddenWindow)?;
+        self.request(Request::CreateWindow(window));
+        Ok(window)
+    }
+
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes> {
+        self.reply::<Option<_>>(Query::GetWindowAttributes(window)).flatten()
+    }
+
+    fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
+        self.request(Request::ChangeWindowAttributes(window, attrs.to_vec()));
+    }
+
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
+        self.request(Request::ChangeWindowAttributes(window, attrs.to_vec()));
+        self.reply_result(Query::ChangeWindowAttributesChecked(window))
+    }
+
+    fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
+        self.request(Request::ConfigureWindow(window, attrs.to_vec()));
+    }
+
+    fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
+        self.request(Request::ReparentWindow(window, parent));
+    }
+
+    fn map_window(&self, window: XWindowID) {
+        self.request(Request::MapWindow(window));
+    }
+
+    fn unmap_window(&self, window: XWindowID) {
+        self.request(Request::UnmapWindow(window));
+    }
+
+    fn destroy_window(&self, window: &Client) {
+        self.request(Request::DestroyWindow(window.id()));
+    }
+
+    fn kill_client(&self, window: XWindowID) {
+        self.request(Request::KillClient(window));
+    }
+
+    fn touch_property(&self, window: XWindowID, property: Atom, _type: Atom) {
+        self.request(Request::TouchProperty(window, property));
+    }
+
+    fn set_timestamp(&self, time: Timestamp) {
+        self.time.set(time);
+    }
+
+    fn set_input_focus(&self, window: XWindowID) {
+        self.request(Request::SetInputFocus(window));
+    }
+
+    fn set_geometry(&self, window: XWindowID, geom: Geometry) {
+        self.configure_window(window, &crate::utils::configure_geometry(geom));
+    }
+
+    fn get_geometry(&self, window: XWindowID) -> Result<Geometry> {
+        self.reply_result(Query::GetGeometry(window))
+    }
+
+    fn grab_key(&self, window: XWindowID, mask: ModMask, keysym: KeySym) {
+        self.request(Request::GrabKey(window, mask, keysym));
+    }
+
+    fn grab_button(&self,
+        window: XWindowID,
+        _mask: ButtonMask,
+        button: ButtonIndex,
+        modmask: ModMask,
+        _confine: bool
+    ) {
+        self.request(Request::GrabButton(window, button, modmask));
+    }
+
+    fn grab_pointer(&self, window: XWindowID, _mask: EventMask) {
+        self.request(Request::GrabPointer(window));
+    }
+
+    fn ungrab_pointer(&self) {
+        self.request(Request::UngrabPointer);
+    }
+
+    fn grab_keyboard(&self, window: XWindowID) {
+        self.request(Request::GrabKeyboard(window));
+    }
+
+    fn ungrab_keyboard(&self) {
+        self.request(Request::UngrabKeyboard);
+    }
+
+    fn flush(&self) {}
+}
+
+impl Icccm for ReplayConn {
+    fn get_client_properties(&self, window: XWindowID) -> XWinProperties {
+        self.reply(Query::ClientProperties(window)).unwrap_or_else(|| XWinProperties {
+            wm_name: String::new(),
+            wm_icon_name: String::new(),
+            wm_size_hints: None,
+            wm_hints: None,
+            wm_class: (String::new(), String::new()),
+            wm_protocols: None,
+            wm_state: WindowState::default(),
+        })
+    }
+
+    fn get_wm_name(&self, window: XWindowID) -> String {
+        self.reply(Query::WmName(window)).unwrap_or_default()
+    }
+
+    fn get_wm_icon_name(&self, window: XWindowID) -> String {
+        self.reply(Query::WmIconName(window)).unwrap_or_default()
+    }
+
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints> {
+        self.reply::<Option<_>>(Query::WmSizeHints(window)).flatten()
+    }
+
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints> {
+        self.reply::<Option<_>>(Query::WmHints(window)).flatten()
+    }
+
+    fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)> {
+        self.reply::<Option<_>>(Query::WmClass(window)).flatten()
+    }
+
+    fn get_wm_protocols(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.reply::<Option<_>>(Query::WmProtocols(window)).flatten()
+    }
+
+    fn get_wm_state(&self, window: XWindowID) -> WindowState {
+        self.reply(Query::WmState(window)).unwrap_or_default()
+    }
+
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState) {
+        self.request(Request::SetIcccmWmState(window, state));
+    }
+
+    fn send_take_focus(&self, window: XWindowID) {
+        self.request(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([self.atoms.WM_TAKE_FOCUS, self.time.get(), 0, 0, 0]),
+        }));
+    }
+
+    fn get_wm_transient_for(&self, window: XWindowID) -> Option<XWindowID> {
+        self.reply::<Option<_>>(Query::WmTransientFor(window)).flatten()
+    }
+
+    fn get_urgency(&self, window: XWindowID) -> bool {
+        self.reply(Query::Urgency(window)).unwrap_or(false)
+    }
+}
+
+impl Ewmh for ReplayConn {
+    fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        self.reply::<Option<_>>(Query::WindowType(window)).flatten()
+    }
+
+    fn get_window_states(&self, window: XWindowID) -> NetWindowStates {
+        self.reply(Query::WindowStates(window)).unwrap_or_default()
+    }
+
+    fn set_supported(&self, _screen_idx: i32, atoms: &[Atom]) {
+        self.request(Request::SetSupported(atoms.to_vec()));
+    }
+
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates) {
+        self.request(Request::SetWmState(window, states.to_atoms(&self.atoms)));
+    }
+
+    fn set_client_list(&self, _screen_idx: i32, clients: &[XWindowID]) {
+        self.request(Request::SetClientList(clients.to_vec()));
+    }
+
+    fn set_client_list_stacking(&self, _screen_idx: i32, clients: &[XWindowID]) {
+        self.request(Request::SetClientListStacking(clients.to_vec()));
+    }
+
+    fn set_number_of_desktops(&self, _screen_idx: i32, number: u32) {
+        self.request(Request::SetNumberOfDesktops(number));
+    }
+
+    fn set_current_desktop(&self, _screen_idx: i32, idx: u32) {
+        self.request(Request::SetCurrentDesktop(idx));
+    }
+
+    fn set_desktop_names(&self, _screen_idx: i32, names: &[&str]) {
+        self.request(Request::SetDesktopNames(names.iter().map(|name| name.to_string()).collect()));
+    }
+
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32) {
+        self.request(Request::SetWmDesktop(window, idx));
+    }
+
+    fn set_active_window(&self, _screen_idx: i32, window: XWindowID) {
+        self.request(Request::SetActiveWindow(window));
+    }
+
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID) {
+        self.request(Request::SetSupportingWmCheck(window, check));
+    }
+
+    fn set_wm_name(&self, window: XWindowID, name: &str) {
+        self.request(Request::SetWmName(window, name.into()));
+    }
+
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut> {
+        self.reply::<Option<_>>(Query::WmStrut(window)).flatten()
+    }
+
+    fn set_workarea(&self, _screen_idx: i32, areas: &[Geometry]) {
+        self.request(Request::SetWorkarea(areas.to_vec()));
+    }
+
+    fn set_showing_desktop(&self, _screen_idx: i32, showing: bool) {
+        self.request(Request::SetShowingDesktop(showing));
+    }
+
+    fn send_ping(&self, window: XWindowID, time: Timestamp) {
+        self.request(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([self.atoms.WM_PING, time, window, 0, 0]),
+        }));
+    }
+
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter> {
+        self.reply::<Option<_>>(Query::WmSyncRequestCounter(window)).flatten()
+    }
+
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp> {
+        self.reply::<Option<_>>(Query::WmUserTime(window)).flatten()
+    }
+
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon> {
+        self.reply(Query::WmIcon(window)).unwrap_or_default()
+    }
+
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp) {
+        self.request(Request::SendEvent(ClientMessageEvent {
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: ClientMessageData::U32([
+                self.atoms.WM_SYNC_REQUEST, time, value as u32, (value >> 32) as u32, 0
+            ]),
+        }));
+    }
+}
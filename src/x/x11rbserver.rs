@@ -0,0 +1,1280 @@
+//! An alternative backend for communicating with the X server,
+//! built on top of the pure-Rust x11rb crate.
+//!
+//! Enabled with the `x11rb-backend` feature. Unlike the XCB backend,
+//! it does not link against libxcb or xcb-util, so it can be used for
+//! fully static builds.
+
+use std::cell::{Cell, RefCell};
+
+use x11rb::connection::Connection;
+use x11rb::rust_connection::RustConnection;
+use x11rb::cookie::Cookie;
+use x11rb::errors::{ConnectionError, ReplyError, ReplyOrIdError};
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    self,
+    AtomEnum,
+    ChangeWindowAttributesAux,
+    ConfigureWindowAux,
+    CreateWindowAux,
+    ConnectionExt as _,
+    GetPropertyReply,
+    GrabMode,
+    InputFocus,
+    PropMode,
+};
+use x11rb::protocol::sync::{self, ConnectionExt as _};
+use x11rb::wrapper::ConnectionExt as _;
+
+use crate::window::Client;
+use crate::utils;
+use crate::types::{
+    Geometry,
+    Point,
+    NetWindowStates,
+    XWinProperties,
+    WindowState,
+    SizeHints,
+    WmHints,
+    Strut,
+    Icon,
+};
+
+pub use super::core::*;
+use super::{Icccm, Ewmh, consts};
+
+impl From<ConnectionError> for XError {
+    fn from(_: ConnectionError) -> XError {
+        XError::ConnectionClosed
+    }
+}
+
+impl From<ReplyError> for XError {
+    fn from(e: ReplyError) -> XError {
+        match e {
+            ReplyError::ConnectionError(_) => XError::ConnectionClosed,
+            ReplyError::X11Error(_) => XError::RequestError,
+        }
+    }
+}
+
+impl From<ReplyOrIdError> for XError {
+    fn from(e: ReplyOrIdError) -> XError {
+        match e {
+            ReplyOrIdError::ConnectionError(_) => XError::ConnectionClosed,
+            _ => XError::RequestError,
+        }
+    }
+}
+
+/// Interns all the atoms used by the WM.
+fn intern_atoms(conn: &RustConnection) -> Result<InternedAtoms> {
+    // send all the requests first, then wait for the replies
+    let cookies = InternedAtoms::NAMES.iter()
+        .map(|name| Ok((*name, conn.intern_atom(false, name.as_bytes())?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let atoms = cookies.into_iter()
+        .map(|(name, cookie)| cookie.reply()
+            .map(|reply| reply.atom)
+            .map_err(|_| XError::InternAtom(name)))
+        .collect::<Result<Vec<Atom>>>()?;
+
+    Ok(InternedAtoms::from_interned(&atoms))
+}
+
+/// The keyboard mapping of the X server, used to translate
+/// between keycodes and keysyms.
+struct Keymap {
+    min_keycode: KeyCode,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<KeySym>,
+}
+
+impl Keymap {
+    fn new(conn: &RustConnection) -> Result<Self> {
+        let setup = conn.setup();
+        let min_keycode = setup.min_keycode;
+        let count = setup.max_keycode - min_keycode + 1;
+
+        let reply = conn.get_keyboard_mapping(min_keycode, count)?.reply()?;
+
+        Ok(Self {
+            min_keycode,
+            keysyms_per_keycode: reply.keysyms_per_keycode as usize,
+            keysyms: reply.keysyms,
+        })
+    }
+
+    /// Returns the first keysym of the given keycode.
+    fn keysym(&self, code: KeyCode) -> KeySym {
+        if code < self.min_keycode {
+            return 0
+        }
+        let idx = (code - self.min_keycode) as usize * self.keysyms_per_keycode;
+
+        self.keysyms.get(idx).copied().unwrap_or(0)
+    }
+
+    /// Returns the first keycode that produces the given keysym.
+    fn keycode(&self, keysym: KeySym) -> Option<KeyCode> {
+        if self.keysyms_per_keycode == 0 {
+            return None
+        }
+        self.keysyms.iter()
+            .position(|sym| *sym == keysym)
+            .map(|idx| self.min_keycode + (idx / self.keysyms_per_keycode) as KeyCode)
+    }
+}
+
+fn button_event(event: xproto::ButtonPressEvent) -> PointerEvent {
+    PointerEvent {
+        window: event.event,
+        root: event.root,
+        child: event.child,
+        root_pt: Point {x: event.root_x as i32, y: event.root_y as i32},
+        event_pt: Point {x: event.event_x as i32, y: event.event_y as i32},
+        button: event.detail as ButtonIndex,
+        state: event.state as u32,
+        time: event.time,
+    }
+}
+
+fn crossing_event(event: xproto::EnterNotifyEvent) -> CrossingEvent {
+    CrossingEvent {
+        window: event.event,
+        root: event.root,
+        child: event.child,
+        root_pt: Point {x: event.root_x as i32, y: event.root_y as i32},
+        event_pt: Point {x: event.event_x as i32, y: event.event_y as i32},
+        mode: u8::from(event.mode) as u32,
+        detail: u8::from(event.detail) as u32,
+        state: event.state as u32,
+        time: event.time,
+    }
+}
+
+fn focus_event(event: xproto::FocusInEvent) -> FocusEvent {
+    FocusEvent {
+        window: event.event,
+        mode: u8::from(event.mode) as u32,
+        detail: u8::from(event.detail) as u32,
+    }
+}
+
+/// Lays a generic event header out as it was sent, x11rb having parsed it already.
+fn sync_value(value: sync::Int64) -> i64 {
+    ((value.hi as i64) << 32) | value.lo as i64
+}
+
+fn sync_int64(value: i64) -> sync::Int64 {
+    sync::Int64 {hi: (value >> 32) as i32, lo: value as u32}
+}
+
+fn generic_event(event: &xproto::GeGenericEvent) -> ExtensionEvent {
+    let mut data = [0; 32];
+    data[0] = event.response_type;
+    data[1] = event.extension;
+    data[2..4].copy_from_slice(&event.sequence.to_ne_bytes());
+    data[4..8].copy_from_slice(&event.length.to_ne_bytes());
+    data[8..10].copy_from_slice(&event.event_type.to_ne_bytes());
+
+    ExtensionEvent::from_raw(data)
+}
+
+fn circulate_event(event: xproto::CirculateNotifyEvent) -> CirculateEvent {
+    CirculateEvent {
+        id: event.window,
+        event: event.event,
+        place: u8::from(event.place) as u32,
+    }
+}
+
+/// Holds a handle to an x11rb connection, as well as other attributes
+/// about the window manager.
+pub struct X11RBConnection {
+    pub(crate) conn: RustConnection,
+    pub atoms: InternedAtoms,
+    pub root: XWindowID,
+    pub cursor: xproto::Cursor,
+    pub current_scr: i32,
+    keymap: RefCell<Keymap>,
+    /// The time of the event being handled.
+    time: Cell<Timestamp>,
+}
+
+impl X11RBConnection {
+    /// Creates a new X Connection.
+    pub fn new(xconn: RustConnection, idx: i32) -> Result<Self> {
+        let atoms = intern_atoms(&xconn)?;
+        let root = xconn.setup()
+            .roots
+            .get(idx as usize)
+            .ok_or(XError::NoSuchScreen(idx))?
+            .root;
+        let keymap = Keymap::new(&xconn)?;
+
+        // needed for _NET_WM_SYNC_REQUEST, which works without it
+        let sync = xconn.sync_initialize(sync::X11_XML_VERSION.0 as u8, sync::X11_XML_VERSION.1 as u8)
+            .map(|cookie| cookie.reply());
+        if !matches!(sync, Ok(Ok(_))) {
+            warn!("XSync extension is not available");
+        }
+
+        Ok(Self {
+            conn: xconn,
+            atoms,
+            root,
+            cursor: 0,
+            current_scr: idx,
+            keymap: RefCell::new(keymap),
+            time: Cell::new(consts::CURRENT_TIME),
+        })
+    }
+
+    /// Get a reference to the underlying x11rb connection.
+    pub fn get_raw(&self) -> &RustConnection {
+        &self.conn
+    }
+
+    /// Get the next raw event from the X server.
+    pub fn next_raw_event(&self) -> Result<Event> {
+        self.conn.flush()?;
+
+        Ok(self.conn.wait_for_event()?)
+    }
+
+    pub fn process_raw_event(&self, event: Event) -> Result<XEvent> {
+        use XEvent::*;
+
+        Ok(match event {
+            Event::ConfigureNotify(event) => {
+                ConfigureNotify(ConfigureEvent {
+                    id: event.window,
+                    event: event.event,
+                    geom: Geometry {
+                        x: event.x as i32,
+                        y: event.y as i32,
+                        width: event.width as i32,
+                        height: event.height as i32,
+                    },
+                    border_width: event.border_width as u32,
+                    above_sibling: event.above_sibling,
+                    override_redirect: event.override_redirect,
+                    is_root: event.window == self.root,
+                })
+            }
+            Event::ConfigureRequest(event) => {
+                let has = |flag: u32| event.value_mask & flag as u16 != 0;
+
+                ConfigureRequest(ConfigureRequestData {
+                    id: event.window,
+                    x: if has(consts::CONFIG_WINDOW_X) {Some(event.x as i32)} else {None},
+                    y: if has(consts::CONFIG_WINDOW_Y) {Some(event.y as i32)} else {None},
+                    width: if has(consts::CONFIG_WINDOW_WIDTH) {Some(event.width as i32)} else {None},
+                    height: if has(consts::CONFIG_WINDOW_HEIGHT) {Some(event.height as i32)} else {None},
+                    border_width: if has(consts::CONFIG_WINDOW_BORDER_WIDTH) {
+                        Some(event.border_width as u32)
+                    } else {None},
+                    sibling: if has(consts::CONFIG_WINDOW_SIBLING) {Some(event.sibling)} else {None},
+                    stack_mode: if has(consts::CONFIG_WINDOW_STACK_MODE) {
+                        Some(u32::from(event.stack_mode))
+                    } else {None},
+                    is_root: event.window == self.root,
+                })
+            }
+            Event::MapRequest(event) => {
+                let override_redirect =
+                if let Some(attrs) = self.get_window_attributes(event.window) {
+                    attrs.override_redirect
+                } else {false};
+
+                MapRequest(MapRequestData {
+                    id: event.window,
+                    parent: event.parent,
+                    override_redirect,
+                })
+            }
+            Event::MapNotify(event) => MapNotify(MapEvent {
+                id: event.window,
+                event: event.event,
+                override_redirect: event.override_redirect,
+            }),
+            Event::UnmapNotify(event) => UnmapNotify(UnmapEvent {
+                id: event.window,
+                event: event.event,
+                synthetic: event.response_type & 0x80 != 0,
+            }),
+            Event::DestroyNotify(event) => DestroyNotify(DestroyEvent {
+                id: event.window,
+                event: event.event,
+            }),
+            Event::CreateNotify(event) => {
+                CreateNotify(CreateEvent {
+                    id: event.window,
+                    parent: event.parent,
+                    geom: Geometry {
+                        x: event.x as i32,
+                        y: event.y as i32,
+                        width: event.width as i32,
+                        height: event.height as i32,
+                    },
+                    border_width: event.border_width as u32,
+                    override_redirect: event.override_redirect,
+                })
+            }
+            Event::CirculateRequest(event) => CirculateRequest(circulate_event(event)),
+            Event::CirculateNotify(event) => CirculateNotify(circulate_event(event)),
+            Event::GravityNotify(event) => {
+                GravityNotify(event.window, Point {
+                    x: event.x as i32,
+                    y: event.y as i32,
+                })
+            }
+            Event::EnterNotify(event) => EnterNotify(crossing_event(event)),
+            Event::LeaveNotify(event) => LeaveNotify(crossing_event(event)),
+            Event::FocusIn(event) => FocusIn(focus_event(event)),
+            Event::FocusOut(event) => FocusOut(focus_event(event)),
+            Event::MotionNotify(event) => {
+                MotionNotify(PointerEvent {
+                    window: event.event,
+                    root: event.root,
+                    child: event.child,
+                    root_pt: Point {x: event.root_x as i32, y: event.root_y as i32},
+                    event_pt: Point {x: event.event_x as i32, y: event.event_y as i32},
+                    button: consts::BUTTON_INDEX_ANY,
+                    state: event.state as u32,
+                    time: event.time,
+                })
+            }
+            Event::ReparentNotify(event) => ReparentNotify(ReparentEvent {
+                id: event.window,
+                event: event.event,
+                parent: event.parent,
+                pt: Point {x: event.x as i32, y: event.y as i32},
+                override_redirect: event.override_redirect,
+            }),
+            Event::PropertyNotify(event) => {
+                PropertyNotify(PropertyEvent {
+                    id: event.window,
+                    atom: event.atom,
+                    time: event.time,
+                })
+            }
+            Event::KeyPress(event) => KeyPress(self.keypress_event(event)),
+            Event::KeyRelease(event) => KeyRelease(self.keypress_event(event)),
+            Event::ButtonPress(event) => ButtonPress(button_event(event)),
+            Event::ButtonRelease(event) => ButtonRelease(button_event(event)),
+            Event::Expose(event) => {
+                Expose(ExposeEvent {
+                    id: event.window,
+                    geom: Geometry {
+                        x: event.x as i32,
+                        y: event.y as i32,
+                        width: event.width as i32,
+                        height: event.height as i32,
+                    },
+                    count: event.count as u32,
+                })
+            }
+            Event::MappingNotify(event) => {
+                let request = u8::from(event.request) as u32;
+
+                if request == consts::MAPPING_KEYBOARD {
+                    *self.keymap.borrow_mut() = Keymap::new(&self.conn)?;
+                }
+
+                MappingNotify(MappingEvent {
+                    request,
+                    first_keycode: event.first_keycode,
+                    count: event.count as u32,
+                })
+            }
+            Event::ClientMessage(event) => {
+                let window = event.window;
+                let type_ = event.type_;
+
+                let data = match event.format {
+                    8 => ClientMessageData::U8(event.data.as_data8()),
+                    16 => ClientMessageData::U16(event.data.as_data16()),
+                    32 => ClientMessageData::U32(event.data.as_data32()),
+                    n => return Err(XError::ProtocolError(
+                        format!("Client message for window {} has format {}", window, n)
+                    )),
+                };
+
+                ClientMessage(ClientMessageEvent {window, type_, data})
+            }
+            Event::Error(error) => Error(XErrorEvent {
+                code: error.error_code,
+                sequence: error.sequence,
+                resource: error.bad_value,
+                major_opcode: error.major_opcode,
+                minor_opcode: error.minor_opcode,
+            }),
+            Event::GeGeneric(event) => Extension(generic_event(&event)),
+            Event::SyncAlarmNotify(event) => SyncAlarmNotify(SyncAlarmEvent {
+                alarm: event.alarm,
+                counter_value: sync_value(event.counter_value),
+                alarm_value: sync_value(event.alarm_value),
+                time: event.timestamp,
+            }),
+            Event::SyncCounterNotify(event) => Extension(ExtensionEvent::from_raw((&event).into())),
+            Event::Unknown(raw) if raw.first()
+                .is_some_and(|&kind| kind & !0x80 >= consts::FIRST_EXTENSION_EVENT) => {
+                let mut data = [0; 32];
+                let len = raw.len().min(32);
+                data[..len].copy_from_slice(&raw[..len]);
+
+                Extension(ExtensionEvent::from_raw(data))
+            }
+            unhandled => Unknown(unhandled.response_type() & !0x80),
+        })
+    }
+
+    /// Returns the root window of the given screen.
+    fn screen_root(&self, screen_idx: i32) -> Option<XWindowID> {
+        let root = self.conn.setup().roots.get(screen_idx as usize).map(|screen| screen.root);
+        if root.is_none() {
+            error!("Screen {} does not exist", screen_idx);
+        }
+        root
+    }
+
+    /// Replaces a 32-bit property on the root window of the given screen.
+    fn set_root_property<A: Into<Atom>>(&self, screen_idx: i32, property: Atom, type_: A, data: &[u32]) {
+        let root = match self.screen_root(screen_idx) {
+            Some(root) => root,
+            None => return,
+        };
+
+        if let Err(e) = self.conn.change_property32(
+            PropMode::REPLACE, root, property, type_, data
+        ) {
+            error!("Could not set property {} on root window: {}", property, e)
+        }
+    }
+
+    fn keypress_event(&self, event: xproto::KeyPressEvent) -> KeypressEvent {
+        KeypressEvent {
+            mask: event.state as u32,
+            keysym: self.keymap.borrow().keysym(event.detail),
+            keycode: event.detail,
+            window: event.event,
+            root: event.root,
+            child: event.child,
+            time: event.time,
+        }
+    }
+
+    /// Sends a GetProperty request for the whole of a property.
+    fn property_cookie(&self, window: XWindowID, property: Atom, type_: Atom)
+    -> Option<Cookie<'_, RustConnection, GetPropertyReply>> {
+        self.conn.get_property(false, window, property, type_, 0, u32::MAX).ok()
+    }
+
+    /// Gets the value of a property.
+    ///
+    /// Returns None if the request failed or the property is not set.
+    fn get_property(&self, window: XWindowID, property: Atom, type_: Atom)
+    -> Option<GetPropertyReply> {
+        property_reply(self.property_cookie(window, property, type_))
+    }
+
+    fn configure_aux(attrs: &[(u16, u32)]) -> ConfigureWindowAux {
+        let mut aux = ConfigureWindowAux::new();
+
+        for &(attr, value) in attrs {
+            match attr as u32 {
+                consts::CONFIG_WINDOW_X => aux.x = Some(value as i32),
+                consts::CONFIG_WINDOW_Y => aux.y = Some(value as i32),
+                consts::CONFIG_WINDOW_WIDTH => aux.width = Some(value),
+                consts::CONFIG_WINDOW_HEIGHT => aux.height = Some(value),
+                consts::CONFIG_WINDOW_BORDER_WIDTH => aux.border_width = Some(value),
+                consts::CONFIG_WINDOW_SIBLING => aux.sibling = Some(value),
+                consts::CONFIG_WINDOW_STACK_MODE => aux.stack_mode = Some(value.into()),
+                unknown => warn!("Unknown configure mask {}", unknown),
+            }
+        }
+
+        aux
+    }
+
+    fn attributes_aux(attrs: &[(u32, u32)]) -> ChangeWindowAttributesAux {
+        let mut aux = ChangeWindowAttributesAux::new();
+
+        for &(attr, value) in attrs {
+            match attr {
+                consts::CW_BACK_PIXEL => aux.background_pixel = Some(value),
+                consts::CW_BORDER_PIXEL => aux.border_pixel = Some(value),
+                consts::CW_OVERRIDE_REDIRECT => aux.override_redirect = Some(value),
+                consts::CW_EVENT_MASK => aux.event_mask = Some(value),
+                consts::CW_CURSOR => aux.cursor = Some(value),
+                unknown => warn!("Unsupported window attribute mask {}", unknown),
+            }
+        }
+
+        aux
+    }
+}
+
+impl XConn for X11RBConnection {
+    /// Returns atoms supported by the connection.
+    fn get_atoms(&self) -> InternedAtoms {
+        self.atoms
+    }
+
+    /// Get the root ID of the current screen.
+    fn get_root_id(&self) -> XWindowID {
+        self.root
+    }
+
+    /// Set the screen of the current root.
+    fn set_root_scr(&mut self, scr: i32) {
+        if let Some(screen) = self.conn.setup().roots.get(scr as usize) {
+            self.current_scr = scr;
+            self.root = screen.root;
+        } else {
+            error!("Screen {} does not exist", scr);
+        }
+    }
+
+    fn next_event(&self) -> Result<XEvent> {
+        self.process_raw_event(self.next_raw_event()?)
+    }
+
+    /// Get a list of all active windows.
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
+        Ok(self.conn.query_tree(window)?.reply()?.children)
+    }
+
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64> {
+        let value = self.conn.sync_query_counter(counter)?.reply()?.counter_value;
+
+        Ok(sync_value(value))
+    }
+
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm> {
+        let alarm = self.conn.generate_id()?;
+
+        // with a delta of 0 the alarm waits to be set again once it has triggered
+        self.conn.sync_create_alarm(alarm, &sync::CreateAlarmAux::new()
+            .counter(counter)
+            .value_type(sync::VALUETYPE::ABSOLUTE)
+            .value(sync_int64(value))
+            .test_type(sync::TESTTYPE::POSITIVE_COMPARISON)
+            .delta(sync_int64(0))
+            .events(1)
+        )?;
+
+        Ok(alarm)
+    }
+
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64) {
+        if let Err(e) = self.conn.sync_change_alarm(alarm, &sync::ChangeAlarmAux::new().value(sync_int64(value))) {
+            error!("Could not set sync alarm {}: {}", alarm, e)
+        }
+    }
+
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm) {
+        if let Err(e) = self.conn.sync_destroy_alarm(alarm) {
+            error!("Could not destroy sync alarm {}: {}", alarm, e)
+        }
+    }
+
+    fn query_pointer(&self, window_id: XWindowID) -> Result<PointerQueryReply> {
+        debug!("Querying pointer location for window {}", window_id);
+
+        let reply = self.conn.query_pointer(window_id)?.reply()?;
+
+        Ok(PointerQueryReply {
+            root: reply.root,
+            child: reply.child,
+            location: Point {
+                x: reply.root_x as i32,
+                y: reply.root_y as i32,
+            },
+        })
+    }
+
+    /// Create the cursor.
+    fn create_cursor(&mut self, glyph: u16) -> Result<()> {
+        debug!("Creating cursor");
+        let font = self.conn.generate_id()?;
+        let cursor_id = self.conn.generate_id()?;
+
+        self.conn.open_font(font, b"cursor")?.check()?;
+        self.conn.create_glyph_cursor(
+            cursor_id, font, font,
+            glyph, glyph + 1,
+            0, 0, 0,
+            0xffff, 0xffff, 0xffff,
+        )?.check()?;
+        self.conn.close_font(font)?;
+
+        self.cursor = cursor_id;
+        Ok(())
+    }
+
+    fn create_hidden_window(&self) -> Result<XWindowID> {
+        let window = self.conn.generate_id()?;
+
+        self.conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window, self.root,
+            -1, -1, 1, 1, 0,
+            xproto::WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().override_redirect(1),
+        )?.check()?;
+
+        Ok(window)
+    }
+
+    /// Set cursor.
+    fn set_cursor(&mut self, window: XWindowID) {
+        debug!("Setting cursor for {}", window);
+        self.change_window_attributes(window, &utils::cursor_attrs(self.cursor))
+    }
+
+    /// Get window attributes
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes> {
+        debug!("Getting attributes for window {}", window);
+
+        let reply = self.conn.get_window_attributes(window).ok()?.reply().ok()?;
+
+        Some(WindowAttributes {
+            override_redirect: reply.override_redirect,
+            map_state: match u8::from(reply.map_state) as u32 {
+                consts::MAP_STATE_VIEWABLE => MapState::Viewable,
+                consts::MAP_STATE_UNVIEWABLE => MapState::Unviewable,
+                _ => MapState::Unmapped,
+            },
+        })
+    }
+
+    fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
+        if let Err(e) = self.conn.change_window_attributes(window, &Self::attributes_aux(attrs)) {
+            error!("Could not change attributes for window {}: {}", window, e)
+        }
+    }
+
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
+        Ok(self.conn.change_window_attributes(window, &Self::attributes_aux(attrs))?.check()?)
+    }
+
+    fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
+        debug!("Configuring window {}", window);
+        if let Err(e) = self.conn.configure_window(window, &Self::configure_aux(attrs)) {
+            error!("Could not configure window {}: {}", window, e)
+        }
+    }
+
+    fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
+        debug!("Reparenting window {} under window {}", window, parent);
+        if let Err(e) = self.conn.reparent_window(window, parent, 0, 0) {
+            error!("Could not reparent window {}: {}", window, e)
+        }
+    }
+
+    fn map_window(&self, window_id: XWindowID) {
+        debug!("Mapping window {}", window_id);
+
+        if let Err(e) = self.conn.map_window(window_id) {
+            error!("Could not map window {}: {}", window_id, e)
+        }
+    }
+
+    fn unmap_window(&self, window_id: XWindowID) {
+        debug!("Unmapping window {}", window_id);
+
+        if let Err(e) = self.conn.unmap_window(window_id) {
+            error!("Could not unmap window {}: {}", window_id, e)
+        }
+    }
+
+    fn destroy_window(&self, window: &Client) {
+        debug!("Destroying window {}", window.id());
+        let res = if window.supports(self.atoms.WM_DELETE_WINDOW) {
+            debug!("Destroying window via ICCCM WM_DELETE_WINDOW");
+
+            let msg_event = xproto::ClientMessageEvent {
+                response_type: xproto::CLIENT_MESSAGE_EVENT,
+                format: 32,
+                sequence: 0,
+                window: window.id(),
+                type_: self.atoms.WM_PROTOCOLS,
+                data: [
+                    self.atoms.WM_DELETE_WINDOW,
+                    consts::CURRENT_TIME,
+                    0, 0, 0,
+                ].into(),
+            };
+
+            self.conn.send_event(
+                false,
+                window.id(),
+                consts::EVENT_MASK_NO_EVENT,
+                msg_event,
+            ).map(|_| ())
+        } else {
+            debug!("Destroying window via destroy_window");
+
+            self.conn.destroy_window(window.id()).map(|_| ())
+        };
+
+        if let Err(e) = res {
+            error!("Could not destroy window {}: {}", window.id(), e)
+        }
+    }
+
+    fn kill_client(&self, window_id: XWindowID) {
+        debug!("Killing client of window {}", window_id);
+
+        if let Err(e) = self.conn.kill_client(window_id) {
+            error!("Could not kill client of window {}: {}", window_id, e)
+        }
+    }
+
+    fn touch_property(&self, window: XWindowID, property: Atom, type_: Atom) {
+        if let Err(e) = self.conn.change_property8(PropMode::APPEND, window, property, type_, &[]) {
+            error!("Could not change property {} of window {}: {}", property, window, e)
+        }
+    }
+
+    fn set_timestamp(&self, time: Timestamp) {
+        self.time.set(time);
+    }
+
+    fn set_input_focus(&self, window_id: XWindowID) {
+        debug!("Setting focus for window {}", window_id);
+
+        if let Err(e) = self.conn.set_input_focus(
+            InputFocus::POINTER_ROOT, window_id, self.time.get()
+        ) {
+            error!("Could not focus window {}: {}", window_id, e)
+        }
+    }
+
+    fn set_geometry(&self, window_id: XWindowID, geom: Geometry) {
+        self.configure_window(window_id, &utils::configure_geometry(geom))
+    }
+
+    fn get_geometry(&self, window_id: XWindowID) -> Result<Geometry> {
+        debug!("Getting geometry");
+        let reply = self.conn.get_geometry(window_id)?.reply()?;
+
+        Ok(Geometry::from((
+            reply.x as i32,
+            reply.y as i32,
+            reply.width as i32,
+            reply.height as i32,
+        )))
+    }
+
+    fn grab_key(&self, window_id: XWindowID, mask: ModMask, keysym: KeySym) {
+        debug!("Grabbing key {} for window {}", keysym, window_id);
+
+        let code = self.keymap.borrow().keycode(keysym);
+        if let Some(code) = code {
+            let res = self.conn.grab_key(
+                false,
+                window_id,
+                mask as u16,
+                code,
+                GrabMode::ASYNC,
+                GrabMode::ASYNC,
+            ).map_err(ReplyError::from).and_then(|cookie| cookie.check());
+
+            if res.is_err() {
+                warn!("Unable to grab key {}", keysym)
+            }
+        } else {
+            warn!("Returned null keycode for keysym {}, not grabbing", keysym)
+        }
+    }
+
+    fn grab_button(&self,
+        window_id: XWindowID,
+        mask: ButtonMask,
+        button: ButtonIndex,
+        modmask: ModMask,
+        confine: bool)
+    {
+        debug!("Grab button {} for window: {}", button, window_id);
+
+        let res = self.conn.grab_button(
+            false,
+            window_id,
+            mask as u16,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+            if confine { window_id } else { consts::NONE },
+            consts::NONE,
+            (button as u8).into(),
+            modmask as u16,
+        ).map_err(ReplyError::from).and_then(|cookie| cookie.check());
+
+        if res.is_err() {
+            warn!("Unable to grab button {}", button)
+        }
+    }
+
+    fn grab_pointer(&self, window_id: XWindowID, mask: EventMask) {
+        debug!("Grabbing pointer for window: {}", window_id);
+
+        if let Err(e) = self.conn.grab_pointer(
+            false,
+            window_id,
+            mask as u16,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+            consts::NONE,
+            consts::NONE,
+            consts::CURRENT_TIME,
+        ) {
+            error!("Could not grab pointer: {}", e)
+        }
+    }
+
+    fn ungrab_pointer(&self) {
+        debug!("Ungrabbing pointer");
+
+        if let Err(e) = self.conn.ungrab_pointer(consts::CURRENT_TIME) {
+            error!("Could not ungrab pointer: {}", e)
+        }
+    }
+
+    fn grab_keyboard(&self, window_id: XWindowID) {
+        debug!("Grabbing keyboard for window: {}", window_id);
+
+        if let Err(e) = self.conn.grab_keyboard(
+            false,
+            window_id,
+            consts::CURRENT_TIME,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+        ) {
+            error!("Could not grab keyboard: {}", e)
+        }
+    }
+
+    fn ungrab_keyboard(&self) {
+        debug!("Ungrabbing keyboard");
+
+        if let Err(e) = self.conn.ungrab_keyboard(consts::CURRENT_TIME) {
+            error!("Could not ungrab keyboard: {}", e)
+        }
+    }
+
+    fn flush(&self) {
+        if let Err(e) = self.conn.flush() {
+            error!("Could not flush connection: {}", e)
+        }
+    }
+}
+
+impl Icccm for X11RBConnection {
+    fn get_client_properties(&self, window: XWindowID) -> XWinProperties {
+        debug!("Getting client properties for window {}", window);
+        // fire off all requests at once, then get replies all together
+        let any = AtomEnum::ANY.into();
+        let wm_name_cookie = self.property_cookie(window, AtomEnum::WM_NAME.into(), any);
+        let wm_icon_cookie = self.property_cookie(window, AtomEnum::WM_ICON_NAME.into(), any);
+        let wm_size_hints_cookie = self.property_cookie(
+            window, AtomEnum::WM_NORMAL_HINTS.into(), AtomEnum::WM_SIZE_HINTS.into()
+        );
+        let wm_hints_cookie = self.property_cookie(
+            window, AtomEnum::WM_HINTS.into(), AtomEnum::WM_HINTS.into()
+        );
+        let wm_class_cookie = self.property_cookie(
+            window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into()
+        );
+        let wm_protocols_cookie = self.property_cookie(
+            window, self.atoms.WM_PROTOCOLS, AtomEnum::ATOM.into()
+        );
+        let wm_state_cookie = self.property_cookie(
+            window, self.atoms.ICCCM_WM_STATE, self.atoms.ICCCM_WM_STATE
+        );
+
+        XWinProperties {
+            wm_name: parse_string(property_reply(wm_name_cookie)),
+            wm_icon_name: parse_string(property_reply(wm_icon_cookie)),
+            wm_size_hints: parse_size_hints(property_reply(wm_size_hints_cookie)),
+            wm_hints: parse_wm_hints(property_reply(wm_hints_cookie)),
+            wm_class: parse_wm_class(property_reply(wm_class_cookie))
+                .unwrap_or_else(|| (String::new(), String::new())),
+            wm_protocols: parse_u32s(property_reply(wm_protocols_cookie)),
+            wm_state: parse_wm_state(property_reply(wm_state_cookie)),
+        }
+    }
+
+    fn get_wm_name(&self, window: XWindowID) -> String {
+        parse_string(self.get_property(
+            window, AtomEnum::WM_NAME.into(), AtomEnum::ANY.into()
+        ))
+    }
+
+    fn get_wm_icon_name(&self, window: XWindowID) -> String {
+        parse_string(self.get_property(
+            window, AtomEnum::WM_ICON_NAME.into(), AtomEnum::ANY.into()
+        ))
+    }
+
+    fn get_wm_size_hints(&self, window: XWindowID) -> Option<SizeHints> {
+        parse_size_hints(self.get_property(
+            window, AtomEnum::WM_NORMAL_HINTS.into(), AtomEnum::WM_SIZE_HINTS.into()
+        ))
+    }
+
+    fn get_wm_hints(&self, window: XWindowID) -> Option<WmHints> {
+        parse_wm_hints(self.get_property(
+            window, AtomEnum::WM_HINTS.into(), AtomEnum::WM_HINTS.into()
+        ))
+    }
+
+    fn get_wm_class(&self, window: XWindowID) -> Option<(String, String)> {
+        parse_wm_class(self.get_property(
+            window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into()
+        ))
+    }
+
+    fn get_wm_protocols(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        debug!("Getting protocols for window {}", window);
+        parse_u32s(self.get_property(
+            window, self.atoms.WM_PROTOCOLS, AtomEnum::ATOM.into()
+        ))
+    }
+
+    fn get_wm_state(&self, window: XWindowID) -> WindowState {
+        parse_wm_state(self.get_property(
+            window, self.atoms.ICCCM_WM_STATE, self.atoms.ICCCM_WM_STATE
+        ))
+    }
+
+    fn set_icccm_wm_state(&self, window: XWindowID, state: WindowState) {
+        debug!("Setting WM_STATE of window {} to {:?}", window, state);
+        if let Err(e) = self.conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            self.atoms.ICCCM_WM_STATE,
+            self.atoms.ICCCM_WM_STATE,
+            &[state.into(), consts::NONE],
+        ) {
+            error!("Could not set WM_STATE for window {}: {}", window, e)
+        }
+    }
+
+    fn send_take_focus(&self, window: XWindowID) {
+        debug!("Sending WM_TAKE_FOCUS to window {}", window);
+
+        let msg_event = xproto::ClientMessageEvent {
+            response_type: xproto::CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: [self.atoms.WM_TAKE_FOCUS, self.time.get(), 0, 0, 0].into(),
+        };
+
+        if let Err(e) = self.conn.send_event(false, window, consts::EVENT_MASK_NO_EVENT, msg_event) {
+            error!("Could not send WM_TAKE_FOCUS to window {}: {}", window, e)
+        }
+    }
+
+    fn get_wm_transient_for(&self, window: XWindowID) -> Option<XWindowID> {
+        debug!("Get wm_transient_for for window {}", window);
+        parse_u32s(self.get_property(
+            window, AtomEnum::WM_TRANSIENT_FOR.into(), AtomEnum::WINDOW.into()
+        ))?.first().copied()
+    }
+
+    fn get_urgency(&self, window: XWindowID) -> bool {
+        if let Some(hints) = self.get_wm_hints(window) {
+            return hints.urgent
+        }
+        false
+    }
+}
+
+impl Ewmh for X11RBConnection {
+    fn get_window_type(&self, window: XWindowID) -> Option<Vec<Atom>> {
+        debug!("Getting type for window {}", window);
+
+        parse_u32s(self.get_property(
+            window, self.atoms.WM_WINDOW_TYPE, AtomEnum::ATOM.into()
+        ))
+    }
+
+    fn get_window_states(&self, window: XWindowID) -> NetWindowStates {
+        debug!("Getting EWMH states for window {}", window);
+
+        parse_u32s(self.get_property(window, self.atoms.WM_STATE, AtomEnum::ATOM.into()))
+            .map(|states| NetWindowStates::from_atoms(&states, &self.atoms))
+            .unwrap_or_default()
+    }
+
+    fn set_supported(&self, screen_idx: i32, atoms: &[Atom]) {
+        self.set_root_property(screen_idx, self.atoms.SUPPORTED, AtomEnum::ATOM, atoms);
+    }
+
+    fn set_wm_state(&self, window: XWindowID, states: &NetWindowStates) {
+        if let Err(e) = self.conn.change_property32(
+            PropMode::REPLACE, window, self.atoms.WM_STATE, AtomEnum::ATOM, &states.to_atoms(&self.atoms)
+        ) {
+            error!("Could not set _NET_WM_STATE for window {}: {}", window, e)
+        }
+    }
+
+    fn set_client_list(&self, screen_idx: i32, clients: &[XWindowID]) {
+        self.set_root_property(screen_idx, self.atoms.CLIENT_LIST, AtomEnum::WINDOW, clients);
+    }
+
+    fn set_client_list_stacking(&self, screen_idx: i32, clients: &[XWindowID]) {
+        self.set_root_property(screen_idx, self.atoms.CLIENT_LIST_STACKING, AtomEnum::WINDOW, clients);
+    }
+
+    fn set_number_of_desktops(&self, screen_idx: i32, number: u32) {
+        self.set_root_property(screen_idx, self.atoms.NUMBER_OF_DESKTOPS, AtomEnum::CARDINAL, &[number]);
+    }
+
+    fn set_current_desktop(&self, screen_idx: i32, idx: u32) {
+        self.set_root_property(screen_idx, self.atoms.CURRENT_DESKTOP, AtomEnum::CARDINAL, &[idx]);
+    }
+
+    fn set_desktop_names(&self, screen_idx: i32, names: &[&str]) {
+        let root = match self.screen_root(screen_idx) {
+            Some(root) => root,
+            None => return,
+        };
+
+        // each name is null-terminated
+        let mut data = Vec::new();
+        for name in names {
+            data.extend_from_slice(name.as_bytes());
+            data.push(0);
+        }
+
+        if let Err(e) = self.conn.change_property8(
+            PropMode::REPLACE, root, self.atoms.DESKTOP_NAMES, self.atoms.UTF8_STRING, &data
+        ) {
+            error!("Could not set _NET_DESKTOP_NAMES: {}", e)
+        }
+    }
+
+    fn set_wm_desktop(&self, window: XWindowID, idx: u32) {
+        if let Err(e) = self.conn.change_property32(
+            PropMode::REPLACE, window, self.atoms.WM_DESKTOP, AtomEnum::CARDINAL, &[idx]
+        ) {
+            error!("Could not set _NET_WM_DESKTOP for window {}: {}", window, e)
+        }
+    }
+
+    fn set_active_window(&self, screen_idx: i32, window: XWindowID) {
+        self.set_root_property(screen_idx, self.atoms.ACTIVE_WINDOW, AtomEnum::WINDOW, &[window]);
+    }
+
+    fn set_supporting_wm_check(&self, window: XWindowID, check: XWindowID) {
+        if let Err(e) = self.conn.change_property32(
+            PropMode::REPLACE, window, self.atoms.SUPPORTING_WM_CHECK, AtomEnum::WINDOW, &[check]
+        ) {
+            error!("Could not set _NET_SUPPORTING_WM_CHECK for window {}: {}", window, e)
+        }
+    }
+
+    fn set_wm_name(&self, window: XWindowID, name: &str) {
+        if let Err(e) = self.conn.change_property8(
+            PropMode::REPLACE, window, self.atoms.WM_NAME, self.atoms.UTF8_STRING, name.as_bytes()
+        ) {
+            error!("Could not set _NET_WM_NAME for window {}: {}", window, e)
+        }
+    }
+
+    fn get_wm_strut(&self, window: XWindowID) -> Option<Strut> {
+        debug!("Getting struts for window {}", window);
+
+        let partial = parse_u32s(self.get_property(
+            window, self.atoms.WM_STRUT_PARTIAL, AtomEnum::CARDINAL.into()
+        ));
+        if let Some(strut) = partial.as_deref().and_then(Strut::from_partial) {
+            return Some(strut)
+        }
+
+        parse_u32s(self.get_property(window, self.atoms.WM_STRUT, AtomEnum::CARDINAL.into()))
+            .filter(|values| values.len() >= 4)
+            .map(|values| Strut::from_edges(values[0], values[1], values[2], values[3]))
+    }
+
+    fn set_workarea(&self, screen_idx: i32, areas: &[Geometry]) {
+        let data: Vec<u32> = areas.iter()
+            .flat_map(|area| [area.x as u32, area.y as u32, area.width as u32, area.height as u32])
+            .collect();
+
+        self.set_root_property(screen_idx, self.atoms.WORKAREA, AtomEnum::CARDINAL, &data);
+    }
+
+    fn set_showing_desktop(&self, screen_idx: i32, showing: bool) {
+        self.set_root_property(
+            screen_idx, self.atoms.SHOWING_DESKTOP, AtomEnum::CARDINAL, &[showing as u32]
+        );
+    }
+
+    fn send_ping(&self, window: XWindowID, time: Timestamp) {
+        debug!("Pinging window {}", window);
+
+        let msg_event = xproto::ClientMessageEvent {
+            response_type: xproto::CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: [self.atoms.WM_PING, time, window, 0, 0].into(),
+        };
+
+        if let Err(e) = self.conn.send_event(false, window, consts::EVENT_MASK_NO_EVENT, msg_event) {
+            error!("Could not ping window {}: {}", window, e)
+        }
+    }
+
+    fn get_wm_sync_request_counter(&self, window: XWindowID) -> Option<SyncCounter> {
+        debug!("Getting sync request counter for window {}", window);
+
+        parse_u32s(self.get_property(
+            window, self.atoms.WM_SYNC_REQUEST_COUNTER, AtomEnum::CARDINAL.into()
+        ))?.first().copied()
+    }
+
+    fn send_sync_request(&self, window: XWindowID, value: i64, time: Timestamp) {
+        debug!("Sending sync request {} to window {}", value, window);
+
+        let msg_event = xproto::ClientMessageEvent {
+            response_type: xproto::CLIENT_MESSAGE_EVENT,
+            format: 32,
+            sequence: 0,
+            window,
+            type_: self.atoms.WM_PROTOCOLS,
+            data: [
+                self.atoms.WM_SYNC_REQUEST,
+                time,
+                value as u32,
+                (value >> 32) as u32,
+                0,
+            ].into(),
+        };
+
+        if let Err(e) = self.conn.send_event(false, window, consts::EVENT_MASK_NO_EVENT, msg_event) {
+            error!("Could not send sync request to window {}: {}", window, e)
+        }
+    }
+
+    fn get_wm_user_time(&self, window: XWindowID) -> Option<Timestamp> {
+        debug!("Getting user time for window {}", window);
+
+        let window = parse_u32s(self.get_property(
+            window, self.atoms.WM_USER_TIME_WINDOW, AtomEnum::WINDOW.into()
+        )).and_then(|values| values.first().copied()).unwrap_or(window);
+
+        parse_u32s(self.get_property(window, self.atoms.WM_USER_TIME, AtomEnum::CARDINAL.into()))?
+            .first().copied()
+    }
+
+    fn get_wm_icon(&self, window: XWindowID) -> Vec<Icon> {
+        debug!("Getting icons for window {}", window);
+
+        parse_u32s(self.get_property(window, self.atoms.WM_ICON, AtomEnum::CARDINAL.into()))
+            .map(|values| Icon::decode_all(&values))
+            .unwrap_or_default()
+    }
+}
+
+/// Waits for the reply to a GetProperty request.
+///
+/// Returns None if the request failed or the property is not set.
+fn property_reply(cookie: Option<Cookie<'_, RustConnection, GetPropertyReply>>)
+-> Option<GetPropertyReply> {
+    cookie?.reply().ok().filter(|reply| reply.type_ != consts::NONE)
+}
+
+fn parse_string(reply: Option<GetPropertyReply>) -> String {
+    reply.map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+        .unwrap_or_default()
+}
+
+fn parse_u32s(reply: Option<GetPropertyReply>) -> Option<Vec<u32>> {
+    Some(reply?.value32()?.collect())
+}
+
+/// Parses WM_CLASS into its instance and class.
+fn parse_wm_class(reply: Option<GetPropertyReply>) -> Option<(String, String)> {
+    let reply = reply?;
+    let mut parts = reply.value.split(|b| *b == 0)
+        .map(|part| String::from_utf8_lossy(part).into_owned());
+
+    Some((parts.next().unwrap_or_default(), parts.next().unwrap_or_default()))
+}
+
+fn parse_wm_state(reply: Option<GetPropertyReply>) -> WindowState {
+    parse_u32s(reply)
+        .and_then(|state| state.first().copied())
+        .map(WindowState::from)
+        .unwrap_or_default()
+}
+
+/// Parses WM_NORMAL_HINTS as laid out in ICCCM section 4.1.2.3.
+fn parse_size_hints(reply: Option<GetPropertyReply>) -> Option<SizeHints> {
+    const US_POSITION: u32 = 1;
+    const US_SIZE: u32 = 1 << 1;
+    const P_POSITION: u32 = 1 << 2;
+    const P_SIZE: u32 = 1 << 3;
+    const P_MIN_SIZE: u32 = 1 << 4;
+    const P_MAX_SIZE: u32 = 1 << 5;
+    const P_RESIZE_INC: u32 = 1 << 6;
+    const P_ASPECT: u32 = 1 << 7;
+    const P_BASE_SIZE: u32 = 1 << 8;
+    const P_WIN_GRAVITY: u32 = 1 << 9;
+
+    let raw = parse_u32s(reply)?;
+    // older clients leave out base size and gravity
+    if raw.len() < 15 {
+        return None
+    }
+    let flags = raw[0];
+    let field = |flag: u32, idx: usize| if flags & flag != 0 {
+        Some((raw[idx] as i32, raw[idx + 1] as i32))
+    } else {None};
+
+    Some(SizeHints {
+        position: field(US_POSITION | P_POSITION, 1),
+        size: field(US_SIZE | P_SIZE, 3),
+        min_size: field(P_MIN_SIZE, 5),
+        max_size: field(P_MAX_SIZE, 7),
+        resize: field(P_RESIZE_INC, 9),
+        min_aspect: field(P_ASPECT, 11),
+        max_aspect: field(P_ASPECT, 13),
+        base: raw.get(16).and_then(|_| field(P_BASE_SIZE, 15)),
+        gravity: raw.get(17).filter(|_| flags & P_WIN_GRAVITY != 0).copied(),
+    })
+}
+
+/// Parses WM_HINTS as laid out in ICCCM section 4.1.2.4.
+fn parse_wm_hints(reply: Option<GetPropertyReply>) -> Option<WmHints> {
+    const INPUT_HINT: u32 = 1;
+    const STATE_HINT: u32 = 1 << 1;
+    const URGENCY_HINT: u32 = 1 << 8;
+
+    let raw = parse_u32s(reply)?;
+    let flags = *raw.first()?;
+
+    Some(WmHints {
+        state: if flags & STATE_HINT != 0 {
+            raw.get(2).copied().map(WindowState::from).unwrap_or_default()
+        } else {
+            WindowState::Normal
+        },
+        urgent: flags & URGENCY_HINT != 0,
+        // clients that do not say otherwise are given the focus
+        input: flags & INPUT_HINT == 0 || raw.get(1).is_some_and(|&input| input != 0),
+    })
+}
+
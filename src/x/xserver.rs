@@ -4,13 +4,15 @@
 use xcb_util::{ewmh, cursor};
 use xcb_util::keysyms::KeySymbols;
 
+use std::cell::Cell;
 use std::convert::TryFrom;
 
 use crate::window::Client;
 use crate::utils;
 use crate::types::{Geometry, Point};
 
-pub use super::{core::*, event::*};
+pub use super::core::*;
+use super::consts;
 
 // used for casting events and stuff
 macro_rules! cast {
@@ -19,12 +21,32 @@ macro_rules! cast {
     };
 }
 
+fn sync_value(value: xcb::sync::Int64) -> i64 {
+    ((value.hi() as i64) << 32) | value.lo() as i64
+}
+
 impl From<xcb::GenericError> for XError {
     fn from(_: xcb::GenericError) -> XError {
         XError::RequestError
     }
 }
 
+/// Interns all the atoms used by the WM.
+fn intern_atoms(conn: &ewmh::Connection) -> Result<InternedAtoms> {
+    // send all the requests first, then wait for the replies
+    let cookies: Vec<_> = InternedAtoms::NAMES.iter()
+        .map(|name| (*name, xcb::intern_atom(conn, false, name)))
+        .collect();
+
+    let atoms = cookies.into_iter()
+        .map(|(name, cookie)| cookie.get_reply()
+            .map(|reply| reply.atom())
+            .map_err(|_| XError::InternAtom(name)))
+        .collect::<Result<Vec<Atom>>>()?;
+
+    Ok(InternedAtoms::from_interned(&atoms))
+}
+
 /// Holds a handle to an XCB connection, as well as other attributes
 /// about the window manager.
 pub struct XCBConnection {
@@ -33,24 +55,41 @@ pub struct XCBConnection {
     pub root: XWindowID,
     pub cursor: xcb::Cursor,
     pub current_scr: i32,
+    /// The time of the event being handled.
+    pub(crate) time: Cell<Timestamp>,
+    /// The first event code of the XSync extension, if it is available.
+    sync_event: Option<u8>,
 }
 
 impl XCBConnection {
     /// Creates a new X Connection.
-    pub fn new(xconn: ewmh::Connection, idx: i32) -> Self {
-        let atoms = InternedAtoms::new(&xconn);
+    pub fn new(xconn: ewmh::Connection, idx: i32) -> Result<Self> {
+        let atoms = intern_atoms(&xconn)?;
         let root = xconn.get_setup()
             .roots()
             .nth(idx as usize)
-            .expect("Could not get root id")
+            .ok_or(XError::NoSuchScreen(idx))?
             .root();
-        Self {
+
+        // needed for _NET_WM_SYNC_REQUEST, which works without it
+        let sync_event = if xcb::sync::initialize(
+            &xconn, xcb::sync::MAJOR_VERSION as u8, xcb::sync::MINOR_VERSION as u8
+        ).get_reply().is_ok() {
+            xconn.get_extension_data(xcb::sync::id()).map(|ext| ext.first_event())
+        } else {
+            warn!("XSync extension is not available");
+            None
+        };
+
+        Ok(Self {
             conn: xconn,
-            atoms: atoms,
-            root: root,
+            atoms,
+            root,
             cursor: 0,
             current_scr: idx,
-        }
+            time: Cell::new(xcb::CURRENT_TIME),
+            sync_event,
+        })
     }
 
     /// Get a reference to the underlying XCB Connection.
@@ -59,44 +98,440 @@ impl XCBConnection {
     }
     
     /// Gets the setup of the underlying xcb connection.
-    pub fn get_setup(&self) -> xcb::Setup {
+    pub fn get_setup(&self) -> xcb::Setup<'_> {
         self.conn.get_setup()
     }
 
+    pub fn lookup_keysym(&self, event:&xcb::KeyPressEvent) -> (xcb::ModMask, xcb::Keysym) {
+        let keysym = KeySymbols::new(&self.conn).press_lookup_keysym(event, 0);
+
+        (event.state() as u32, keysym)
+    }
+
+    /// Get the next raw event from the X server.
+    pub fn next_raw_event(&self) -> Result<xcb::GenericEvent> {
+        self.conn.flush();
+
+        if let Some(event) = self.conn.poll_for_queued_event() {
+            Ok(event)
+        } else {
+            self.conn.wait_for_event().ok_or(XError::ConnectionClosed)
+        }
+    }
+
+    pub fn process_raw_event(&self, event: xcb::GenericEvent) -> Result<XEvent> {
+        use XEvent::*;
+        // release, leave, focus out and circulate request events share
+        // their layout with the corresponding event below
+        use xcb:: {
+            ConfigureNotifyEvent,
+            ConfigureRequestEvent,
+            MapRequestEvent,
+            MapNotifyEvent,
+            UnmapNotifyEvent,
+            DestroyNotifyEvent,
+            CreateNotifyEvent,
+            CirculateNotifyEvent,
+            GravityNotifyEvent,
+            EnterNotifyEvent,
+            FocusInEvent,
+            MotionNotifyEvent,
+            ReparentNotifyEvent,
+            PropertyNotifyEvent,
+            KeyPressEvent,
+            ButtonPressEvent,
+            MappingNotifyEvent,
+        };
+        Ok(match event.response_type() & !0x80 {
+            xcb::CONFIGURE_NOTIFY => {
+                let event = cast!(ConfigureNotifyEvent, event);
+
+                ConfigureNotify(ConfigureEvent{
+                    id: event.window(),
+                    event: event.event(),
+                    geom: Geometry {
+                        x: event.x() as i32,
+                        y: event.y() as i32,
+                        width: event.width() as i32,
+                        height: event.height() as i32,
+                    },
+                    border_width: event.border_width() as u32,
+                    above_sibling: event.above_sibling(),
+                    override_redirect: event.override_redirect(),
+                    is_root: event.window() == self.root,
+                })
+            }
+            xcb::CONFIGURE_REQUEST => {
+                let event = cast!(ConfigureRequestEvent, event);
+                let mask = event.value_mask();
+                let has = |flag: u32| mask & flag as u16 != 0;
+
+                ConfigureRequest(ConfigureRequestData {
+                    id: event.window(),
+                    x: if has(xcb::CONFIG_WINDOW_X) {Some(event.x() as i32)} else {None},
+                    y: if has(xcb::CONFIG_WINDOW_Y) {Some(event.y() as i32)} else {None},
+                    width: if has(xcb::CONFIG_WINDOW_WIDTH) {Some(event.width() as i32)} else {None},
+                    height: if has(xcb::CONFIG_WINDOW_HEIGHT) {Some(event.height() as i32)} else {None},
+                    border_width: if has(xcb::CONFIG_WINDOW_BORDER_WIDTH) {
+                        Some(event.border_width() as u32)
+                    } else {None},
+                    sibling: if has(xcb::CONFIG_WINDOW_SIBLING) {Some(event.sibling())} else {None},
+                    stack_mode: if has(xcb::CONFIG_WINDOW_STACK_MODE) {
+                        Some(event.stack_mode() as u32)
+                    } else {None},
+                    is_root: event.window() == self.root,
+                })
+            },
+            xcb::MAP_REQUEST => {
+                let event = cast!(MapRequestEvent, event);
+                let override_redirect = 
+                if let Some(attrs) = self.get_window_attributes(event.window()) {
+                    attrs.override_redirect
+                } else {false};
+
+                MapRequest(MapRequestData {
+                    id: event.window(),
+                    parent: event.parent(),
+                    override_redirect,
+                })
+            },
+            xcb::MAP_NOTIFY => {
+                let event = cast!(MapNotifyEvent, event);
+
+                MapNotify(MapEvent {
+                    id: event.window(),
+                    event: event.event(),
+                    override_redirect: event.override_redirect(),
+                })
+            },
+            xcb::UNMAP_NOTIFY => {
+                let event = cast!(UnmapNotifyEvent, event);
+
+                UnmapNotify(UnmapEvent {
+                    id: event.window(),
+                    event: event.event(),
+                    synthetic: event.response_type() & 0x80 != 0,
+                })
+            },
+            xcb::DESTROY_NOTIFY => {
+                let event = cast!(DestroyNotifyEvent, event);
+
+                DestroyNotify(DestroyEvent {
+                    id: event.window(),
+                    event: event.event(),
+                })
+            },
+            xcb::CREATE_NOTIFY => {
+                let event = cast!(CreateNotifyEvent, event);
+
+                CreateNotify(CreateEvent {
+                    id: event.window(),
+                    parent: event.parent(),
+                    geom: Geometry {
+                        x: event.x() as i32,
+                        y: event.y() as i32,
+                        width: event.width() as i32,
+                        height: event.height() as i32,
+                    },
+                    border_width: event.border_width() as u32,
+                    override_redirect: event.override_redirect(),
+                })
+            },
+            kind @ xcb::CIRCULATE_REQUEST | kind @ xcb::CIRCULATE_NOTIFY => {
+                let event = cast!(CirculateNotifyEvent, event);
+                let event = CirculateEvent {
+                    id: event.window(),
+                    event: event.event(),
+                    place: event.place() as u32,
+                };
+
+                if kind == xcb::CIRCULATE_REQUEST {
+                    CirculateRequest(event)
+                } else {
+                    CirculateNotify(event)
+                }
+            },
+            xcb::GRAVITY_NOTIFY => {
+                let event = cast!(GravityNotifyEvent, event);
+
+                GravityNotify(event.window(), Point {
+                    x: event.x() as i32,
+                    y: event.y() as i32,
+                })
+            },
+            kind @ xcb::ENTER_NOTIFY | kind @ xcb::LEAVE_NOTIFY => {
+                let event = cast!(EnterNotifyEvent, event);
+                let event = CrossingEvent {
+                    window: event.event(),
+                    root: event.root(),
+                    child: event.child(),
+                    root_pt: Point {x: event.root_x() as i32, y: event.root_y() as i32},
+                    event_pt: Point {x: event.event_x() as i32, y: event.event_y() as i32},
+                    mode: event.mode() as u32,
+                    detail: event.detail() as u32,
+                    state: event.state() as u32,
+                    time: event.time(),
+                };
+
+                if kind == xcb::ENTER_NOTIFY {
+                    EnterNotify(event)
+                } else {
+                    LeaveNotify(event)
+                }
+            },
+            kind @ xcb::FOCUS_IN | kind @ xcb::FOCUS_OUT => {
+                let event = cast!(FocusInEvent, event);
+                let event = FocusEvent {
+                    window: event.event(),
+                    mode: event.mode() as u32,
+                    detail: event.detail() as u32,
+                };
+
+                if kind == xcb::FOCUS_IN {
+                    FocusIn(event)
+                } else {
+                    FocusOut(event)
+                }
+            },
+            xcb::MOTION_NOTIFY => {
+                let event = cast!(MotionNotifyEvent, event);
+
+                MotionNotify(PointerEvent {
+                    window: event.event(),
+                    root: event.root(),
+                    child: event.child(),
+                    root_pt: Point {x: event.root_x() as i32, y: event.root_y() as i32},
+                    event_pt: Point {x: event.event_x() as i32, y: event.event_y() as i32},
+                    button: consts::BUTTON_INDEX_ANY,
+                    state: event.state() as u32,
+                    time: event.time(),
+                })
+            },
+            xcb::REPARENT_NOTIFY => {
+                let event = cast!(ReparentNotifyEvent, event);
+
+                ReparentNotify(ReparentEvent {
+                    id: event.window(),
+                    event: event.event(),
+                    parent: event.parent(),
+                    pt: Point {x: event.x() as i32, y: event.y() as i32},
+                    override_redirect: event.override_redirect(),
+                })
+            },
+            xcb::PROPERTY_NOTIFY => {
+                let event = cast!(PropertyNotifyEvent, event);
+
+                PropertyNotify(PropertyEvent {
+                    id: event.window(),
+                    atom: event.atom(),
+                    time: event.time(),
+                })
+            },
+            kind @ xcb::KEY_PRESS | kind @ xcb::KEY_RELEASE => {
+                let event = cast!(KeyPressEvent, event);
+
+                let (modmask, keysym) = self.lookup_keysym(event);
+                let event = KeypressEvent {
+                    mask: modmask,
+                    keysym,
+                    keycode: event.detail(),
+                    window: event.event(),
+                    root: event.root(),
+                    child: event.child(),
+                    time: event.time(),
+                };
+
+                if kind == xcb::KEY_PRESS {
+                    KeyPress(event)
+                } else {
+                    KeyRelease(event)
+                }
+            },
+            kind @ xcb::BUTTON_PRESS | kind @ xcb::BUTTON_RELEASE => {
+                let event = cast!(ButtonPressEvent, event);
+                let event = PointerEvent {
+                    window: event.event(),
+                    root: event.root(),
+                    child: event.child(),
+                    root_pt: Point {x: event.root_x() as i32, y: event.root_y() as i32},
+                    event_pt: Point {x: event.event_x() as i32, y: event.event_y() as i32},
+                    button: event.detail() as ButtonIndex,
+                    state: event.state() as u32,
+                    time: event.time(),
+                };
+
+                if kind == xcb::BUTTON_PRESS {
+                    ButtonPress(event)
+                } else {
+                    ButtonRelease(event)
+                }
+            },
+            xcb::EXPOSE => {
+                let event = cast!(xcb::ExposeEvent, event);
+
+                Expose(ExposeEvent {
+                    id: event.window(),
+                    geom: Geometry {
+                        x: event.x() as i32,
+                        y: event.y() as i32,
+                        width: event.width() as i32,
+                        height: event.height() as i32,
+                    },
+                    count: event.count() as u32,
+                })
+            },
+            xcb::MAPPING_NOTIFY => {
+                let event = cast!(MappingNotifyEvent, event);
+
+                MappingNotify(MappingEvent {
+                    request: event.request() as u32,
+                    first_keycode: event.first_keycode(),
+                    count: event.count() as u32,
+                })
+            },
+            xcb::CLIENT_MESSAGE => {
+                let event = cast!(xcb::ClientMessageEvent, event);
+                let data = event.data();
+                let window = event.window();
+                let type_ = event.type_();
+
+                let data = match event.format() {
+                    8 => ClientMessageData::try_from(data.data8()),
+                    16 => ClientMessageData::try_from(data.data16()),
+                    32 => ClientMessageData::try_from(data.data32()),
+                    n => return Err(XError::ProtocolError(
+                        format!("Client message for window {} has format {}", window, n)
+                    )),
+                }.map_err(|_| XError::ProtocolError(
+                    format!("Client message for window {} has invalid data", window)
+                ))?;
+
+                ClientMessage(ClientMessageEvent {window, type_, data})
+            },
+            0 => {
+                // errors are delivered as events with a response type of 0
+                let error = unsafe {
+                    &*(event.ptr as *const xcb::ffi::base::xcb_generic_error_t)
+                };
+
+                Error(XErrorEvent {
+                    code: error.error_code,
+                    sequence: error.sequence,
+                    resource: error.resource_id,
+                    major_opcode: error.major_code,
+                    minor_opcode: error.minor_code,
+                })
+            },
+            kind if Some(kind) == self.sync_event.map(|base| base + xcb::sync::ALARM_NOTIFY) => {
+                let event = cast!(xcb::sync::AlarmNotifyEvent, event);
+
+                SyncAlarmNotify(SyncAlarmEvent {
+                    alarm: event.alarm(),
+                    counter_value: sync_value(event.counter_value()),
+                    alarm_value: sync_value(event.alarm_value()),
+                    time: event.timestamp(),
+                })
+            },
+            kind if kind == consts::GE_GENERIC || kind >= consts::FIRST_EXTENSION_EVENT => {
+                // every event is at least 32 bytes long
+                let data = unsafe { *(event.ptr as *const [u8; 32]) };
+
+                Extension(ExtensionEvent::from_raw(data))
+            },
+            unhandled => Unknown(unhandled),
+        })
+    }
+}
+
+impl XConn for XCBConnection {
     /// Returns atoms supported by the connection.
-    pub fn get_atoms(&self) -> InternedAtoms {
+    fn get_atoms(&self) -> InternedAtoms {
         self.atoms
     }
 
     /// Get the root ID of the current screen.
-    pub fn get_root_id(&self) -> XWindowID {
-        self.conn.get_setup()
-            .roots()
-            .nth(self.current_scr as usize)
-            .expect("Could not get root id")
-            .root()
+    fn get_root_id(&self) -> XWindowID {
+        self.root
     }
 
-    /// Get the root geometry (which is usually the screen resolution)
-    pub fn get_root_geom(&self) -> Result<Geometry> {
-        let root_id = self.get_root_id();
-
-        self.get_geometry(root_id)
+    /// Set the screen of the current root.
+    fn set_root_scr(&mut self, scr: i32) {
+        if let Some(screen) = self.conn.get_setup().roots().nth(scr as usize) {
+            self.current_scr = scr;
+            self.root = screen.root();
+        } else {
+            error!("Screen {} does not exist", scr);
+        }
     }
 
-    /// Set the screen of the current root.
-    pub fn set_root_scr(&mut self, scr: i32) {
-        self.current_scr = scr;
+    fn next_event(&self) -> Result<XEvent> {
+        self.process_raw_event(self.next_raw_event()?)
     }
 
     /// Get a list of all active windows.
-    pub fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
+    fn query_tree(&self, window: XWindowID) -> Result<Vec<XWindowID>> {
         Ok(xcb::query_tree(&self.conn, window).get_reply()
             .map(|ok| ok.children().to_owned())?)
     }
 
+    fn query_sync_counter(&self, counter: SyncCounter) -> Result<i64> {
+        let value = xcb::sync::query_counter(&self.conn, counter).get_reply()?.counter_value();
+
+        Ok(sync_value(value))
+    }
+
+    fn create_sync_alarm(&self, counter: SyncCounter, value: i64) -> Result<SyncAlarm> {
+        let alarm = self.conn.generate_id();
+        let mask = xcb::sync::CA_COUNTER | xcb::sync::CA_VALUE_TYPE | xcb::sync::CA_VALUE
+            | xcb::sync::CA_TEST_TYPE | xcb::sync::CA_DELTA | xcb::sync::CA_EVENTS;
+        // the value list is sent as is, with 64 bit values split into their high and low halves;
+        // with a delta of 0 the alarm waits to be set again once it has triggered
+        let values: [u32; 8] = [
+            counter,
+            xcb::sync::VALUETYPE_ABSOLUTE,
+            (value >> 32) as u32, value as u32,
+            xcb::sync::TESTTYPE_POSITIVE_COMPARISON,
+            0, 0,
+            1,
+        ];
+        unsafe {
+            xcb::ffi::sync::xcb_sync_create_alarm(
+                (*self.conn).get_raw_conn(), alarm, mask, values.as_ptr() as *const _
+            );
+        }
+
+        Ok(alarm)
+    }
+
+    fn set_sync_alarm(&self, alarm: SyncAlarm, value: i64) {
+        let values: [u32; 2] = [(value >> 32) as u32, value as u32];
+        unsafe {
+            xcb::ffi::sync::xcb_sync_change_alarm(
+                (*self.conn).get_raw_conn(), alarm, xcb::sync::CA_VALUE, values.as_ptr() as *const _
+            );
+        }
+    }
+
+    fn destroy_sync_alarm(&self, alarm: SyncAlarm) {
+        xcb::sync::destroy_alarm(&self.conn, alarm);
+    }
+
+    fn query_pointer(&self, window_id: XWindowID) -> Result<PointerQueryReply> {
+        debug!("Querying pointer location for window {}", window_id);
+
+        Ok(xcb::query_pointer(&self.conn, window_id).get_reply()
+            .map(|ok| PointerQueryReply {
+                root: ok.root(),
+                child: ok.child(),
+                location: Point {
+                    x: ok.root_x() as i32,
+                    y: ok.root_y() as i32,
+                },
+            })?)
+    }
+
     /// Create the cursor.
-    pub fn create_cursor(&mut self, glyph: u16) -> Result<()> {
+    fn create_cursor(&mut self, glyph: u16) -> Result<()> {
         debug!("Creating cursor");
         let cursor_id = cursor::create_font_cursor_checked(&self.conn, glyph)?;
         self.cursor = cursor_id;
@@ -104,72 +539,74 @@ impl XCBConnection {
     }
 
     /// Set cursor.
-    pub fn set_cursor(&mut self, window: XWindowID) {
+    fn set_cursor(&mut self, window: XWindowID) {
         debug!("Setting cursor for {}", window);
         self.change_window_attributes(window, &utils::cursor_attrs(self.cursor))
     }
 
+    fn create_hidden_window(&self) -> Result<XWindowID> {
+        let window = self.conn.generate_id();
+
+        xcb::create_window_checked(&self.conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window, self.root,
+            -1, -1, 1, 1, 0,
+            consts::WINDOW_CLASS_INPUT_ONLY,
+            xcb::COPY_FROM_PARENT,
+            &[(consts::CW_OVERRIDE_REDIRECT, 1)],
+        ).request_check()?;
+
+        Ok(window)
+    }
+
     /// Get window attributes
-    pub fn get_window_attributes(&self, window: XWindowID) -> Option<xcb::GetWindowAttributesReply> {
+    fn get_window_attributes(&self, window: XWindowID) -> Option<WindowAttributes> {
         debug!("Getting attributes for window {}", window);
 
         xcb::get_window_attributes(&self.conn, window).get_reply().ok()
+            .map(|ok| WindowAttributes {
+                override_redirect: ok.override_redirect(),
+                map_state: match ok.map_state() as u32 {
+                    xcb::MAP_STATE_VIEWABLE => MapState::Viewable,
+                    xcb::MAP_STATE_UNVIEWABLE => MapState::Unviewable,
+                    _ => MapState::Unmapped,
+                },
+            })
     }
 
-    pub fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
+    fn change_window_attributes(&self, window: XWindowID, attrs: &[(u32, u32)]) {
         //debug!("Changing attributes for window {}", window);
         xcb::change_window_attributes(&self.conn, window, attrs);
     }
 
-    pub fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
+    fn change_window_attributes_checked(&self, window: XWindowID, attrs: &[(u32, u32)]) -> Result<()> {
         //debug!("Changing window attributes");
         Ok(xcb::change_window_attributes_checked(&self.conn, window, attrs).request_check()?)
     }
 
-    pub fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
+    fn configure_window(&self, window: XWindowID, attrs: &[(u16, u32)]) {
         debug!("Configuring window {}", window);
         xcb::configure_window(&self.conn, window, attrs);
     }
 
-    pub fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
+    fn reparent_window(&self, window: XWindowID, parent: XWindowID) {
         debug!("Reparenting window {} under window {}", window, parent);
         xcb::reparent_window(&self.conn, window, parent, 0, 0);
     }
 
-    // pub fn create_window(&self, parent: XWindowID, geom: Geometry, border_width,) -> XWindowID {
-    //     let win_id = self.conn.generate_id();
-
-    //     let cookie = xcb::create_window(
-    //         &self.conn,
-    //         xcb::COPY_FROM_PARENT,
-    //         win_id,
-    //         parent,
-    //         geom.x,
-    //         geom.y,
-    //         geom.height,
-    //         geom.width,
-    //     );
-    // }
-
-    pub fn map_window(&self, window_id: XWindowID) {
+    fn map_window(&self, window_id: XWindowID) {
         debug!("Mapping window {}", window_id);
 
-        let cookie = xcb::map_window(&self.conn, window_id);
-        if let Err(e) = cookie.request_check() {
-            error!("Could not map window {}: {}", window_id, e)
-        }
+        xcb::map_window(&self.conn, window_id);
     }
 
-    pub fn unmap_window(&self, window_id: XWindowID) {
+    fn unmap_window(&self, window_id: XWindowID) {
         debug!("Unmapping window {}", window_id);
 
-        let cookie = xcb::unmap_window(&self.conn, window_id);
-        if let Err(e) = cookie.request_check() {
-            error!("Could not unmap window {}: {}", window_id, e)
-        }
+        xcb::unmap_window(&self.conn, window_id);
     }
 
-    pub fn destroy_window(&self, window: &Client) {
+    fn destroy_window(&self, window: &Client) {
         debug!("Destroying window {}", window.id());
         if window.supports(self.atoms.WM_DELETE_WINDOW) {
             debug!("Destroying window via ICCCM WM_DELETE_WINDOW");
@@ -196,25 +633,33 @@ impl XCBConnection {
         }
     }
 
-    pub fn set_input_focus(&self, window_id: XWindowID) {
-        debug!("Setting focus for window {}", window_id);
+    fn kill_client(&self, window_id: XWindowID) {
+        debug!("Killing client of window {}", window_id);
+
+        xcb::kill_client(&self.conn, window_id);
+    }
+
+    fn touch_property(&self, window: XWindowID, property: Atom, type_: Atom) {
+        xcb::change_property(
+            &self.conn, xcb::PROP_MODE_APPEND as u8, window, property, type_, 8, &[] as &[u8]
+        );
+    }
 
-        xcb::set_input_focus(&self.conn, xcb::INPUT_FOCUS_POINTER_ROOT as u8, window_id, xcb::CURRENT_TIME);
+    fn set_timestamp(&self, time: Timestamp) {
+        self.time.set(time);
     }
 
-    pub fn set_geometry(&self, window_id: XWindowID, geom :Geometry) {
-        self.configure_window(window_id, &utils::configure_resize(
-            geom.width as u32,
-            geom.height as u32,
-        ));
+    fn set_input_focus(&self, window_id: XWindowID) {
+        debug!("Setting focus for window {}", window_id);
+
+        xcb::set_input_focus(&self.conn, xcb::INPUT_FOCUS_POINTER_ROOT as u8, window_id, self.time.get());
+    }
 
-        self.configure_window(window_id, &utils::configure_move(
-            geom.x as u32,
-            geom.y as u32,
-        ))
+    fn set_geometry(&self, window_id: XWindowID, geom :Geometry) {
+        self.configure_window(window_id, &utils::configure_geometry(geom))
     } 
 
-    pub fn get_geometry(&self, window_id: XWindowID) -> Result<Geometry> {
+    fn get_geometry(&self, window_id: XWindowID) -> Result<Geometry> {
         debug!("Getting geometry");
         Ok(xcb::get_geometry(&self.conn, window_id).get_reply()
             .map(|ok| Geometry::from(
@@ -227,13 +672,7 @@ impl XCBConnection {
             ))?)
     }
 
-    pub fn lookup_keysym(&self, event:&xcb::KeyPressEvent) -> (xcb::ModMask, xcb::Keysym) {
-        let keysym = KeySymbols::new(&self.conn).press_lookup_keysym(event, 0);
-
-        (event.state() as u32, keysym)
-    }
-
-    pub fn grab_key(&self, window_id: XWindowID, mask: xcb::ModMask, keysym: xcb::Keysym) {
+    fn grab_key(&self, window_id: XWindowID, mask: ModMask, keysym: KeySym) {
         debug!("Grabbing key {} for window {}", keysym, window_id);
 
         let code = KeySymbols::new(&self.conn).get_keycode(keysym).next();
@@ -255,11 +694,11 @@ impl XCBConnection {
         }
     }
 
-    pub fn grab_button(&self, 
+    fn grab_button(&self, 
         window_id: XWindowID, 
-        mask: xcb::ButtonMask, 
-        button: xcb::ButtonIndex, 
-        modmask: xcb::ModMask, 
+        mask: ButtonMask, 
+        button: ButtonIndex, 
+        modmask: ModMask, 
         confine: bool) 
     {
         debug!("Grab button {} for window: {}", button, window_id);
@@ -280,7 +719,7 @@ impl XCBConnection {
         });
     }
 
-    pub fn grab_pointer(&self, window_id: XWindowID, mask: xcb::EventMask) {
+    fn grab_pointer(&self, window_id: XWindowID, mask: EventMask) {
         debug!("Grabbing pointer for window: {}", window_id);
 
         xcb::grab_pointer(
@@ -296,186 +735,32 @@ impl XCBConnection {
         );
     }
 
-    pub fn ungrab_pointer(&self) {
+    fn ungrab_pointer(&self) {
         debug!("Ungrabbing pointer");
 
         xcb::ungrab_pointer(&self.conn, xcb::CURRENT_TIME);
     }
 
-    pub fn query_pointer(&self, window_id: XWindowID) -> Result<xcb::QueryPointerReply> {
-        debug!("Querying pointer location for window {}", window_id);
+    fn grab_keyboard(&self, window_id: XWindowID) {
+        debug!("Grabbing keyboard for window: {}", window_id);
 
-        Ok(xcb::query_pointer(&self.conn, window_id).get_reply()?)
+        xcb::grab_keyboard(
+            &self.conn,
+            false,
+            window_id,
+            xcb::CURRENT_TIME,
+            xcb::GRAB_MODE_ASYNC as u8,
+            xcb::GRAB_MODE_ASYNC as u8,
+        );
     }
 
-    pub fn next_event(&self) -> xcb::GenericEvent {
-        self.conn.flush();
+    fn ungrab_keyboard(&self) {
+        debug!("Ungrabbing keyboard");
 
-        if let Some(event) = self.conn.poll_for_queued_event() {
-            event
-        } else {
-            self.conn.wait_for_event().unwrap_or_else(|| {
-                error!("The X server closed the connection, exiting");
-                std::process::exit(10);
-            })
-        }
+        xcb::ungrab_keyboard(&self.conn, xcb::CURRENT_TIME);
     }
 
-    pub fn process_raw_event(&self, event: xcb::GenericEvent) -> XEvent {
-        use XEvent::*;
-        #[allow(unused_imports)]
-        use xcb:: {
-            ConfigureNotifyEvent,
-            ConfigureRequestEvent,
-            MapRequestEvent,
-            MapNotifyEvent,
-            UnmapNotifyEvent,
-            DestroyNotifyEvent,
-            EnterNotifyEvent,
-            LeaveNotifyEvent,
-            MotionNotifyEvent,
-            ReparentNotifyEvent,
-            PropertyNotifyEvent,
-            KeyPressEvent,
-            ButtonPressEvent,
-            ClientMessageEvent,
-        };
-        match event.response_type() & !0x80 {
-            xcb::CONFIGURE_NOTIFY => {
-                let event = cast!(ConfigureNotifyEvent, event);
-
-                ConfigureNotify(ConfigureEvent{
-                    id: event.window(),
-                    geom: Geometry {
-                        x: event.x() as i32,
-                        y: event.y() as i32,
-                        width: event.width() as i32,
-                        height: event.height() as i32,
-                    },
-                    is_root: event.window() == self.root,
-                })
-            }
-            xcb::CONFIGURE_REQUEST => {
-                let event = cast!(ConfigureRequestEvent, event);
-
-                ConfigureRequest(ConfigureEvent{
-                    id: event.window(),
-                    geom: Geometry {
-                        x: event.x() as i32,
-                        y: event.y() as i32,
-                        width: event.width() as i32,
-                        height: event.height() as i32,
-                    },
-                    is_root: event.window() == self.root,
-                })
-            },
-            xcb::MAP_REQUEST => {
-                let event = cast!(MapRequestEvent, event);
-                let override_redirect = 
-                if let Some(attrs) = self.get_window_attributes(event.window()) {
-                    attrs.override_redirect()
-                } else {false};
-
-                MapRequest(event.window(), override_redirect)
-            },
-            xcb::MAP_NOTIFY => {
-                let event = cast!(MapNotifyEvent, event);
-
-                MapNotify(event.window())
-            },
-            xcb::UNMAP_NOTIFY => {
-                let event = cast!(UnmapNotifyEvent, event);
-
-                UnmapNotify(event.window())
-            },
-            xcb::DESTROY_NOTIFY => {
-                let event = cast!(DestroyNotifyEvent, event);
-
-                DestroyNotify(event.window())
-            },
-            xcb::ENTER_NOTIFY => {
-                let event = cast!(EnterNotifyEvent, event);
-
-                EnterNotify(event.child())
-            },
-            xcb::LEAVE_NOTIFY => {
-                let event = cast!(LeaveNotifyEvent, event);
-
-                LeaveNotify(event.child())
-            },
-            xcb::MOTION_NOTIFY => {
-                let event = cast!(MotionNotifyEvent, event);
-
-                MotionNotify(Point {
-                    x: event.root_x() as i32,
-                    y: event.root_y() as i32,
-                })
-            },
-            xcb::REPARENT_NOTIFY => {
-                let event = cast!(ReparentNotifyEvent, event);
-
-                ReparentNotify(event.window())
-            },
-            xcb::PROPERTY_NOTIFY => {
-                let event = cast!(PropertyNotifyEvent, event);
-
-                PropertyNotify(PropertyEvent {
-                    id: event.window(),
-                    atom: event.atom(),
-                    time: event.time(),
-                })
-            },
-            xcb::KEY_PRESS => {
-                let event = cast!(KeyPressEvent, event);
-
-                let (modmask, keysym) = self.lookup_keysym(event);
-
-                KeyPress(KeypressEvent {
-                    mask: modmask,
-                    keysym: keysym,
-                })
-            },
-            xcb::KEY_RELEASE => KeyRelease,
-            xcb::BUTTON_PRESS => {
-                let _event = cast!(ButtonPressEvent, event);
-
-                todo!()
-            },
-            xcb::BUTTON_RELEASE => ButtonRelease,
-            xcb::CLIENT_MESSAGE => {
-                let event = cast!(ClientMessageEvent, event);
-                let data = event.data();
-                let window = event.window();
-
-                match event.format() {
-                    8 => {
-                        let data = ClientMessageData::try_from(
-                            data.data8()
-                        ).expect("Invalid client data");
-                        assert!(data.is_u8());
-
-                        ClientMessage(window, data)
-                    }
-                    16 => {
-                        let data = ClientMessageData::try_from(
-                            data.data16()
-                        ).expect("Invalid client data");
-                        assert!(data.is_u16());
-                        
-                        ClientMessage(window, data)
-                    }
-                    32 => {
-                        let data = ClientMessageData::try_from(
-                            data.data32()
-                        ).expect("Invalid client data");
-                        assert!(data.is_u32());
-                        
-                        ClientMessage(window, data)
-                    }
-                    _ => unreachable!() //todo: make this function fallible
-                }
-            },
-            unhandled => Unknown(unhandled),
-        }
+    fn flush(&self) {
+        self.conn.flush();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,41 @@
+//! Helpers for tests that feed events to a `WindowManager`
+//! attached to a DummyConn.
+
+use rcwm::manager::WindowManager;
+use rcwm::x::{DummyConn, XEvent};
+use rcwm::x::core::{ClientMessageEvent, ClientMessageData, MapRequestData, XWindowID};
+use rcwm::x::dummy::{DummyWindow, DUMMY_ROOT};
+use rcwm::types::Geometry;
+
+pub const ROOT_GEOM: Geometry = Geometry {x: 0, y: 0, width: 1000, height: 800};
+/// The geometry of a single tiled window, inside its borders.
+pub const FULL: Geometry = Geometry {x: 0, y: 0, width: 996, height: 796};
+
+pub fn setup() -> WindowManager<DummyConn> {
+    WindowManager::register(DummyConn::new(ROOT_GEOM), 0)
+        .expect("Failed to register window manager")
+}
+
+pub fn map_normal(wm: &mut WindowManager<DummyConn>) -> XWindowID {
+    let id = wm.conn().create_normal_window();
+    wm.process_event(XEvent::MapRequest(MapRequestData::new(id, DUMMY_ROOT)));
+    id
+}
+
+pub fn map_window(wm: &mut WindowManager<DummyConn>, window: DummyWindow) -> XWindowID {
+    let id = wm.conn().create_window(window);
+    wm.process_event(XEvent::MapRequest(MapRequestData::new(id, DUMMY_ROOT)));
+    id
+}
+
+pub fn client_message(window: XWindowID, type_: u32, data: [u32; 5]) -> XEvent {
+    XEvent::ClientMessage(ClientMessageEvent {
+        window,
+        type_,
+        data: ClientMessageData::U32(data),
+    })
+}
+
+pub fn geom_of(wm: &WindowManager<DummyConn>, id: XWindowID) -> Geometry {
+    wm.conn().window(id).expect("window should exist").geom
+}
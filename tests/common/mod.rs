@@ -0,0 +1,7 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+pub mod dummy;
+#[cfg(feature = "xcb-backend")]
+pub mod xvfb;
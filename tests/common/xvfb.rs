@@ -0,0 +1,392 @@
+//! A headless test harness for running RaccoonWM end to end.
+//!
+//! Each `Harness` starts a private Xvfb server, registers a `WindowManager`
+//! on it and runs its event loop in a separate thread. Tests then act as
+//! ordinary X clients over their own connection: they create windows with
+//! the properties they want (class, type, hints, transient-for), and
+//! assert on the resulting window tree, geometries and properties.
+//!
+//! The window manager runs on the x11rb backend if `x11rb-backend` is
+//! enabled, and on the XCB backend otherwise. The tests themselves
+//! always talk to the server through XCB.
+//!
+//! If Xvfb is not installed, `Harness::start` returns `None` and the test
+//! should return early.
+
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rcwm::manager::WindowManager;
+use rcwm::types::Geometry;
+#[cfg(not(feature = "x11rb-backend"))]
+use rcwm::x::XCBConnection;
+#[cfg(feature = "x11rb-backend")]
+use rcwm::x::X11RBConnection;
+
+/// The size of the screen created by Xvfb.
+pub const SCREEN_WIDTH: i32 = 1280;
+pub const SCREEN_HEIGHT: i32 = 800;
+
+/// How long to wait for the window manager to react before giving up.
+const TIMEOUT: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Connects to the display and registers the window manager on it.
+#[cfg(not(feature = "x11rb-backend"))]
+fn register_wm(display: &str) -> WindowManager<XCBConnection> {
+    use xcb_util::ewmh;
+
+    let (conn, screen_idx) = xcb::Connection::connect(Some(display))
+        .expect("Failed to connect to Xvfb");
+    let conn = ewmh::Connection::connect(conn)
+        .map_err(|(err, _)| err)
+        .expect("Failed to connect via EWMH");
+
+    let conn = XCBConnection::new(conn, screen_idx)
+        .expect("Failed to set up connection");
+    WindowManager::register(conn, screen_idx)
+        .expect("Failed to register window manager")
+}
+
+/// Connects to the display and registers the window manager on it,
+/// using the x11rb backend.
+#[cfg(feature = "x11rb-backend")]
+fn register_wm(display: &str) -> WindowManager<X11RBConnection> {
+    let (conn, screen_idx) = x11rb::rust_connection::RustConnection::connect(Some(display))
+        .expect("Failed to connect to Xvfb");
+    let screen_idx = screen_idx as i32;
+
+    let conn = X11RBConnection::new(conn, screen_idx)
+        .expect("Failed to set up connection");
+    WindowManager::register(conn, screen_idx)
+        .expect("Failed to register window manager")
+}
+
+/// A running Xvfb server with the window manager attached.
+pub struct Harness {
+    pub conn: xcb::Connection,
+    pub root: u32,
+    root_visual: u32,
+    display: String,
+}
+
+impl Harness {
+    /// Starts Xvfb and the window manager.
+    ///
+    /// Returns `None` if Xvfb could not be found.
+    pub fn start() -> Option<Self> {
+        let (tx, rx) = mpsc::channel();
+
+        // Xvfb is spawned from the window manager's thread and is set to die
+        // with it, so that it outlives the event loop and is cleaned up
+        // when the test binary exits.
+        thread::spawn(move || {
+            let mut command = Command::new("Xvfb");
+            command
+                .args(["-displayfd", "1", "-nolisten", "tcp", "-screen", "0"])
+                .arg(format!("{}x{}x24", SCREEN_WIDTH, SCREEN_HEIGHT))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null());
+            unsafe {
+                command.pre_exec(|| {
+                    nix::libc::prctl(nix::libc::PR_SET_PDEATHSIG, nix::libc::SIGTERM);
+                    Ok(())
+                });
+            }
+
+            let mut xvfb = match command.spawn() {
+                Ok(xvfb) => xvfb,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    tx.send(None).unwrap();
+                    return
+                }
+                Err(e) => panic!("Could not start Xvfb: {}", e),
+            };
+
+            // Xvfb writes the display number to -displayfd once it is ready
+            let mut display = String::new();
+            BufReader::new(xvfb.stdout.take().unwrap())
+                .read_line(&mut display)
+                .expect("Could not read display number from Xvfb");
+            let display = format!(":{}", display.trim());
+
+            let mut wm = register_wm(&display);
+
+            tx.send(Some(display)).unwrap();
+
+            // this only returns once the connection is closed
+            let _ = wm.run();
+        });
+
+        let display = rx.recv()
+            .expect("Window manager failed to start")?;
+
+        let (conn, screen_idx) = xcb::Connection::connect(Some(&display))
+            .expect("Failed to connect to Xvfb");
+
+        let (root, root_visual) = {
+            let screen = conn.get_setup()
+                .roots()
+                .nth(screen_idx as usize)
+                .expect("Could not get screen");
+            (screen.root(), screen.root_visual())
+        };
+
+        Some(Self {
+            conn,
+            root,
+            root_visual,
+            display,
+        })
+    }
+
+    /// The display name of the Xvfb server, e.g. ":99".
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    /// Starts building a new client window.
+    pub fn window(&self) -> WindowBuilder<'_> {
+        WindowBuilder {
+            harness: self,
+            geom: Geometry {x: 0, y: 0, width: 200, height: 100},
+            class: None,
+            window_type: Some("_NET_WM_WINDOW_TYPE_NORMAL"),
+            transient_for: None,
+            min_size: None,
+            max_size: None,
+            override_redirect: false,
+        }
+    }
+
+    /// Polls `cond` until it returns true or the timeout expires.
+    pub fn wait_until<F>(&self, mut cond: F) -> bool
+    where
+        F: FnMut(&Self) -> bool
+    {
+        let start = Instant::now();
+
+        while start.elapsed() < TIMEOUT {
+            if cond(self) {
+                return true
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        cond(self)
+    }
+
+    /// Waits until `window` has the geometry `geom`, and returns
+    /// its last known geometry.
+    pub fn wait_for_geometry(&self, window: u32, geom: Geometry) -> Geometry {
+        self.wait_until(|h| h.geometry(window) == geom);
+        self.geometry(window)
+    }
+
+    pub fn atom(&self, name: &str) -> u32 {
+        xcb::intern_atom(&self.conn, false, name)
+            .get_reply()
+            .expect("Could not intern atom")
+            .atom()
+    }
+
+    pub fn geometry(&self, window: u32) -> Geometry {
+        let reply = xcb::get_geometry(&self.conn, window)
+            .get_reply()
+            .expect("Could not get geometry");
+
+        Geometry {
+            x: reply.x() as i32,
+            y: reply.y() as i32,
+            width: reply.width() as i32,
+            height: reply.height() as i32,
+        }
+    }
+
+    pub fn is_viewable(&self, window: u32) -> bool {
+        xcb::get_window_attributes(&self.conn, window)
+            .get_reply()
+            .map(|attrs| attrs.map_state() == xcb::MAP_STATE_VIEWABLE as u8)
+            .unwrap_or(false)
+    }
+
+    pub fn focused(&self) -> u32 {
+        xcb::get_input_focus(&self.conn)
+            .get_reply()
+            .expect("Could not get input focus")
+            .focus()
+    }
+
+    /// Returns the children of the root window, in stacking order from
+    /// bottom to top.
+    pub fn children(&self) -> Vec<u32> {
+        xcb::query_tree(&self.conn, self.root)
+            .get_reply()
+            .expect("Could not query tree")
+            .children()
+            .to_vec()
+    }
+
+    /// Reads a 32-bit list property such as `_NET_SUPPORTED`.
+    pub fn get_property_u32(&self, window: u32, name: &str) -> Vec<u32> {
+        xcb::get_property(
+            &self.conn, false, window, self.atom(name), xcb::ATOM_ANY, 0, 1024
+        )
+        .get_reply()
+        .map(|reply| reply.value::<u32>().to_vec())
+        .unwrap_or_default()
+    }
+
+    pub fn unmap(&self, window: u32) {
+        xcb::unmap_window(&self.conn, window);
+        self.conn.flush();
+    }
+
+    pub fn destroy(&self, window: u32) {
+        xcb::destroy_window(&self.conn, window);
+        self.conn.flush();
+    }
+
+    fn set_property_u32(&self, window: u32, property: u32, type_: u32, data: &[u32]) {
+        xcb::change_property(
+            &self.conn, xcb::PROP_MODE_REPLACE as u8, window, property, type_, 32, data
+        );
+    }
+}
+
+/// Builds and maps a scripted client window.
+pub struct WindowBuilder<'a> {
+    harness: &'a Harness,
+    geom: Geometry,
+    class: Option<(String, String)>,
+    window_type: Option<&'static str>,
+    transient_for: Option<u32>,
+    min_size: Option<(u32, u32)>,
+    max_size: Option<(u32, u32)>,
+    override_redirect: bool,
+}
+
+impl<'a> WindowBuilder<'a> {
+    pub fn geometry(mut self, geom: Geometry) -> Self {
+        self.geom = geom;
+        self
+    }
+
+    pub fn class(mut self, instance: &str, class: &str) -> Self {
+        self.class = Some((instance.into(), class.into()));
+        self
+    }
+
+    /// Sets `_NET_WM_WINDOW_TYPE` to the given atom name.
+    ///
+    /// Windows are `_NET_WM_WINDOW_TYPE_NORMAL` by default.
+    pub fn window_type(mut self, window_type: &'static str) -> Self {
+        self.window_type = Some(window_type);
+        self
+    }
+
+    /// Do not set `_NET_WM_WINDOW_TYPE` at all.
+    pub fn untyped(mut self) -> Self {
+        self.window_type = None;
+        self
+    }
+
+    pub fn transient_for(mut self, window: u32) -> Self {
+        self.transient_for = Some(window);
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    pub fn override_redirect(mut self) -> Self {
+        self.override_redirect = true;
+        self
+    }
+
+    /// Creates the window, sets its properties and maps it.
+    pub fn map(self) -> u32 {
+        let h = self.harness;
+        let window = h.conn.generate_id();
+
+        xcb::create_window(
+            &h.conn,
+            xcb::COPY_FROM_PARENT as u8,
+            window,
+            h.root,
+            self.geom.x as i16,
+            self.geom.y as i16,
+            self.geom.width as u16,
+            self.geom.height as u16,
+            0,
+            xcb::WINDOW_CLASS_INPUT_OUTPUT as u16,
+            h.root_visual,
+            &[(xcb::CW_OVERRIDE_REDIRECT, self.override_redirect as u32)],
+        );
+
+        if let Some((instance, class)) = &self.class {
+            let value = format!("{}\0{}\0", instance, class);
+            xcb::change_property(
+                &h.conn,
+                xcb::PROP_MODE_REPLACE as u8,
+                window,
+                xcb::ATOM_WM_CLASS,
+                xcb::ATOM_STRING,
+                8,
+                value.as_bytes(),
+            );
+        }
+
+        if let Some(window_type) = self.window_type {
+            h.set_property_u32(
+                window,
+                h.atom("_NET_WM_WINDOW_TYPE"),
+                xcb::ATOM_ATOM,
+                &[h.atom(window_type)]
+            );
+        }
+
+        if let Some(parent) = self.transient_for {
+            h.set_property_u32(
+                window, xcb::ATOM_WM_TRANSIENT_FOR, xcb::ATOM_WINDOW, &[parent]
+            );
+        }
+
+        if self.min_size.is_some() || self.max_size.is_some() {
+            // see ICCCM 4.1.2.3 for the layout of WM_SIZE_HINTS
+            const P_MIN_SIZE: u32 = 1 << 4;
+            const P_MAX_SIZE: u32 = 1 << 5;
+
+            let mut hints = [0u32; 18];
+            if let Some((width, height)) = self.min_size {
+                hints[0] |= P_MIN_SIZE;
+                hints[5] = width;
+                hints[6] = height;
+            }
+            if let Some((width, height)) = self.max_size {
+                hints[0] |= P_MAX_SIZE;
+                hints[7] = width;
+                hints[8] = height;
+            }
+            h.set_property_u32(
+                window, xcb::ATOM_WM_NORMAL_HINTS, xcb::ATOM_WM_SIZE_HINTS, &hints
+            );
+        }
+
+        xcb::map_window(&h.conn, window);
+        h.conn.flush();
+
+        window
+    }
+}
@@ -0,0 +1,1248 @@
+//! Event handling tests, run by feeding events to a `WindowManager`
+//! attached to a DummyConn.
+
+mod common;
+
+use common::dummy::{setup, map_normal, map_window, client_message, geom_of, ROOT_GEOM, FULL};
+
+use rcwm::manager::WindowManager;
+use rcwm::x::{DummyConn, XConn, XError, XEvent};
+use rcwm::x::core::{
+    ConfigureRequestData,
+    ClientMessageEvent,
+    ClientMessageData,
+    CirculateEvent,
+    CrossingEvent,
+    DestroyEvent,
+    KeypressEvent,
+    KeySym,
+    MapRequestData,
+    PointerEvent,
+    PropertyEvent,
+    UnmapEvent,
+    XErrorEvent,
+    XWindowID,
+};
+use rcwm::x::dummy::{DummyWindow, Request, DUMMY_ROOT};
+use rcwm::x::consts;
+use rcwm::types::{keysym, Direction, Geometry, Point, SizeHints, Strut, WindowState, WmHints, WMError};
+use rcwm::config;
+
+fn enter_notify(id: XWindowID, mode: u32) -> XEvent {
+    XEvent::EnterNotify(CrossingEvent {
+        window: id,
+        root: DUMMY_ROOT,
+        child: consts::NONE,
+        root_pt: Point {x: 0, y: 0},
+        event_pt: Point {x: 0, y: 0},
+        mode,
+        detail: consts::NOTIFY_DETAIL_NONLINEAR,
+        state: 0,
+        time: 0,
+    })
+}
+
+#[test]
+fn map_request_tiles_normal_window() {
+    let mut wm = setup();
+    let win = map_normal(&mut wm);
+
+    assert!(wm.conn().is_mapped(win));
+    assert!(wm.desktop().current().contains(win).is_some());
+    assert_eq!(geom_of(&wm, win), FULL);
+}
+
+#[test]
+fn map_request_for_dock_is_untracked() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+
+    let dock = wm.conn().create_normal_window();
+    wm.conn().modify_window(dock, |win| {
+        win.window_type = vec![atoms.WM_WINDOW_TYPE_DOCK];
+    });
+    wm.process_event(XEvent::MapRequest(MapRequestData::new(dock, DUMMY_ROOT)));
+    let win = map_normal(&mut wm);
+
+    assert!(wm.conn().is_mapped(dock));
+    assert!(wm.desktop().current().contains(dock).is_none());
+    assert_eq!(geom_of(&wm, win), FULL);
+}
+
+#[test]
+fn unmap_notify_removes_window() {
+    let mut wm = setup();
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(slave)));
+
+    assert!(wm.desktop().current().contains(slave).is_none());
+    assert_eq!(geom_of(&wm, master), FULL);
+}
+
+#[test]
+fn destroy_notify_removes_window() {
+    let mut wm = setup();
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+
+    wm.conn().remove_window(slave);
+    wm.process_event(XEvent::DestroyNotify(DestroyEvent::new(slave)));
+
+    assert!(wm.desktop().client(slave).is_none());
+    assert_eq!(geom_of(&wm, master), FULL);
+}
+
+#[test]
+fn config_request_for_tiled_window_is_denied() {
+    let mut wm = setup();
+    let win = map_normal(&mut wm);
+
+    wm.process_event(XEvent::ConfigureRequest(ConfigureRequestData {
+        id: win,
+        x: Some(10),
+        y: Some(10),
+        width: Some(100),
+        height: Some(100),
+        border_width: None,
+        sibling: None,
+        stack_mode: None,
+        is_root: false,
+    }));
+
+    assert_eq!(geom_of(&wm, win), FULL);
+}
+
+#[test]
+fn enter_notify_focuses_window() {
+    let mut wm = setup();
+    let first = map_normal(&mut wm);
+    let second = map_normal(&mut wm);
+    assert_eq!(wm.conn().focused(), second);
+
+    wm.process_event(enter_notify(first, consts::NOTIFY_MODE_NORMAL));
+    assert_eq!(wm.conn().focused(), first);
+
+    wm.process_event(enter_notify(second, consts::NOTIFY_MODE_GRAB));
+    assert_eq!(wm.conn().focused(), first);
+}
+
+#[test]
+fn run_survives_errors_until_disconnect() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = wm.conn().create_normal_window();
+
+    // a malformed _NET_WM_STATE message should be logged and skipped
+    wm.conn().push_event(XEvent::ClientMessage(ClientMessageEvent {
+        window: win,
+        type_: atoms.WM_STATE,
+        data: ClientMessageData::U8([0; 20]),
+    }));
+    wm.conn().push_event(XEvent::MapRequest(MapRequestData::new(win, DUMMY_ROOT)));
+
+    let result = wm.run();
+
+    assert!(matches!(result, Err(WMError::XError(XError::ConnectionClosed))));
+    assert_eq!(geom_of(&wm, win), FULL);
+}
+
+#[test]
+fn bad_window_error_removes_stale_client() {
+    let mut wm = setup();
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+
+    // the client died without the window manager being notified
+    wm.conn().remove_window(slave);
+    wm.process_event(XEvent::Error(XErrorEvent {
+        code: consts::BAD_WINDOW,
+        sequence: 42,
+        resource: slave,
+        major_opcode: 12,
+        minor_opcode: 0,
+    }));
+
+    assert!(wm.desktop().current().contains(slave).is_none());
+    assert_eq!(geom_of(&wm, master), FULL);
+}
+
+#[test]
+fn other_errors_keep_client() {
+    let mut wm = setup();
+    let win = map_normal(&mut wm);
+
+    wm.process_event(XEvent::Error(XErrorEvent {
+        code: consts::BAD_MATCH,
+        sequence: 42,
+        resource: win,
+        major_opcode: 42,
+        minor_opcode: 0,
+    }));
+
+    assert!(wm.desktop().current().contains(win).is_some());
+}
+
+#[test]
+fn circulate_request_restacks_window() {
+    let mut wm = setup();
+    let bottom = wm.conn().create_normal_window();
+    let top = wm.conn().create_normal_window();
+
+    wm.process_event(XEvent::CirculateRequest(CirculateEvent {
+        id: bottom,
+        event: DUMMY_ROOT,
+        place: consts::PLACE_ON_TOP,
+    }));
+
+    assert_eq!(wm.conn().stacking_order(), vec![top, bottom]);
+}
+
+#[test]
+fn client_list_follows_mapping_order() {
+    let mut wm = setup();
+    let first = map_normal(&mut wm);
+    let second = map_normal(&mut wm);
+    let third = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().client_list(), vec![first, second, third]);
+    assert_eq!(wm.conn().client_list_stacking(), vec![first, second, third]);
+
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(second)));
+
+    assert_eq!(wm.conn().client_list(), vec![first, third]);
+    assert_eq!(wm.conn().client_list_stacking(), vec![first, third]);
+}
+
+#[test]
+fn client_list_stacking_follows_restack() {
+    let mut wm = setup();
+    let first = map_normal(&mut wm);
+    let second = map_normal(&mut wm);
+
+    wm.process_event(XEvent::CirculateRequest(CirculateEvent {
+        id: first,
+        event: DUMMY_ROOT,
+        place: consts::PLACE_ON_TOP,
+    }));
+
+    assert_eq!(wm.conn().client_list(), vec![first, second]);
+    assert_eq!(wm.conn().client_list_stacking(), vec![second, first]);
+
+    wm.process_event(XEvent::CirculateRequest(CirculateEvent {
+        id: first,
+        event: DUMMY_ROOT,
+        place: consts::PLACE_ON_BOTTOM,
+    }));
+
+    assert_eq!(wm.conn().client_list_stacking(), vec![first, second]);
+}
+
+#[test]
+fn desktop_properties_are_published() {
+    let wm = setup();
+
+    assert_eq!(wm.conn().number_of_desktops(), 10);
+    assert_eq!(wm.conn().desktop_names()[..3], ["1", "2", "3"]);
+    assert_eq!(wm.conn().current_desktop(), 0);
+}
+
+#[test]
+fn current_desktop_message_switches_workspace() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [2, 0, 0, 0, 0]));
+
+    assert_eq!(wm.desktop().current_idx(), 2);
+    assert_eq!(wm.conn().current_desktop(), 2);
+    assert!(!wm.conn().is_mapped(win));
+}
+
+#[test]
+fn cycling_workspace_starts_from_current_desktop() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [2, 0, 0, 0, 0]));
+    wm.cycle_workspace(Direction::Forward);
+
+    assert_eq!(wm.desktop().current_idx(), 3);
+    assert_eq!(wm.conn().current_desktop(), 3);
+}
+
+#[test]
+fn wm_desktop_message_moves_window() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().window(slave).unwrap().desktop, Some(0));
+
+    wm.process_event(client_message(slave, atoms.WM_DESKTOP, [3, 1, 0, 0, 0]));
+
+    assert_eq!(wm.desktop().workspace_of(slave), Some(3));
+    assert_eq!(wm.conn().window(slave).unwrap().desktop, Some(3));
+    assert!(!wm.conn().is_mapped(slave));
+    assert_eq!(geom_of(&wm, master), FULL);
+
+    // moving it back shows it again
+    wm.process_event(client_message(slave, atoms.WM_DESKTOP, [0, 1, 0, 0, 0]));
+
+    assert_eq!(wm.desktop().workspace_of(slave), Some(0));
+    assert!(wm.conn().is_mapped(slave));
+}
+
+#[test]
+fn active_window_follows_focus() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().active_window(), first);
+
+    let second = map_normal(&mut wm);
+    wm.process_event(enter_notify(second, consts::NOTIFY_MODE_NORMAL));
+
+    assert_eq!(wm.conn().active_window(), second);
+
+    // nothing is focused on an empty workspace
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [1, 0, 0, 0, 0]));
+
+    assert_eq!(wm.conn().active_window(), consts::NONE);
+}
+
+#[test]
+fn activation_request_switches_workspace() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+    wm.process_event(client_message(win, atoms.WM_DESKTOP, [2, 1, 0, 0, 0]));
+    let other = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().active_window(), other);
+
+    wm.process_event(client_message(
+        win, atoms.ACTIVE_WINDOW, [consts::SOURCE_APPLICATION, 0, 0, 0, 0]
+    ));
+
+    assert_eq!(wm.desktop().current_idx(), 2);
+    assert_eq!(wm.conn().focused(), win);
+    assert_eq!(wm.conn().active_window(), win);
+    assert!(!wm.conn().is_mapped(other));
+}
+
+#[test]
+fn supporting_wm_check_identifies_wm() {
+    let wm = setup();
+    let atoms = wm.conn().get_atoms();
+
+    let check = wm.conn().window(DUMMY_ROOT).unwrap().wm_check
+        .expect("_NET_SUPPORTING_WM_CHECK should be set on the root window");
+    let window = wm.conn().window(check).unwrap();
+
+    assert_eq!(window.wm_check, Some(check));
+    assert_eq!(window.net_name, "rcwm");
+    assert!(!window.mapped);
+
+    let supported = wm.conn().supported();
+    for atom in [
+        atoms.SUPPORTING_WM_CHECK, atoms.WM_NAME, atoms.CLIENT_LIST, atoms.ACTIVE_WINDOW,
+        atoms.WM_WINDOW_TYPE_DOCK, atoms.WM_WINDOW_TYPE_DESKTOP,
+    ] {
+        assert!(supported.contains(&atom));
+    }
+    for atom in [atoms.WM_STATE_SHADED, atoms.WM_STATE_SKIP_TASKBAR, atoms.WM_STATE_SKIP_PAGER] {
+        assert!(!supported.contains(&atom));
+    }
+}
+
+#[test]
+fn dock_struts_shrink_workarea() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().workarea()[0], ROOT_GEOM);
+
+    let dock = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_DOCK],
+        strut: Some(Strut {
+            top: 20,
+            top_start_x: 0,
+            top_end_x: 999,
+            ..Strut::default()
+        }),
+        ..DummyWindow::default()
+    });
+    let workarea = Geometry {x: 0, y: 20, width: 1000, height: 780};
+
+    assert!(wm.conn().is_mapped(dock));
+    assert!(!wm.conn().client_list().contains(&dock));
+    assert_eq!(wm.conn().workarea(), vec![workarea; wm.desktop().workspace_count()]);
+    assert_eq!(geom_of(&wm, win), Geometry {x: 0, y: 20, width: 996, height: 776});
+
+    // a strut that does not overlap the screen reserves nothing
+    wm.conn().modify_window(dock, |win| win.strut = Some(Strut {
+        left: 50,
+        left_start_y: 900,
+        left_end_y: 1000,
+        ..Strut::default()
+    }));
+    wm.process_event(XEvent::PropertyNotify(PropertyEvent {
+        id: dock,
+        atom: wm.conn().get_atoms().WM_STRUT_PARTIAL,
+        time: 0,
+    }));
+
+    assert_eq!(wm.conn().workarea()[0], ROOT_GEOM);
+    assert_eq!(geom_of(&wm, win), FULL);
+}
+
+#[test]
+fn unmapped_dock_releases_space() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dock = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_DOCK],
+        strut: Some(Strut::from_edges(0, 0, 0, 30)),
+        ..DummyWindow::default()
+    });
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+
+    assert_eq!(wm.conn().workarea()[0], Geometry {x: 0, y: 0, width: 1000, height: 770});
+    assert_eq!(geom_of(&wm, master).height, 766);
+
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(dock)));
+
+    assert_eq!(wm.conn().workarea()[0], ROOT_GEOM);
+    assert_eq!(geom_of(&wm, master).height, 796);
+    assert_eq!(geom_of(&wm, slave).height, 796);
+}
+
+fn pointer_event(pt: Point, button: u32) -> PointerEvent {
+    PointerEvent {
+        window: DUMMY_ROOT,
+        root: DUMMY_ROOT,
+        child: consts::NONE,
+        root_pt: pt,
+        event_pt: pt,
+        button,
+        state: 0,
+        time: 0,
+    }
+}
+
+#[test]
+fn close_window_message_closes_client() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let polite = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW],
+        ..DummyWindow::default()
+    });
+    let plain = map_normal(&mut wm);
+    wm.conn().clear_requests();
+
+    wm.process_event(client_message(polite, atoms.CLOSE_WINDOW, [0, consts::SOURCE_PAGER, 0, 0, 0]));
+
+    assert!(wm.conn().window(polite).is_some());
+    assert!(wm.conn().requests().iter().any(|req| matches!(
+        req, Request::SendEvent(event) if event.window == polite && event.type_ == atoms.WM_PROTOCOLS
+    )));
+
+    wm.process_event(client_message(plain, atoms.CLOSE_WINDOW, [0, consts::SOURCE_PAGER, 0, 0, 0]));
+
+    assert!(wm.conn().window(plain).is_none());
+}
+
+#[test]
+fn moveresize_window_message_configures_floating_window() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+    let tiled = map_normal(&mut wm);
+
+    let flags = consts::NET_MOVERESIZE_WINDOW_X | consts::NET_MOVERESIZE_WINDOW_HEIGHT;
+    wm.process_event(client_message(dialog, atoms.MOVERESIZE_WINDOW, [flags, 50, 0, 0, 250]));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 50, y: 100, width: 300, height: 250});
+
+    let flags = consts::NET_MOVERESIZE_WINDOW_X | consts::NET_MOVERESIZE_WINDOW_Y;
+    wm.process_event(client_message(tiled, atoms.MOVERESIZE_WINDOW, [flags, 50, 50, 0, 0]));
+
+    assert_eq!(geom_of(&wm, tiled), FULL);
+}
+
+#[test]
+fn wm_moveresize_drags_window_until_release() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [110, 110, consts::NET_WM_MOVERESIZE_MOVE, 1, 1]
+    ));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 130, y: 150}, 0)));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 120, y: 140, width: 300, height: 200});
+    assert!(wm.conn().requests().contains(&Request::GrabPointer(DUMMY_ROOT)));
+
+    wm.process_event(XEvent::ButtonRelease(pointer_event(Point {x: 130, y: 150}, 1)));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 300, y: 300}, 0)));
+
+    assert!(wm.conn().requests().contains(&Request::UngrabPointer));
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 120, y: 140, width: 300, height: 200});
+}
+
+#[test]
+fn keyboard_resize_follows_pointer_until_click() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+    wm.conn().set_pointer(Point {x: 200, y: 200});
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [0, 0, consts::NET_WM_MOVERESIZE_SIZE_KEYBOARD, 0, 1]
+    ));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 220, y: 230}, 0)));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 100, width: 320, height: 230});
+
+    wm.process_event(XEvent::ButtonPress(pointer_event(Point {x: 220, y: 230}, 1)));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 300, y: 300}, 0)));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 100, width: 320, height: 230});
+}
+
+#[test]
+fn wm_moveresize_top_left_keeps_opposite_corner() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [110, 110, consts::NET_WM_MOVERESIZE_SIZE_TOPLEFT, 1, 1]
+    ));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 130, y: 150}, 0)));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 120, y: 140, width: 280, height: 160});
+}
+
+#[test]
+fn wm_moveresize_top_edge_keeps_width() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [200, 100, consts::NET_WM_MOVERESIZE_SIZE_TOP, 1, 1]
+    ));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 230, y: 60}, 0)));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 60, width: 300, height: 240});
+}
+
+fn key_press(keysym: KeySym) -> XEvent {
+    XEvent::KeyPress(KeypressEvent {
+        mask: 0,
+        keysym,
+        keycode: 0,
+        window: DUMMY_ROOT,
+        root: DUMMY_ROOT,
+        child: consts::NONE,
+        time: 0,
+    })
+}
+
+#[test]
+fn keyboard_resize_follows_arrow_keys_until_return() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [0, 0, consts::NET_WM_MOVERESIZE_SIZE_KEYBOARD, 0, 1]
+    ));
+    assert!(wm.conn().requests().contains(&Request::GrabKeyboard(DUMMY_ROOT)));
+
+    wm.process_event(key_press(keysym::XK_Right));
+    wm.process_event(key_press(keysym::XK_Right));
+    wm.process_event(key_press(keysym::XK_Down));
+    wm.process_event(key_press(keysym::XK_Return));
+    wm.process_event(key_press(keysym::XK_Left));
+
+    let step = config::KEYBOARD_DRAG_STEP;
+    assert_eq!(
+        geom_of(&wm, dialog),
+        Geometry {x: 100, y: 100, width: 300 + 2 * step, height: 200 + step}
+    );
+    assert!(wm.conn().requests().contains(&Request::UngrabKeyboard));
+}
+
+#[test]
+fn keyboard_move_is_undone_by_escape() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let geom = Geometry {x: 100, y: 100, width: 300, height: 200};
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom,
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [0, 0, consts::NET_WM_MOVERESIZE_MOVE_KEYBOARD, 0, 1]
+    ));
+    wm.process_event(key_press(keysym::XK_Left));
+    wm.process_event(key_press(keysym::XK_Up));
+
+    let step = config::KEYBOARD_DRAG_STEP;
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100 - step, y: 100 - step, ..geom});
+
+    wm.process_event(key_press(keysym::XK_Escape));
+
+    assert_eq!(geom_of(&wm, dialog), geom);
+    assert!(wm.conn().requests().contains(&Request::UngrabKeyboard));
+    assert!(wm.conn().requests().contains(&Request::UngrabPointer));
+}
+
+#[test]
+fn wm_moveresize_cancel_ends_drag() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [110, 110, consts::NET_WM_MOVERESIZE_MOVE, 1, 1]
+    ));
+    wm.process_event(client_message(
+        dialog, atoms.WM_MOVERESIZE, [0, 0, consts::NET_WM_MOVERESIZE_CANCEL, 0, 1]
+    ));
+    wm.process_event(XEvent::MotionNotify(pointer_event(Point {x: 130, y: 150}, 0)));
+
+    assert!(wm.conn().requests().contains(&Request::UngrabPointer));
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 100, width: 300, height: 200});
+}
+
+/// An event that only moves the server time forward.
+fn time_passes(time: u32) -> XEvent {
+    XEvent::MotionNotify(PointerEvent {time, ..pointer_event(Point {x: 0, y: 0}, 0)})
+}
+
+#[test]
+fn closing_client_pings_it() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_PING],
+        ..DummyWindow::default()
+    });
+    wm.process_event(time_passes(1000));
+
+    wm.process_event(client_message(win, atoms.CLOSE_WINDOW, [0, consts::SOURCE_PAGER, 0, 0, 0]));
+
+    assert!(wm.conn().requests().contains(&Request::SendEvent(ClientMessageEvent {
+        window: win,
+        type_: atoms.WM_PROTOCOLS,
+        data: ClientMessageData::U32([atoms.WM_PING, 1000, win, 0, 0]),
+    })));
+
+    // the client sends the ping back to the root window
+    wm.process_event(client_message(DUMMY_ROOT, atoms.WM_PROTOCOLS, [atoms.WM_PING, 1000, win, 0, 0]));
+    wm.process_event(time_passes(1000 + 2 * config::PING_TIMEOUT));
+
+    assert!(wm.desktop().client(win).unwrap().is_responding());
+}
+
+#[test]
+fn answer_to_another_ping_is_ignored() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_PING],
+        ..DummyWindow::default()
+    });
+    wm.process_event(time_passes(1000));
+    wm.ping_client(win).unwrap();
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.WM_PROTOCOLS, [atoms.WM_PING, 500, win, 0, 0]));
+    wm.process_event(time_passes(1000 + config::PING_TIMEOUT + 1));
+
+    assert!(!wm.desktop().client(win).unwrap().is_responding());
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.WM_PROTOCOLS, [atoms.WM_PING, 1000, win, 0, 0]));
+
+    assert!(wm.desktop().client(win).unwrap().is_responding());
+}
+
+#[test]
+fn unanswered_ping_lets_close_kill_client() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_PING],
+        ..DummyWindow::default()
+    });
+
+    wm.ping_client(win).unwrap();
+    wm.process_event(time_passes(config::PING_TIMEOUT / 2));
+
+    assert!(wm.desktop().client(win).unwrap().is_responding());
+
+    wm.process_event(time_passes(config::PING_TIMEOUT + 1));
+
+    assert!(!wm.desktop().client(win).unwrap().is_responding());
+
+    wm.process_event(client_message(win, atoms.CLOSE_WINDOW, [0, consts::SOURCE_PAGER, 0, 0, 0]));
+
+    assert!(wm.conn().requests().contains(&Request::KillClient(win)));
+    assert!(wm.conn().window(win).is_none());
+}
+
+#[test]
+fn closing_frozen_client_again_kills_it() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_PING],
+        ..DummyWindow::default()
+    });
+    let close = |time| XEvent::KeyPress(KeypressEvent {
+        mask: config::MODKEY,
+        keysym: keysym::XK_w,
+        keycode: 0,
+        window: DUMMY_ROOT,
+        root: DUMMY_ROOT,
+        child: consts::NONE,
+        time,
+    });
+
+    wm.process_event(close(1000));
+    assert!(wm.conn().window(win).is_some());
+
+    // no event reaches the window manager while the client is frozen
+    wm.process_event(close(1000 + config::PING_TIMEOUT + 1));
+
+    assert!(wm.conn().requests().contains(&Request::KillClient(win)));
+    assert!(wm.conn().window(win).is_none());
+}
+
+#[test]
+fn synced_resize_waits_for_client() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let counter = 500;
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        protocols: vec![atoms.WM_SYNC_REQUEST],
+        sync_counter: Some(counter),
+        ..DummyWindow::default()
+    });
+    wm.conn().set_sync_counter(counter, 0);
+    let motion = |x, y| XEvent::MotionNotify(pointer_event(Point {x, y}, 0));
+    let size = |wm: &WindowManager<DummyConn>| {
+        let geom = geom_of(wm, dialog);
+        (geom.width, geom.height)
+    };
+
+    wm.process_event(XEvent::ButtonPress(PointerEvent {
+        child: dialog,
+        ..pointer_event(Point {x: 200, y: 200}, consts::BUTTON_INDEX_3)
+    }));
+    wm.process_event(motion(210, 210));
+
+    assert_eq!(size(&wm), (310, 210));
+    assert!(wm.conn().requests().contains(&Request::SendEvent(ClientMessageEvent {
+        window: dialog,
+        type_: atoms.WM_PROTOCOLS,
+        data: ClientMessageData::U32([atoms.WM_SYNC_REQUEST, 0, 1, 0, 0]),
+    })));
+
+    // the client has not handled the first configure yet
+    wm.process_event(motion(220, 220));
+
+    assert_eq!(size(&wm), (310, 210));
+
+    // the held back configure is sent once the client catches up,
+    // without waiting for the pointer to move again
+    wm.conn().set_sync_counter(counter, 1);
+    let alarm = wm.conn().next_event().unwrap();
+    assert!(matches!(alarm, XEvent::SyncAlarmNotify(_)));
+    wm.process_event(alarm);
+
+    assert_eq!(size(&wm), (320, 220));
+    // the counter is only queried once, when the resize begins
+    let queries = wm.conn().requests().iter()
+        .filter(|r| matches!(r, Request::QuerySyncCounter(_)))
+        .count();
+    assert_eq!(queries, 1);
+
+    wm.conn().set_sync_counter(counter, 2);
+    let alarm = wm.conn().next_event().unwrap();
+    wm.process_event(alarm);
+    wm.process_event(motion(230, 230));
+
+    assert_eq!(size(&wm), (330, 230));
+
+    // the final size is sent on release, even if the client is behind
+    wm.process_event(motion(240, 250));
+    assert_eq!(size(&wm), (330, 230));
+    wm.process_event(XEvent::ButtonRelease(pointer_event(Point {x: 240, y: 250}, consts::BUTTON_INDEX_3)));
+
+    assert_eq!(size(&wm), (340, 250));
+}
+
+/// A click on the root window, standing in for any user input.
+fn user_input(time: u32) -> XEvent {
+    XEvent::ButtonPress(PointerEvent {time, ..pointer_event(Point {x: 0, y: 0}, 1)})
+}
+
+#[test]
+fn window_mapped_after_input_elsewhere_is_marked_urgent() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+
+    wm.process_event(user_input(100));
+    let second = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        user_time: Some(50),
+        ..DummyWindow::default()
+    });
+
+    assert!(wm.conn().is_mapped(second));
+    assert_eq!(wm.conn().active_window(), first);
+    assert!(wm.conn().window(second).unwrap().net_states.contains(&atoms.WM_STATE_DEMANDS_ATTENTION));
+}
+
+#[test]
+fn window_with_newer_user_time_takes_focus() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    map_normal(&mut wm);
+
+    wm.process_event(user_input(100));
+    let second = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        user_time: Some(200),
+        ..DummyWindow::default()
+    });
+
+    assert_eq!(wm.conn().active_window(), second);
+    assert!(!wm.conn().window(second).unwrap().net_states.contains(&atoms.WM_STATE_DEMANDS_ATTENTION));
+}
+
+#[test]
+fn window_with_zero_user_time_is_not_focused() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+    map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        user_time: Some(0),
+        ..DummyWindow::default()
+    });
+
+    assert_eq!(wm.conn().active_window(), first);
+}
+
+#[test]
+fn icons_are_read_on_map_and_property_change() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let id = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        icon: vec![1, 1, 0xffff0000, 2, 2, 1, 2, 3, 4],
+        ..DummyWindow::default()
+    });
+
+    let client = wm.desktop().client(id).unwrap();
+    assert_eq!(client.icons.len(), 2);
+    assert_eq!(client.icon(2).unwrap().data, vec![1, 2, 3, 4]);
+    assert_eq!(client.icon(32).unwrap().width, 2);
+
+    // a truncated icon is dropped
+    wm.conn().modify_window(id, |win| win.icon = vec![1, 1, 0xff00ff00, 4, 4, 0]);
+    wm.process_event(XEvent::PropertyNotify(PropertyEvent {
+        id,
+        atom: atoms.WM_ICON,
+        time: 0,
+    }));
+
+    let client = wm.desktop().client(id).unwrap();
+    assert_eq!(client.icons.len(), 1);
+    assert_eq!(client.icon(1).unwrap().data, vec![0xff00ff00]);
+}
+
+#[test]
+fn desktop_window_covers_screen_below_clients() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+    let desktop = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 10, y: 10, width: 100, height: 100},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DESKTOP],
+        ..DummyWindow::default()
+    });
+
+    assert!(wm.conn().is_mapped(desktop));
+    assert!(wm.desktop().client(desktop).is_none());
+    assert_eq!(geom_of(&wm, desktop), ROOT_GEOM);
+    assert_eq!(wm.conn().stacking_order().first(), Some(&desktop));
+    assert_eq!(wm.conn().active_window(), win);
+
+    // it cannot be raised above the clients
+    wm.process_event(XEvent::CirculateRequest(CirculateEvent {
+        id: desktop,
+        event: DUMMY_ROOT,
+        place: consts::PLACE_ON_TOP,
+    }));
+
+    assert_eq!(wm.conn().stacking_order().first(), Some(&desktop));
+}
+
+#[test]
+fn showing_desktop_hides_and_restores_clients() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+    let second = map_normal(&mut wm);
+    wm.process_event(enter_notify(first, consts::NOTIFY_MODE_NORMAL));
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.SHOWING_DESKTOP, [1, 0, 0, 0, 0]));
+
+    assert!(wm.conn().showing_desktop());
+    assert!(!wm.conn().is_mapped(first));
+    assert!(!wm.conn().is_mapped(second));
+    assert_eq!(wm.conn().active_window(), consts::NONE);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.SHOWING_DESKTOP, [0, 0, 0, 0, 0]));
+
+    assert!(!wm.conn().showing_desktop());
+    assert!(wm.conn().is_mapped(first));
+    assert!(wm.conn().is_mapped(second));
+    assert_eq!(wm.conn().active_window(), first);
+}
+
+#[test]
+fn mapping_window_stops_showing_desktop() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.SHOWING_DESKTOP, [1, 0, 0, 0, 0]));
+    let second = map_normal(&mut wm);
+
+    assert!(!wm.conn().showing_desktop());
+    assert!(wm.conn().is_mapped(first));
+    assert_eq!(wm.conn().active_window(), second);
+}
+
+fn wm_state_of(wm: &WindowManager<DummyConn>, id: XWindowID) -> WindowState {
+    wm.conn().window(id).expect("window should exist").wm_state
+}
+
+#[test]
+fn client_unmap_withdraws_window() {
+    let mut wm = setup();
+    let win = map_normal(&mut wm);
+
+    assert_eq!(wm_state_of(&wm, win), WindowState::Normal);
+
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(win)));
+
+    assert!(wm.desktop().client(win).is_none());
+    assert_eq!(wm_state_of(&wm, win), WindowState::Withdrawn);
+}
+
+#[test]
+fn workspace_switch_unmap_is_not_withdrawal() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [1, 0, 0, 0, 0]));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(win)));
+
+    assert!(wm.desktop().client(win).is_some());
+    assert_eq!(wm_state_of(&wm, win), WindowState::Iconic);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [0, 0, 0, 0, 0]));
+
+    assert!(wm.conn().is_mapped(win));
+    assert_eq!(wm_state_of(&wm, win), WindowState::Normal);
+}
+
+#[test]
+fn sent_window_unmap_is_not_withdrawal() {
+    let mut wm = setup();
+    let win = map_normal(&mut wm);
+
+    wm.send_window_to(1);
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(win)));
+
+    assert!(wm.desktop().client(win).is_some());
+    assert_eq!(wm_state_of(&wm, win), WindowState::Iconic);
+
+    wm.goto_workspace(1);
+
+    assert!(wm.conn().is_mapped(win));
+    assert_eq!(wm_state_of(&wm, win), WindowState::Normal);
+}
+
+#[test]
+fn synthetic_unmap_withdraws_iconic_window() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [1, 0, 0, 0, 0]));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(win)));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent {
+        id: win,
+        event: DUMMY_ROOT,
+        synthetic: true,
+    }));
+
+    assert!(wm.desktop().client(win).is_none());
+    assert_eq!(wm_state_of(&wm, win), WindowState::Withdrawn);
+}
+
+fn enter_notify_at(id: XWindowID, time: u32) -> XEvent {
+    match enter_notify(id, consts::NOTIFY_MODE_NORMAL) {
+        XEvent::EnterNotify(event) => XEvent::EnterNotify(CrossingEvent {time, ..event}),
+        _ => unreachable!(),
+    }
+}
+
+fn take_focus(wm: &WindowManager<DummyConn>, window: XWindowID, time: u32) -> Request {
+    let atoms = wm.conn().get_atoms();
+    Request::SendEvent(ClientMessageEvent {
+        window,
+        type_: atoms.WM_PROTOCOLS,
+        data: ClientMessageData::U32([atoms.WM_TAKE_FOCUS, time, 0, 0, 0]),
+    })
+}
+
+#[test]
+fn globally_active_window_is_sent_take_focus() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_TAKE_FOCUS],
+        hints: Some(WmHints {state: WindowState::Normal, urgent: false, input: false}),
+        ..DummyWindow::default()
+    });
+    let other = map_normal(&mut wm);
+
+    wm.process_event(enter_notify_at(win, 500));
+
+    assert!(wm.conn().requests().contains(&take_focus(&wm, win, 500)));
+    assert!(!wm.conn().requests().contains(&Request::SetInputFocus(win)));
+    // the client sets the focus itself
+    assert_eq!(wm.conn().focused(), other);
+    assert_eq!(wm.conn().active_window(), win);
+}
+
+#[test]
+fn locally_active_window_is_focused_and_sent_take_focus() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_TAKE_FOCUS],
+        hints: Some(WmHints {state: WindowState::Normal, urgent: false, input: true}),
+        ..DummyWindow::default()
+    });
+    map_normal(&mut wm);
+
+    wm.process_event(enter_notify_at(win, 700));
+
+    assert_eq!(wm.conn().focused(), win);
+    assert!(wm.conn().requests().contains(&take_focus(&wm, win, 700)));
+}
+
+#[test]
+fn focus_before_any_input_uses_server_time() {
+    let conn = DummyConn::new(ROOT_GEOM);
+    conn.set_server_time(300);
+    let mut wm = WindowManager::register(conn, 0)
+        .expect("Failed to register window manager");
+    let atoms = wm.conn().get_atoms();
+
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        protocols: vec![atoms.WM_DELETE_WINDOW, atoms.WM_TAKE_FOCUS],
+        hints: Some(WmHints {state: WindowState::Normal, urgent: false, input: true}),
+        ..DummyWindow::default()
+    });
+
+    assert_eq!(wm.conn().focused(), win);
+    assert!(wm.conn().requests().contains(&take_focus(&wm, win, 300)));
+}
+
+const NO_SIZE_HINTS: SizeHints = SizeHints {
+    position: None,
+    size: None,
+    min_size: None,
+    max_size: None,
+    resize: None,
+    min_aspect: None,
+    max_aspect: None,
+    base: None,
+    gravity: None,
+};
+
+#[test]
+fn fixed_size_window_floats() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let geom = Geometry {x: 100, y: 100, width: 300, height: 200};
+    let win = map_window(&mut wm, DummyWindow {
+        geom,
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        size_hints: Some(SizeHints {
+            min_size: Some((300, 200)),
+            max_size: Some((300, 200)),
+            ..NO_SIZE_HINTS
+        }),
+        ..DummyWindow::default()
+    });
+
+    assert!(wm.desktop().client(win).unwrap().is_floating());
+    assert_eq!(geom_of(&wm, win), geom);
+}
+
+#[test]
+fn aspect_ratio_leaves_out_base_size() {
+    let hints = SizeHints {
+        base: Some((100, 100)),
+        min_size: Some((100, 100)),
+        min_aspect: Some((2, 1)),
+        max_aspect: Some((2, 1)),
+        ..NO_SIZE_HINTS
+    };
+
+    // 200x100 above the base size is already 2:1
+    assert_eq!(hints.constrain(300, 200), (300, 200));
+
+    // the minimum size only stands in for the base size in increments
+    let hints = SizeHints {base: None, ..hints};
+
+    assert_eq!(hints.constrain(300, 200), (300, 150));
+}
+
+#[test]
+fn partial_configure_request_keeps_other_fields() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 100, y: 100, width: 300, height: 200},
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+    let untracked = wm.conn().create_window(DummyWindow {
+        geom: Geometry {x: 50, y: 60, width: 70, height: 80},
+        override_redirect: true,
+        ..DummyWindow::default()
+    });
+    let resize = |id| XEvent::ConfigureRequest(ConfigureRequestData {
+        id,
+        x: None,
+        y: None,
+        width: Some(400),
+        height: None,
+        border_width: None,
+        sibling: None,
+        stack_mode: None,
+        is_root: false,
+    });
+
+    wm.process_event(resize(dialog));
+    wm.process_event(resize(untracked));
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 100, width: 400, height: 200});
+    assert_eq!(geom_of(&wm, untracked), Geometry {x: 50, y: 60, width: 400, height: 80});
+}
+
+#[test]
+fn configure_request_follows_size_hints_and_gravity() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_window(&mut wm, DummyWindow {
+        geom: Geometry {x: 0, y: 0, width: 100, height: 100},
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        size_hints: Some(SizeHints {
+            min_size: Some((300, 200)),
+            max_size: Some((300, 200)),
+            ..NO_SIZE_HINTS
+        }),
+        ..DummyWindow::default()
+    });
+    // let the window be resized again, in steps
+    wm.conn().modify_window(win, |win| win.size_hints = Some(SizeHints {
+        resize: Some((10, 20)),
+        gravity: Some(consts::GRAVITY_SOUTH_EAST),
+        ..NO_SIZE_HINTS
+    }));
+    wm.process_event(XEvent::PropertyNotify(PropertyEvent {
+        id: win,
+        atom: consts::ATOM_WM_NORMAL_HINTS,
+        time: 0,
+    }));
+
+    wm.process_event(XEvent::ConfigureRequest(ConfigureRequestData {
+        id: win,
+        x: Some(0),
+        y: Some(0),
+        width: Some(105),
+        height: Some(107),
+        border_width: None,
+        sibling: None,
+        stack_mode: None,
+        is_root: false,
+    }));
+
+    // the bottom right corner stays where the client asked for it
+    assert_eq!(geom_of(&wm, win), Geometry {x: 5, y: 7, width: 100, height: 100});
+}
@@ -0,0 +1,83 @@
+//! Record and replay tests, run by recording a session against a
+//! DummyConn and replaying it without one.
+
+use std::path::PathBuf;
+
+use rcwm::manager::WindowManager;
+use rcwm::x::{DummyConn, RecordingConn, ReplayConn, XError, XEvent};
+use rcwm::x::consts;
+use rcwm::x::core::{CrossingEvent, MapRequestData, UnmapEvent};
+use rcwm::x::dummy::{Request, DUMMY_ROOT};
+use rcwm::types::{Geometry, Point, WMError};
+
+const ROOT_GEOM: Geometry = Geometry {x: 0, y: 0, width: 1000, height: 800};
+
+fn recording_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rcwm-{}-{}.json", name, std::process::id()))
+}
+
+/// Whether a request changes the state of the X server.
+fn is_write(request: &Request) -> bool {
+    !matches!(request,
+        Request::QueryTree(_) |
+        Request::QueryPointer(_) |
+        Request::GetGeometry(_) |
+        Request::GetWindowAttributes(_) |
+        Request::GetProperty(..)
+    )
+}
+
+/// Records a session with two windows, one of which is unmapped again,
+/// and returns the write requests made during it.
+fn record_session(path: &PathBuf) -> Vec<Request> {
+    let conn = DummyConn::new(ROOT_GEOM);
+    let first = conn.create_normal_window();
+    let second = conn.create_normal_window();
+
+    conn.push_event(XEvent::MapRequest(MapRequestData::new(first, DUMMY_ROOT)));
+    conn.push_event(XEvent::MapRequest(MapRequestData::new(second, DUMMY_ROOT)));
+    conn.push_event(XEvent::EnterNotify(CrossingEvent {
+        window: first,
+        root: DUMMY_ROOT,
+        child: consts::NONE,
+        root_pt: Point {x: 10, y: 10},
+        event_pt: Point {x: 10, y: 10},
+        mode: consts::NOTIFY_MODE_NORMAL,
+        detail: consts::NOTIFY_DETAIL_NONLINEAR,
+        state: 0,
+        time: 1000,
+    }));
+    conn.push_event(XEvent::UnmapNotify(UnmapEvent::new(second)));
+
+    let conn = RecordingConn::create(conn, path).unwrap();
+    let mut wm = WindowManager::register(conn, 0).unwrap();
+    let result = wm.run();
+
+    assert!(matches!(result, Err(WMError::XError(XError::ConnectionClosed))));
+    wm.conn().inner().requests().into_iter().filter(is_write).collect()
+}
+
+#[test]
+fn replay_reproduces_requests() {
+    let path = recording_path("requests");
+    let recorded = record_session(&path);
+
+    let conn = ReplayConn::open(&path).unwrap();
+    let mut wm = WindowManager::register(conn, 0).unwrap();
+    let result = wm.run();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(WMError::XError(XError::ConnectionClosed))));
+    assert_eq!(wm.conn().remaining_events(), 0);
+    assert_eq!(wm.conn().requests(), recorded);
+}
+
+#[test]
+fn replay_rejects_missing_setup() {
+    let recording = "{\"Event\":{\"MapRequest\":{\"id\":2,\"parent\":1,\"override_redirect\":false}}}\n";
+
+    assert!(matches!(
+        ReplayConn::from_reader(recording.as_bytes()),
+        Err(XError::Recording(_))
+    ));
+}
@@ -0,0 +1,307 @@
+//! _NET_WM_STATE tests, run by sending state requests to a `WindowManager`
+//! attached to a DummyConn.
+
+mod common;
+
+use common::dummy::{setup, map_normal, map_window, client_message, geom_of, ROOT_GEOM, FULL};
+
+use rcwm::manager::WindowManager;
+use rcwm::x::{DummyConn, XConn, XEvent};
+use rcwm::x::core::{MapRequestData, UnmapEvent, XWindowID};
+use rcwm::x::dummy::{DummyWindow, Request, DUMMY_ROOT};
+use rcwm::x::consts;
+use rcwm::layout::LayoutType;
+use rcwm::types::{Direction, Geometry, NetWindowState};
+use rcwm::config::{BORDER_URGENT, BORDER_WIDTH};
+use rcwm::utils;
+
+/// A _NET_WM_STATE request for up to two states.
+fn state_request(
+    wm: &WindowManager<DummyConn>, window: XWindowID, action: u32, states: [u32; 2]
+) -> XEvent {
+    let atoms = wm.conn().get_atoms();
+    client_message(window, atoms.WM_STATE, [action, states[0], states[1], 1, 0])
+}
+
+fn has_state(wm: &WindowManager<DummyConn>, id: XWindowID, state: NetWindowState) -> bool {
+    wm.desktop().client(id).expect("window should be managed").has_state(state)
+}
+
+#[test]
+fn fullscreen_covers_screen_and_restores() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let master = map_normal(&mut wm);
+    let slave = map_normal(&mut wm);
+    let tiled = geom_of(&wm, slave);
+
+    let request = state_request(&wm, slave, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_FULLSCREEN, 0]);
+    wm.process_event(request);
+
+    assert_eq!(geom_of(&wm, slave), ROOT_GEOM);
+    assert_eq!(wm.conn().window(slave).unwrap().border_width, 0);
+    assert_eq!(wm.conn().window(slave).unwrap().net_states, vec![atoms.WM_STATE_FULLSCREEN]);
+    assert_eq!(wm.conn().stacking_order().last(), Some(&slave));
+    // the remaining window takes up the tiling
+    assert_eq!(geom_of(&wm, master), FULL);
+
+    let request = state_request(&wm, slave, consts::NET_WM_STATE_REMOVE, [atoms.WM_STATE_FULLSCREEN, 0]);
+    wm.process_event(request);
+
+    assert_eq!(geom_of(&wm, slave), tiled);
+    assert_eq!(wm.conn().window(slave).unwrap().border_width, BORDER_WIDTH);
+    assert!(wm.conn().window(slave).unwrap().net_states.is_empty());
+    assert!(wm.desktop().client(slave).unwrap().is_tiled());
+}
+
+#[test]
+fn toggle_flips_state() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    let toggle = state_request(&wm, win, consts::NET_WM_STATE_TOGGLE, [atoms.WM_STATE_FULLSCREEN, 0]);
+    wm.process_event(toggle);
+
+    assert!(has_state(&wm, win, NetWindowState::Fullscreen));
+
+    wm.process_event(toggle);
+
+    assert!(!has_state(&wm, win, NetWindowState::Fullscreen));
+    assert_eq!(geom_of(&wm, win), FULL);
+
+    // keybinds toggle the focused window
+    wm.toggle_focused(NetWindowState::Above);
+
+    assert!(has_state(&wm, win, NetWindowState::Above));
+}
+
+#[test]
+fn two_states_change_together() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+
+    let request = state_request(
+        &wm, win, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_SKIP_TASKBAR, atoms.WM_STATE_SKIP_PAGER]
+    );
+    wm.process_event(request);
+
+    assert_eq!(
+        wm.conn().window(win).unwrap().net_states,
+        vec![atoms.WM_STATE_SKIP_TASKBAR, atoms.WM_STATE_SKIP_PAGER]
+    );
+}
+
+#[test]
+fn maximize_stretches_floating_window() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let geom = Geometry {x: 100, y: 100, width: 300, height: 200};
+    let dialog = map_window(&mut wm, DummyWindow {
+        geom,
+        window_type: vec![atoms.WM_WINDOW_TYPE_DIALOG],
+        ..DummyWindow::default()
+    });
+
+    let request = state_request(&wm, dialog, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_MAXIMIZED_VERT, 0]);
+    wm.process_event(request);
+
+    assert_eq!(geom_of(&wm, dialog), Geometry {x: 100, y: 0, width: 300, height: 796});
+
+    let request = state_request(&wm, dialog, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_MAXIMIZED_HORZ, 0]);
+    wm.process_event(request);
+
+    assert_eq!(geom_of(&wm, dialog), FULL);
+
+    let request = state_request(
+        &wm, dialog, consts::NET_WM_STATE_REMOVE, [atoms.WM_STATE_MAXIMIZED_VERT, atoms.WM_STATE_MAXIMIZED_HORZ]
+    );
+    wm.process_event(request);
+
+    assert_eq!(geom_of(&wm, dialog), geom);
+}
+
+#[test]
+fn above_stays_on_top() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let above = map_normal(&mut wm);
+    let below = map_normal(&mut wm);
+
+    wm.process_event(state_request(&wm, above, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_ABOVE, 0]));
+    wm.process_event(state_request(&wm, below, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_BELOW, 0]));
+    let other = map_normal(&mut wm);
+
+    let stacking = wm.conn().stacking_order();
+    let pos = |id| stacking.iter().position(|win| *win == id).unwrap();
+    assert!(pos(below) < pos(other));
+    assert!(pos(other) < pos(above));
+    assert_eq!(wm.conn().client_list_stacking(), vec![below, other, above]);
+
+    // above and below exclude each other
+    wm.process_event(state_request(&wm, below, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_ABOVE, 0]));
+
+    assert!(!has_state(&wm, below, NetWindowState::Below));
+    // windows keep their order within a layer
+    assert_eq!(wm.conn().client_list_stacking(), vec![other, below, above]);
+}
+
+#[test]
+fn sticky_window_follows_workspace_switch() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let sticky = map_normal(&mut wm);
+    let other = map_normal(&mut wm);
+
+    wm.process_event(client_message(sticky, atoms.WM_DESKTOP, [consts::ALL_DESKTOPS, 1, 0, 0, 0]));
+
+    assert!(has_state(&wm, sticky, NetWindowState::Sticky));
+    assert_eq!(wm.conn().window(sticky).unwrap().desktop, Some(consts::ALL_DESKTOPS));
+
+    wm.process_event(client_message(DUMMY_ROOT, atoms.CURRENT_DESKTOP, [3, 0, 0, 0, 0]));
+
+    assert_eq!(wm.desktop().workspace_of(sticky), Some(3));
+    assert!(wm.conn().is_mapped(sticky));
+    assert!(!wm.conn().is_mapped(other));
+    assert_eq!(geom_of(&wm, sticky), FULL);
+
+    // sending it to a workspace makes it stay there
+    wm.process_event(client_message(sticky, atoms.WM_DESKTOP, [3, 1, 0, 0, 0]));
+
+    assert!(!has_state(&wm, sticky, NetWindowState::Sticky));
+    assert_eq!(wm.conn().window(sticky).unwrap().desktop, Some(3));
+}
+
+#[test]
+fn hidden_window_is_unmapped_until_activated() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let shown = map_normal(&mut wm);
+    let hidden = map_normal(&mut wm);
+
+    wm.process_event(state_request(&wm, hidden, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_HIDDEN, 0]));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(hidden)));
+
+    assert!(!wm.conn().is_mapped(hidden));
+    assert!(wm.conn().client_list().contains(&hidden));
+    assert_eq!(geom_of(&wm, shown), FULL);
+    assert_eq!(wm.conn().focused(), shown);
+
+    wm.process_event(client_message(
+        hidden, atoms.ACTIVE_WINDOW, [consts::SOURCE_PAGER, 0, 0, 0, 0]
+    ));
+
+    assert!(wm.conn().is_mapped(hidden));
+    assert!(!has_state(&wm, hidden, NetWindowState::Hidden));
+    assert!(wm.desktop().client(hidden).unwrap().is_tiled());
+    assert_eq!(wm.conn().focused(), hidden);
+}
+
+#[test]
+fn map_request_unhides_window() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let hidden = map_normal(&mut wm);
+
+    wm.process_event(state_request(&wm, hidden, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_HIDDEN, 0]));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(hidden)));
+    wm.process_event(XEvent::MapRequest(MapRequestData::new(hidden, DUMMY_ROOT)));
+
+    assert!(wm.conn().is_mapped(hidden));
+    assert!(!has_state(&wm, hidden, NetWindowState::Hidden));
+}
+
+#[test]
+fn cycling_focus_skips_hidden_windows() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let first = map_normal(&mut wm);
+    let hidden = map_normal(&mut wm);
+    let last = map_normal(&mut wm);
+
+    wm.process_event(state_request(&wm, hidden, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_HIDDEN, 0]));
+    wm.process_event(XEvent::UnmapNotify(UnmapEvent::new(hidden)));
+
+    for _ in 0..3 {
+        wm.cycle_focus(Direction::Forward);
+        assert_ne!(wm.conn().focused(), hidden);
+    }
+    assert!([first, last].contains(&wm.conn().focused()));
+}
+
+#[test]
+fn hidden_window_keeps_events_across_workspace_switch() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let hidden = map_normal(&mut wm);
+
+    wm.process_event(state_request(&wm, hidden, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_HIDDEN, 0]));
+    wm.goto_workspace(1);
+    wm.conn().clear_requests();
+    wm.goto_workspace(0);
+
+    let requests = wm.conn().requests();
+    let disabled = requests.iter().rposition(|req| {
+        *req == Request::ChangeWindowAttributes(hidden, utils::disable_events().to_vec())
+    });
+    let enabled = requests.iter().rposition(|req| {
+        *req == Request::ChangeWindowAttributes(hidden, utils::child_events().to_vec())
+    });
+    assert!(enabled > disabled);
+    assert!(!wm.conn().is_mapped(hidden));
+}
+
+#[test]
+fn demands_attention_marks_window_urgent() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = map_normal(&mut wm);
+    let border = |wm: &WindowManager<DummyConn>| {
+        wm.conn().window(win).unwrap().attributes.get(&consts::CW_BORDER_PIXEL).copied()
+    };
+
+    let request = state_request(&wm, win, consts::NET_WM_STATE_ADD, [atoms.WM_STATE_DEMANDS_ATTENTION, 0]);
+    wm.process_event(request);
+
+    assert_eq!(border(&wm), Some(BORDER_URGENT));
+    assert!(wm.desktop().client(win).unwrap().is_urgent());
+
+    let request = state_request(&wm, win, consts::NET_WM_STATE_REMOVE, [atoms.WM_STATE_DEMANDS_ATTENTION, 0]);
+    wm.process_event(request);
+
+    assert_ne!(border(&wm), Some(BORDER_URGENT));
+}
+
+#[test]
+fn initial_states_are_applied_on_map() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    let win = wm.conn().create_window(DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        net_states: vec![atoms.WM_STATE_FULLSCREEN],
+        ..DummyWindow::default()
+    });
+
+    wm.process_event(XEvent::MapRequest(MapRequestData::new(win, DUMMY_ROOT)));
+
+    assert_eq!(geom_of(&wm, win), ROOT_GEOM);
+    assert_eq!(wm.conn().window(win).unwrap().net_states, vec![atoms.WM_STATE_FULLSCREEN]);
+}
+
+#[test]
+fn initial_states_are_applied_on_floating_workspace() {
+    let mut wm = setup();
+    let atoms = wm.conn().get_atoms();
+    wm.set_layout(LayoutType::Floating);
+
+    let win = map_window(&mut wm, DummyWindow {
+        window_type: vec![atoms.WM_WINDOW_TYPE_NORMAL],
+        net_states: vec![atoms.WM_STATE_FULLSCREEN],
+        ..DummyWindow::default()
+    });
+
+    assert!(has_state(&wm, win, NetWindowState::Fullscreen));
+    assert_eq!(geom_of(&wm, win), ROOT_GEOM);
+    assert_eq!(wm.conn().window(win).unwrap().net_states, vec![atoms.WM_STATE_FULLSCREEN]);
+}
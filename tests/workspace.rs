@@ -0,0 +1,148 @@
+//! Workspace and layout tests, run against the in-memory DummyConn.
+
+use rcwm::x::{DummyConn, XConn};
+use rcwm::x::dummy::{DummyWindow, Request};
+use rcwm::desktop::{Desktop, Screen};
+use rcwm::layout::LayoutType;
+use rcwm::types::{Geometry, SizeHints, WMError};
+
+const ROOT_GEOM: Geometry = Geometry {x: 0, y: 0, width: 1000, height: 800};
+
+fn setup() -> (DummyConn, Screen, Desktop) {
+    let conn = DummyConn::new(ROOT_GEOM);
+    let mut screen = Screen::new(0, conn.get_root_id());
+    screen.xwindow.set_geometry_conn(&conn);
+
+    (conn, screen, Desktop::new(LayoutType::DTiled))
+}
+
+fn geom_of(conn: &DummyConn, id: u32) -> Geometry {
+    conn.window(id).expect("window should exist").geom
+}
+
+#[test]
+fn single_window_fills_screen() {
+    let (conn, screen, mut desktop) = setup();
+    let win = conn.create_normal_window();
+
+    desktop.current_mut().add_window(&conn, &screen, win).unwrap();
+
+    assert!(conn.is_mapped(win));
+    assert_eq!(conn.focused(), win);
+    assert_eq!(geom_of(&conn, win), Geometry {x: 0, y: 0, width: 996, height: 796});
+}
+
+#[test]
+fn window_smaller_than_tile_is_centered() {
+    let (conn, screen, mut desktop) = setup();
+    let win = conn.create_window(DummyWindow {
+        window_type: vec![conn.get_atoms().WM_WINDOW_TYPE_NORMAL],
+        size_hints: Some(SizeHints {
+            position: None,
+            size: None,
+            min_size: None,
+            max_size: Some((300, 200)),
+            resize: None,
+            min_aspect: None,
+            max_aspect: None,
+            base: None,
+            gravity: None,
+        }),
+        ..DummyWindow::default()
+    });
+
+    desktop.current_mut().add_window(&conn, &screen, win).unwrap();
+
+    assert_eq!(geom_of(&conn, win), Geometry {x: 348, y: 298, width: 300, height: 200});
+}
+
+#[test]
+fn second_window_splits_screen() {
+    let (conn, screen, mut desktop) = setup();
+    let master = conn.create_normal_window();
+    let slave = conn.create_normal_window();
+
+    desktop.current_mut().add_window(&conn, &screen, master).unwrap();
+    desktop.current_mut().add_window(&conn, &screen, slave).unwrap();
+
+    assert_eq!(desktop.current().master(), Some(master));
+    assert_eq!(conn.focused(), slave);
+    assert_eq!(geom_of(&conn, master), Geometry {x: 0, y: 0, width: 496, height: 796});
+    assert_eq!(geom_of(&conn, slave), Geometry {x: 500, y: 0, width: 496, height: 796});
+}
+
+#[test]
+fn send_window_to_other_workspace() {
+    let (conn, screen, mut desktop) = setup();
+    let master = conn.create_normal_window();
+    let slave = conn.create_normal_window();
+
+    desktop.current_mut().add_window(&conn, &screen, master).unwrap();
+    desktop.current_mut().add_window(&conn, &screen, slave).unwrap();
+    desktop.send_window_to(&conn, &screen, 1).unwrap();
+
+    assert!(desktop.current().contains(slave).is_none());
+    assert!(desktop.get(1).unwrap().contains(slave).is_some());
+    assert!(!conn.is_mapped(slave));
+    assert_eq!(geom_of(&conn, master), Geometry {x: 0, y: 0, width: 996, height: 796});
+
+    desktop.goto(&conn, &screen, 1).unwrap();
+
+    assert!(conn.is_mapped(slave));
+    assert!(!conn.is_mapped(master));
+}
+
+#[test]
+fn floating_window_is_not_tiled() {
+    let (conn, screen, mut desktop) = setup();
+    let master = conn.create_normal_window();
+    let slave = conn.create_normal_window();
+
+    desktop.current_mut().add_window(&conn, &screen, master).unwrap();
+    desktop.current_mut().add_window(&conn, &screen, slave).unwrap();
+    desktop.current_mut().toggle_focused_state(&conn, &screen).unwrap();
+
+    assert_eq!(desktop.current().tiled_count(), 1);
+    assert_eq!(geom_of(&conn, master), Geometry {x: 0, y: 0, width: 996, height: 796});
+    assert_eq!(conn.stacking_order().last(), Some(&slave));
+}
+
+#[test]
+fn send_window_to_invalid_workspace_fails() {
+    let (conn, screen, mut desktop) = setup();
+    let win = conn.create_normal_window();
+
+    desktop.current_mut().add_window(&conn, &screen, win).unwrap();
+
+    assert!(matches!(
+        desktop.send_window_to(&conn, &screen, 42),
+        Err(WMError::NoSuchWorkspace(42))
+    ));
+    assert!(desktop.current().contains(win).is_some());
+}
+
+#[test]
+fn workspace_switch_configures_each_window_once() {
+    let (conn, screen, mut desktop) = setup();
+    let windows: Vec<u32> = (0..4).map(|_| conn.create_normal_window()).collect();
+
+    for win in &windows {
+        desktop.current_mut().add_window(&conn, &screen, *win).unwrap();
+    }
+    desktop.goto(&conn, &screen, 1).unwrap();
+    conn.clear_requests();
+    desktop.goto(&conn, &screen, 0).unwrap();
+
+    let configures: Vec<u32> = conn.requests().into_iter()
+        .filter_map(|req| match req {
+            Request::ConfigureWindow(id, _) => Some(id),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(configures.len(), windows.len());
+    for win in &windows {
+        assert!(configures.contains(win));
+        assert!(conn.is_mapped(*win));
+    }
+}
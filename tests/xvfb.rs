@@ -0,0 +1,172 @@
+//! End to end tests, run against a headless Xvfb server.
+//!
+//! These are skipped if Xvfb is not installed.
+
+#![cfg(feature = "xcb-backend")]
+
+mod common;
+
+use common::xvfb::{Harness, SCREEN_WIDTH, SCREEN_HEIGHT};
+
+use rcwm::config::BORDER_WIDTH;
+use rcwm::types::Geometry;
+
+macro_rules! harness {
+    () => {
+        match Harness::start() {
+            Some(h) => h,
+            None => {
+                eprintln!("Xvfb not found, skipping");
+                return
+            }
+        }
+    };
+}
+
+const BORDERS: i32 = BORDER_WIDTH as i32 * 2;
+
+const FULL: Geometry = Geometry {
+    x: 0, y: 0,
+    width: SCREEN_WIDTH - BORDERS,
+    height: SCREEN_HEIGHT - BORDERS,
+};
+
+const LEFT_HALF: Geometry = Geometry {
+    x: 0, y: 0,
+    width: (SCREEN_WIDTH - BORDERS) / 2 - BORDER_WIDTH as i32,
+    height: SCREEN_HEIGHT - BORDERS,
+};
+
+const RIGHT_HALF: Geometry = Geometry {
+    x: LEFT_HALF.width + BORDERS, y: 0,
+    width: LEFT_HALF.width,
+    height: SCREEN_HEIGHT - BORDERS,
+};
+
+#[test]
+fn supported_atoms_are_set() {
+    let h = harness!();
+
+    let supported = h.get_property_u32(h.root, "_NET_SUPPORTED");
+
+    assert!(supported.contains(&h.atom("_NET_WM_STATE")));
+    assert!(supported.contains(&h.atom("WM_PROTOCOLS")));
+}
+
+#[test]
+fn single_window_is_tiled_to_screen() {
+    let h = harness!();
+
+    let win = h.window().class("term", "Term").map();
+
+    assert_eq!(h.wait_for_geometry(win, FULL), FULL);
+    assert!(h.is_viewable(win));
+    assert!(h.wait_until(|h| h.focused() == win));
+}
+
+#[test]
+fn second_window_splits_screen() {
+    let h = harness!();
+
+    let master = h.window().map();
+    h.wait_for_geometry(master, FULL);
+    let slave = h.window().map();
+
+    assert_eq!(h.wait_for_geometry(master, LEFT_HALF), LEFT_HALF);
+    assert_eq!(h.wait_for_geometry(slave, RIGHT_HALF), RIGHT_HALF);
+    assert!(h.wait_until(|h| h.focused() == slave));
+}
+
+#[test]
+fn unmapped_window_is_removed() {
+    let h = harness!();
+
+    let master = h.window().map();
+    let slave = h.window().map();
+    h.wait_for_geometry(slave, RIGHT_HALF);
+
+    h.unmap(slave);
+
+    assert_eq!(h.wait_for_geometry(master, FULL), FULL);
+    assert!(!h.is_viewable(slave));
+}
+
+#[test]
+fn destroyed_window_is_removed() {
+    let h = harness!();
+
+    let master = h.window().map();
+    let slave = h.window().map();
+    h.wait_for_geometry(slave, RIGHT_HALF);
+
+    h.destroy(master);
+
+    assert_eq!(h.wait_for_geometry(slave, FULL), FULL);
+}
+
+#[test]
+fn config_request_for_tiled_window_is_rejected() {
+    let h = harness!();
+
+    let win = h.window().map();
+    h.wait_for_geometry(win, FULL);
+
+    xcb::configure_window(&h.conn, win, &[
+        (xcb::CONFIG_WINDOW_X as u16, 10),
+        (xcb::CONFIG_WINDOW_Y as u16, 10),
+        (xcb::CONFIG_WINDOW_WIDTH as u16, 100),
+        (xcb::CONFIG_WINDOW_HEIGHT as u16, 100),
+    ]);
+    h.conn.flush();
+
+    assert!(!h.wait_until(|h| h.geometry(win) != FULL));
+}
+
+#[test]
+fn dialog_floats_above_tiled_windows() {
+    let h = harness!();
+
+    let parent = h.window().map();
+    h.wait_for_geometry(parent, FULL);
+
+    let geom = Geometry {x: 100, y: 100, width: 300, height: 200};
+    let dialog = h.window()
+        .window_type("_NET_WM_WINDOW_TYPE_DIALOG")
+        .transient_for(parent)
+        .geometry(geom)
+        .map();
+
+    assert!(h.wait_until(|h| h.is_viewable(dialog)));
+    assert_eq!(h.geometry(dialog), geom);
+    assert_eq!(h.geometry(parent), FULL);
+    assert_eq!(h.children().last(), Some(&dialog));
+}
+
+#[test]
+fn dock_is_not_tiled() {
+    let h = harness!();
+
+    let dock = h.window()
+        .window_type("_NET_WM_WINDOW_TYPE_DOCK")
+        .geometry(Geometry {x: 0, y: 0, width: SCREEN_WIDTH, height: 20})
+        .map();
+    let win = h.window().map();
+
+    assert!(h.wait_until(|h| h.is_viewable(dock)));
+    assert_eq!(h.wait_for_geometry(win, FULL), FULL);
+}
+
+#[test]
+fn override_redirect_window_is_ignored() {
+    let h = harness!();
+
+    let win = h.window().map();
+    h.wait_for_geometry(win, FULL);
+
+    let geom = Geometry {x: 50, y: 50, width: 100, height: 100};
+    let popup = h.window().override_redirect().geometry(geom).map();
+
+    assert!(h.wait_until(|h| h.is_viewable(popup)));
+    assert_eq!(h.geometry(popup), geom);
+    assert_eq!(h.geometry(win), FULL);
+}